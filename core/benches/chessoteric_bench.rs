@@ -1,5 +1,11 @@
 use chessoteric_core::{
-    bitboard::Bitboard, magic::Magic, moves::generate_moves, study::StudyEntry,
+    bitboard::Bitboard,
+    magic::Magic,
+    moves::{
+        KING_ATTACKS, KNIGHT_ATTACKS, generate_king_movement, generate_knight_movement,
+        generate_moves,
+    },
+    study::StudyEntry,
 };
 use criterion::{Criterion, criterion_group, criterion_main};
 use rand::{Rng, SeedableRng};
@@ -53,6 +59,59 @@ fn rook_bishop_raycast_bench(c: &mut Criterion) {
             std::hint::black_box(value);
         });
     });
+
+    // The iterative raycasts the magic tables replace, for comparison
+    c.bench_function("rook_legacy_raycast", |b| {
+        b.iter(|| {
+            let nextu32 = rng.next_u32();
+            let filter_count = (nextu32 % 3) as usize;
+            let square = ((nextu32 >> 2) % 64) as u8;
+            let mut occupency = Bitboard(rng.next_u64());
+            for _ in 0..filter_count {
+                occupency.0 &= rng.next_u64();
+            }
+
+            let value = Bitboard(1 << square).rook_raycast(occupency);
+            std::hint::black_box(value);
+        });
+    });
+
+    c.bench_function("bishop_legacy_raycast", |b| {
+        b.iter(|| {
+            let nextu32 = rng.next_u32();
+            let filter_count = (nextu32 % 3) as usize;
+            let square = ((nextu32 >> 2) % 64) as u8;
+            let mut occupency = Bitboard(rng.next_u64());
+            for _ in 0..filter_count {
+                occupency.0 &= rng.next_u64();
+            }
+
+            let value = Bitboard(1 << square).bishop_raycast(occupency);
+            std::hint::black_box(value);
+        });
+    });
+}
+
+fn knight_king_attacks_bench(c: &mut Criterion) {
+    c.bench_function("knight_king_attacks_shift", |b| {
+        b.iter(|| {
+            for square in 0..64u8 {
+                let origin = Bitboard(1 << std::hint::black_box(square));
+                std::hint::black_box(generate_knight_movement(origin));
+                std::hint::black_box(generate_king_movement(origin));
+            }
+        });
+    });
+
+    c.bench_function("knight_king_attacks_table", |b| {
+        b.iter(|| {
+            for square in 0..64u8 {
+                let square = std::hint::black_box(square) as usize;
+                std::hint::black_box(KNIGHT_ATTACKS[square]);
+                std::hint::black_box(KING_ATTACKS[square]);
+            }
+        });
+    });
 }
 
 fn bench_famous_move_generation(c: &mut Criterion) {
@@ -70,5 +129,6 @@ criterion_group!(
     bench_famous_move_generation,
     bench_standard_move_generation,
     rook_bishop_raycast_bench,
+    knight_king_attacks_bench,
 );
 criterion_main!(move_generation_benches);
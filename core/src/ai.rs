@@ -1,8 +1,29 @@
-use crate::{board::Board, moves::Move};
+use crate::{
+    board::{Board, Color},
+    moves::Move,
+};
 
 pub mod chessoteric;
+pub mod history;
+pub mod mate;
 pub mod random;
 pub mod simple;
+pub mod transposition;
+
+bitflags::bitflags! {
+    /// Optional features of an [`Ai`], so that front-ends only offer what it supports.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Capabilities: u8 {
+        /// Ponder searches ([`AiLimit::ponder`] followed by [`Ai::ponderhit`]).
+        const PONDER = 1 << 0;
+
+        /// Several principal variations reported at once (UCI `MultiPV` option).
+        const MULTI_PV = 1 << 1;
+
+        /// Searches without any limit, running until stopped (UCI `go infinite`).
+        const INFINITE = 1 << 2;
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct AiResult {
@@ -10,7 +31,130 @@ pub struct AiResult {
     pub pv: Vec<Move>,
     pub depth: u16,
     pub nodes: usize,
+
+    /// Score in pawns from white's point of view (positive when white is better), like
+    /// [`crate::eval::evaluate`]. This is the convention used everywhere internally, only the UCI output
+    /// is from the point of view of the side to move (see [`UciScore::from_score`]). Forced mates are scored
+    /// with [`mate_score`].
     pub score: f32,
+
+    /// Statistics of the search that produced this result, if the AI gathers them.
+    pub stats: Option<SearchStats>,
+
+    /// Deepest ply reached by the search (selective depth), at least `depth` for AIs that search.
+    pub seldepth: u16,
+
+    /// Time spent searching in milliseconds, and the resulting speed in nodes per second.
+    pub time_ms: u64,
+    pub nps: u64,
+
+    /// Occupancy of the transposition table in permille, 0 for AIs without one.
+    pub hashfull: u16,
+}
+
+/// Statistics gathered during a search, meant for tuning the search rather than for playing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchStats {
+    /// Number of nodes in the search tree (including the root).
+    pub nodes: usize,
+
+    /// Number of nodes whose moves were generated.
+    pub expanded_nodes: usize,
+
+    /// Number of times the remaining siblings of a node were skipped by alpha-beta pruning.
+    pub beta_cutoffs: usize,
+
+    /// Transposition table lookups, and how many of them found an entry.
+    pub tt_probes: usize,
+    pub tt_hits: usize,
+
+    /// Number of late moves searched at reduced depth that had to be searched again at full depth.
+    pub lmr_researches: usize,
+}
+
+impl SearchStats {
+    /// Fraction of the transposition table lookups that found an entry, 0 if there were none.
+    pub fn tt_hit_rate(&self) -> f32 {
+        if self.tt_probes == 0 {
+            0.0
+        } else {
+            self.tt_hits as f32 / self.tt_probes as f32
+        }
+    }
+
+    /// Average number of children of the expanded nodes, every node but the root being such a child.
+    pub fn average_branching_factor(&self) -> f32 {
+        if self.expanded_nodes == 0 {
+            0.0
+        } else {
+            self.nodes.saturating_sub(1) as f32 / self.expanded_nodes as f32
+        }
+    }
+}
+
+impl AiResult {
+    /// Search speed for the UCI `nps` field, 0 if no time elapsed.
+    pub fn nodes_per_second(nodes: usize, elapsed: std::time::Duration) -> u64 {
+        (nodes as u128 * 1_000_000)
+            .checked_div(elapsed.as_micros())
+            .unwrap_or(0) as u64
+    }
+
+    /// Number of plies in the principal variation.
+    pub fn pv_length(&self) -> usize {
+        self.pv.len()
+    }
+
+    /// UCI notation of the moves of the principal variation, played from `board` (the root of the search) so
+    /// that Chess960 castles are written as the king capturing its rook, see [`Move::to_uci`].
+    pub fn pv_uci(&self, board: &Board) -> Vec<String> {
+        let mut board = *board;
+        self.pv
+            .iter()
+            .map(|mv| {
+                let uci = mv.to_uci(&board);
+                mv.apply(&mut board);
+                uci
+            })
+            .collect()
+    }
+
+    /// The UCI `bestmove` line of the result, with the ponder move when the principal variation has one.
+    pub fn bestmove_line(&self, board: &Board) -> String {
+        match self.pv_uci(board).as_slice() {
+            [best, ponder, ..] => format!("bestmove {} ponder {}", best, ponder),
+            _ => format!("bestmove {}", self.best_move.to_uci(board)),
+        }
+    }
+
+    /// Hash of the moves of the principal variation (FNV-1a over origin, destination and promotion), stable
+    /// across runs and platforms. Meant to detect unintended changes of the search behavior.
+    pub fn pv_signature(&self) -> u64 {
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        for mv in &self.pv {
+            let promotion = mv.promotion.map_or(0xFF, |piece| piece as u8);
+            for byte in [mv.from, mv.to, promotion] {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+            }
+        }
+        hash
+    }
+
+    /// Whether the search proved a forced mate, for either side.
+    pub fn is_forced_mate(&self) -> bool {
+        mate_plies(self.score).is_some()
+    }
+
+    /// Distance to the forced mate in moves (not plies), with the same sign as `score`. Returns `None` if no
+    /// mate was found.
+    pub fn mate_in(&self) -> Option<i32> {
+        // Reported from white's point of view, as the score is
+        match UciScore::from_score(self.score, Color::White) {
+            UciScore::Mate(moves) => Some(moves),
+            UciScore::Centipawns(_) => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -19,28 +163,284 @@ pub enum AiType {
     Sync,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct AiLimit {
+    #[cfg_attr(feature = "serde", serde(rename = "movetime_ms", with = "duration_ms"))]
     pub movetime: Option<std::time::Duration>,
+
+    /// Stop once every line has been searched this many plies deep (UCI `go depth`).
     pub depth: Option<u16>,
+
+    /// Stop once the search tree holds this many nodes (UCI `go nodes`), checked periodically so the search may
+    /// slightly overshoot it.
+    pub nodes: Option<u64>,
+
+    /// Stop as soon as a forced mate for the side to move within this many plies is found. UCI `go mate` gives
+    /// a number of moves, a mate in `n` moves being `2n - 1` plies away.
+    pub mate: Option<u16>,
+
+    /// Ponder search (UCI `go ponder`): the limits only apply from the ponderhit on, see [`Ai::ponderhit`].
+    pub ponder: bool,
+}
+
+/// Serialize optional durations as a number of milliseconds, which is what UCI and most configs use.
+#[cfg(feature = "serde")]
+mod duration_ms {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
+
+/// Convert an internal score (in pawns, as returned by [`crate::eval::evaluate`]) to the integer centipawns
+/// expected by UCI. This is the single conversion point, mate scores are handled by [`UciScore`].
+pub fn to_centipawns(score: f32) -> i32 {
+    (score * 100.0).round() as i32
+}
+
+/// A score as reported in UCI `info` lines, either in centipawns or as a forced mate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciScore {
+    Centipawns(i32),
+
+    /// Mate in the given number of moves (not plies), negative if the side to move is getting mated.
+    Mate(i32),
+}
+
+impl UciScore {
+    /// Build the UCI score from an internal score, mate scores (see [`mate_score`]) being converted to a
+    /// number of moves.
+    ///
+    /// Internal scores are from white's point of view while UCI scores are from the point of view of the
+    /// side to move, hence the score is negated when black is to move.
+    pub fn from_score(score: f32, side_to_move: Color) -> Self {
+        let score = score * side_to_move.score_multiplier();
+        match mate_plies(score) {
+            Some(plies) => {
+                let moves = plies.unsigned_abs().div_ceil(2) as i32;
+                UciScore::Mate(if plies > 0 { moves } else { -moves })
+            }
+            None => UciScore::Centipawns(to_centipawns(score)),
+        }
+    }
+}
+
+/// Score of a checkmate in pawns, far above any evaluation. A mate found `plies` away from the root of the
+/// search is scored `MATE_SCORE - plies` for the winner: shorter mates are preferred, and the distance is
+/// kept as the score propagates toward the root.
+pub const MATE_SCORE: f32 = 100_000.0;
+
+/// Scores beyond this are mates, no search goes that deep.
+const MATE_THRESHOLD: f32 = MATE_SCORE - 1_000.0;
+
+/// Score (white-positive) of a position where `loser` is checkmated, `plies` away from the root.
+pub fn mate_score(loser: Color, plies: u16) -> f32 {
+    -(MATE_SCORE - plies as f32) * loser.score_multiplier()
+}
+
+/// Distance in plies from the root to the checkmate if the score is a mate score, with the same sign as
+/// the score.
+pub fn mate_plies(score: f32) -> Option<i32> {
+    if score.abs() < MATE_THRESHOLD {
+        return None;
+    }
+    let plies = (MATE_SCORE - score.abs()) as i32;
+    Some(if score > 0.0 { plies } else { -plies })
+}
+
+impl std::fmt::Display for UciScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciScore::Centipawns(cp) => write!(f, "cp {}", cp),
+            UciScore::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
 }
 
 pub trait Ai {
     fn name(&self) -> &str;
     fn authors(&self) -> &[&str];
 
+    /// The position of the game the next searches belong to, given as its starting position and the moves
+    /// played from it, so that the searches of its current position can account for the positions the game
+    /// went through (e.g. to score a return to one of them as a repetition). This does not discard what the AI
+    /// kept from its previous searches (e.g. its transposition table), which only `reset` does. AIs without
+    /// such a state ignore it.
+    fn set_position(&self, _board: &Board, _history: &[Move]) {}
+
+    /// Launch a search of the given position. With `print`, the search reports its progress with UCI `info`
+    /// lines, and its `bestmove` when it ends on its own (i.e. once its limits are reached).
     fn start(&self, board: &Board, limits: AiLimit, print: bool) -> AiType;
+
+    /// Interrupt the search and return its result. When the search was started with `print` and already
+    /// reported its `bestmove`, `None` is returned instead, so that the caller reports the best move of an
+    /// interrupted search exactly once. An interrupted search without any result prints `bestmove (none)`
+    /// itself.
     fn stop(&self) -> Option<AiResult>;
     fn reset(&self);
     fn is_ready(&self) -> bool {
         true
     }
+
+    /// Ponder on the position reached once the opponent plays `expected_move` from `board`, during the
+    /// opponent's time: the search ignores its limits until [`Ai::ponderhit`], and is stopped if the opponent
+    /// plays another move. AIs that cannot ponder start a regular search of that position.
+    fn ponder(&self, board: &Board, expected_move: Move, limits: AiLimit, print: bool) -> AiType {
+        let mut board = *board;
+        expected_move.apply(&mut board);
+        self.start(&board, limits, print)
+    }
+
+    /// The opponent played the expected move during a ponder search: the search goes on with what it
+    /// already explored, but is now bound by its limits (time being counted from now on). AIs that cannot
+    /// ponder ignore it.
+    fn ponderhit(&self) {}
+
+    /// The optional features supported by the AI, none by default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::empty()
+    }
 }
 
 pub fn get_ai(name: &str) -> Option<Box<dyn Ai>> {
     match name {
         "simple" => Some(Box::new(simple::SimpleAi::default())),
         "random" => Some(Box::new(random::RandomAi::default())),
+        "chessoteric" => Some(Box::new(chessoteric::ChessotericAi::default())),
         _ => None,
     }
 }
+
+/// The AI `name` reporting its `multipv` best lines, see [`simple::SimpleAi::with_multipv`]. The AIs without
+/// [`Capabilities::MULTI_PV`] only report their principal variation.
+pub fn get_ai_with_multipv(name: &str, multipv: usize) -> Option<Box<dyn Ai>> {
+    match name {
+        "simple" => Some(Box::new(simple::SimpleAi::default().with_multipv(multipv))),
+        _ => get_ai(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uci_score_in_centipawns() {
+        assert_eq!(to_centipawns(1.5), 150);
+        assert_eq!(to_centipawns(-0.334), -33);
+        assert_eq!(
+            format!("score {}", UciScore::from_score(1.5, Color::White)),
+            "score cp 150"
+        );
+
+        // Mates are kept apart from the centipawn scale
+        assert_eq!(
+            UciScore::from_score(mate_score(Color::Black, 3), Color::White),
+            UciScore::Mate(2)
+        );
+        assert_eq!(
+            format!(
+                "score {}",
+                UciScore::from_score(mate_score(Color::White, 2), Color::White)
+            ),
+            "score mate -1"
+        );
+    }
+
+    #[test]
+    fn test_uci_score_from_side_to_move() {
+        // Internal scores are white-positive, a position good for black is positive for black to move
+        assert_eq!(
+            UciScore::from_score(-2.0, Color::Black),
+            UciScore::Centipawns(200)
+        );
+        assert_eq!(
+            UciScore::from_score(-2.0, Color::White),
+            UciScore::Centipawns(-200)
+        );
+        assert_eq!(
+            UciScore::from_score(mate_score(Color::White, 3), Color::Black),
+            UciScore::Mate(2)
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let random = get_ai("random").unwrap().capabilities();
+        let simple = get_ai("simple").unwrap().capabilities();
+        assert_ne!(random, simple);
+        assert!(!random.intersects(Capabilities::PONDER | Capabilities::MULTI_PV));
+        assert!(
+            simple.contains(Capabilities::PONDER | Capabilities::MULTI_PV | Capabilities::INFINITE)
+        );
+    }
+
+    #[test]
+    fn test_default_ponder_searches_after_the_expected_move() {
+        // The random AI cannot ponder, it plays right away in the position after the expected move
+        let ai = get_ai("random").unwrap();
+        let board = Board::default_position();
+        let expected_move = Move::from_uci("e2e4", &board).unwrap();
+        ai.ponder(&board, expected_move, AiLimit::default(), false);
+        ai.ponderhit();
+        let result = ai.stop().unwrap();
+
+        let mut position = board;
+        expected_move.apply(&mut position);
+        let mut moves = Vec::new();
+        crate::moves::generate_moves(&position, &mut moves, &mut false);
+        assert!(moves.contains(&result.best_move));
+    }
+
+    #[test]
+    fn test_mate_scores() {
+        // Shorter mates are better for the winner, and longer ones better for the loser
+        assert!(mate_score(Color::Black, 1) > mate_score(Color::Black, 3));
+        assert!(mate_score(Color::White, 2) < mate_score(Color::White, 4));
+        assert!(mate_score(Color::Black, 63) > 1_000.0);
+
+        assert_eq!(mate_plies(mate_score(Color::Black, 5)), Some(5));
+        assert_eq!(mate_plies(mate_score(Color::White, 4)), Some(-4));
+        assert_eq!(mate_plies(12.5), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_ai_limit_deserialize() {
+        let limits: AiLimit = serde_json::from_str(r#"{"movetime_ms":1000,"depth":6}"#).unwrap();
+        assert_eq!(
+            limits,
+            AiLimit {
+                movetime: Some(std::time::Duration::from_millis(1000)),
+                depth: Some(6),
+                nodes: None,
+                mate: None,
+                ponder: false,
+            }
+        );
+
+        // Missing fields fall back to no limit, and serialization round-trips
+        let limits: AiLimit = serde_json::from_str(r#"{"depth":3,"nodes":5000}"#).unwrap();
+        assert_eq!(limits.movetime, None);
+        assert_eq!(limits.nodes, Some(5000));
+        let json = serde_json::to_string(&limits).unwrap();
+        assert_eq!(serde_json::from_str::<AiLimit>(&json).unwrap(), limits);
+    }
+}
@@ -3,5 +3,605 @@
 //   previous iterations, or if so they will occur at the end of the PV.
 // - Keep track of the "promising" moves outside of the PV, regenerate legal moves on the
 //   fly. Avoid storing all the moves.
+//
+// Unlike `SimpleAi`, no tree is kept: this is a depth-first alpha-beta search (negamax) that makes and
+// unmakes moves on a single board. What survives from one iteration to the next (and from one search to
+// the next of a game) is the best move found in every position, kept in a hash table, alongside two
+// killer moves per ply (quiet moves that caused a cutoff). They are searched first, which keeps the
+// principal variation of the previous iteration in front.
 
-pub fn search() {}
+use std::{
+    cell::{Cell, RefCell},
+    sync::{Arc, atomic::AtomicBool},
+    time::Instant,
+};
+
+use crate::{
+    ai::{
+        Ai, AiLimit, AiResult, AiType, Capabilities, MATE_SCORE, SearchStats, UciScore, mate_plies,
+    },
+    board::Board,
+    eval::evaluate_stm,
+    moves::{Move, generate_captures, generate_moves},
+};
+
+/// Maximum depth (in plies) of the iterative deepening.
+pub const MAX_PLY: usize = 64;
+
+/// Number of nodes searched between two checks of the stop signal and the clock.
+const CHECK_INTERVAL: usize = 1024;
+
+/// Best move found in a position, indexed by zobrist key (always replaced on collision).
+struct BestMoveTable {
+    entries: Vec<Option<(u64, Move)>>,
+}
+
+impl BestMoveTable {
+    const CAPACITY: usize = 1 << 16;
+
+    fn new() -> Self {
+        BestMoveTable {
+            entries: vec![None; Self::CAPACITY],
+        }
+    }
+
+    fn probe(&self, key: u64) -> Option<Move> {
+        match self.entries[(key % Self::CAPACITY as u64) as usize] {
+            Some((entry_key, mv)) if entry_key == key => Some(mv),
+            _ => None,
+        }
+    }
+
+    fn store(&mut self, key: u64, mv: Move) {
+        self.entries[(key % Self::CAPACITY as u64) as usize] = Some((key, mv));
+    }
+
+    /// Occupancy in permille, estimated on the first thousand slots.
+    fn hashfull(&self) -> u16 {
+        self.entries[..1000]
+            .iter()
+            .filter(|entry| entry.is_some())
+            .count() as u16
+    }
+}
+
+struct ChessotericCtx {
+    board: Board,
+    best_moves: BestMoveTable,
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    pv: Vec<Vec<Move>>, // Triangular principal variation table, pv[ply] is the line from that ply on
+    stats: SearchStats,
+    result: Option<AiResult>,
+    limits: AiLimit,
+    start_time: Instant,
+    stop_signal: Arc<AtomicBool>,
+    stopped: bool,
+    next_check: usize, // Node count from which the stop signal and the limits are checked again
+    seldepth: u16,     // Deepest ply reached, quiescence search included
+}
+
+impl ChessotericCtx {
+    fn new(board: &Board, best_moves: BestMoveTable, stop_signal: Arc<AtomicBool>) -> Self {
+        ChessotericCtx {
+            board: *board,
+            best_moves,
+            killers: [[None; 2]; MAX_PLY],
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            stats: SearchStats::default(),
+            result: None,
+            limits: AiLimit::default(),
+            start_time: Instant::now(),
+            stop_signal,
+            stopped: false,
+            next_check: 0,
+            seldepth: 0,
+        }
+    }
+
+    /// Whether the search must be interrupted, only polled every [`CHECK_INTERVAL`] nodes (and at the node
+    /// limit). Never before the first iteration is completed, a search always has a move to report.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.result.is_some() && self.stats.nodes >= self.next_check {
+            self.next_check = self.stats.nodes + CHECK_INTERVAL;
+            if let Some(node_limit) = self.limits.nodes {
+                self.next_check = self.next_check.min(node_limit as usize);
+            }
+            self.stopped = self.stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+                || self
+                    .limits
+                    .movetime
+                    .is_some_and(|movetime| self.start_time.elapsed() >= movetime)
+                || self
+                    .limits
+                    .nodes
+                    .is_some_and(|node_limit| self.stats.nodes as u64 >= node_limit);
+        }
+        self.stopped
+    }
+
+    /// Static evaluation from the point of view of the side to move.
+    fn evaluate(&self) -> f32 {
+        evaluate_stm(&self.board)
+    }
+
+    /// Sort the moves so that the most promising ones come first: the best move previously found in this
+    /// position, then the captures (MVV-LVA), then the killer moves of the ply, then the other quiet moves.
+    fn order_moves(&mut self, moves: &mut [Move], ply: usize) {
+        self.stats.tt_probes += 1;
+        let best_move = self.best_moves.probe(self.board.zobrist);
+        if best_move.is_some() {
+            self.stats.tt_hits += 1;
+        }
+
+        let killers = self.killers[ply];
+        let board = self.board;
+        moves.sort_by_cached_key(|mv| {
+            let key = if Some(*mv) == best_move {
+                1_000_000
+            } else {
+                match mv.mvv_lva_score(&board) {
+                    0 if killers.contains(&Some(*mv)) => 100,
+                    0 => 0,
+                    capture => 1_000 + capture,
+                }
+            };
+            std::cmp::Reverse(key)
+        });
+    }
+
+    /// Only the captures are searched until the position is quiet, the side to move being allowed to
+    /// stand on the static evaluation instead of capturing. Returns `None` if the search was interrupted, as
+    /// [`ChessotericCtx::negamax`] does.
+    fn quiescence(&mut self, ply: usize, mut alpha: f32, beta: f32) -> Option<f32> {
+        if self.should_stop() {
+            return None;
+        }
+        self.stats.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u16);
+
+        let stand_pat = self.evaluate();
+        if stand_pat >= beta {
+            return Some(stand_pat);
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = Vec::new();
+        let mut currently_in_check = false;
+        generate_captures(&self.board, &mut captures, &mut currently_in_check);
+        self.stats.expanded_nodes += 1;
+        let board = self.board;
+        captures.sort_by_cached_key(|mv| std::cmp::Reverse(mv.mvv_lva_score(&board)));
+
+        let mut best = stand_pat;
+        for mv in captures {
+            let undo = mv.make(&mut self.board);
+            let score = self.quiescence(ply + 1, -beta, -alpha);
+            mv.unmake(&mut self.board, undo);
+            let score = -score?;
+
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        Some(best)
+    }
+
+    /// Negamax alpha-beta search, returns the score from the point of view of the side to move, or `None`
+    /// if the search was interrupted (the score is then meaningless).
+    fn negamax(&mut self, depth: u16, ply: usize, mut alpha: f32, beta: f32) -> Option<f32> {
+        if self.should_stop() {
+            return None;
+        }
+        self.pv[ply].clear();
+
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&self.board, &mut moves, &mut currently_in_check);
+
+        if moves.is_empty() {
+            self.stats.nodes += 1;
+            // Scored from the point of view of the side to move, mated `ply` plies away from the root
+            return Some(if currently_in_check {
+                -MATE_SCORE + ply as f32
+            } else {
+                0.0
+            });
+        }
+
+        // The root is never scored as a draw since we still have to pick a move there
+        if ply > 0 && self.board.is_fifty_move_draw() {
+            self.stats.nodes += 1;
+            return Some(0.0);
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        self.stats.nodes += 1;
+        self.stats.expanded_nodes += 1;
+        self.order_moves(&mut moves, ply);
+
+        let mut best = f32::NEG_INFINITY;
+        let mut best_move = moves[0];
+        for (index, mv) in moves.into_iter().enumerate() {
+            let undo = mv.make(&mut self.board);
+            let score = self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            mv.unmake(&mut self.board, undo);
+            let score = -score?;
+
+            // The first move always makes it into the PV, whatever its score
+            if score > best || index == 0 {
+                best = score;
+                best_move = mv;
+
+                let (line, rest) = self.pv.split_at_mut(ply + 1);
+                line[ply].clear();
+                line[ply].push(mv);
+                line[ply].extend_from_slice(&rest[0]);
+            }
+
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if mv.mvv_lva_score(&self.board) == 0 && self.killers[ply][0] != Some(mv) {
+                    self.killers[ply] = [Some(mv), self.killers[ply][0]];
+                }
+                break;
+            }
+        }
+
+        self.best_moves.store(self.board.zobrist, best_move);
+        Some(best)
+    }
+
+    /// Run the iterative deepening until a limit is reached, returns the last depth completed.
+    fn run(&mut self, limits: AiLimit, print: bool) -> u16 {
+        self.limits = limits;
+        self.start_time = Instant::now();
+        let max_depth = self
+            .limits
+            .depth
+            .map_or(MAX_PLY as u16, |depth| depth.min(MAX_PLY as u16));
+        let side_to_move = self.board.next_to_move();
+
+        let mut completed = 0;
+        for depth in 1..=max_depth {
+            let Some(score) = self.negamax(depth, 0, f32::NEG_INFINITY, f32::INFINITY) else {
+                break;
+            };
+            if self.pv[0].is_empty() {
+                break; // No legal move at the root
+            }
+
+            completed = depth;
+            let pv = self.pv[0].clone();
+            let elapsed = self.start_time.elapsed();
+            self.result = Some(AiResult {
+                best_move: pv[0],
+                depth,
+                seldepth: self.seldepth.max(depth),
+                nodes: self.stats.nodes,
+                score: score * side_to_move.score_multiplier(),
+                stats: Some(self.stats.clone()),
+                pv,
+                time_ms: elapsed.as_millis() as u64,
+                nps: AiResult::nodes_per_second(self.stats.nodes, elapsed),
+                hashfull: self.best_moves.hashfull(),
+            });
+
+            if print && let Some(result) = &self.result {
+                println!(
+                    "info depth {} seldepth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+                    depth,
+                    result.seldepth,
+                    UciScore::from_score(result.score, side_to_move),
+                    result.nodes,
+                    result.nps,
+                    result.hashfull,
+                    result.time_ms,
+                    result.pv_uci(&self.board).join(" ")
+                );
+            }
+
+            // A mate short enough for the mate limit ends the search, as does any forced mate: it will not get
+            // any better by searching deeper
+            if let Some(mate_limit) = self.limits.mate
+                && mate_plies(score).is_some_and(|plies| plies > 0 && plies <= mate_limit as i32)
+            {
+                break;
+            }
+            if mate_plies(score).is_some() {
+                break;
+            }
+        }
+
+        // Raising the stop signal tells whether the search was interrupted, in which case reporting its best move
+        // is left to the caller of stop
+        if print
+            && !self
+                .stop_signal
+                .swap(true, std::sync::atomic::Ordering::SeqCst)
+        {
+            match &self.result {
+                Some(result) => println!("{}", result.bestmove_line(&self.board)),
+                None => println!("bestmove (none)"),
+            }
+        }
+
+        completed
+    }
+}
+
+/// Run a search on the current thread until a limit is reached (without printing anything), returns `None`
+/// if there is no legal move. Without any limit, the search only stops at [`MAX_PLY`] or on a forced mate.
+pub fn search(board: &Board, limits: AiLimit) -> Option<AiResult> {
+    let mut ctx = ChessotericCtx::new(
+        board,
+        BestMoveTable::new(),
+        Arc::new(AtomicBool::new(false)),
+    );
+    ctx.run(limits, false);
+    ctx.result
+}
+
+pub struct ChessotericAi {
+    ctx: RefCell<Option<ChessotericCtx>>,
+    stop_signal: Arc<AtomicBool>,
+    thread: RefCell<Option<std::thread::JoinHandle<ChessotericCtx>>>,
+    print: Cell<bool>, // Whether the last search reports to the standard output
+}
+
+impl std::default::Default for ChessotericAi {
+    fn default() -> Self {
+        ChessotericAi {
+            ctx: RefCell::new(None),
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            thread: RefCell::new(None),
+            print: Cell::new(false),
+        }
+    }
+}
+
+impl ChessotericAi {
+    /// Stop the search in flight (if any) and return its context.
+    fn join(&self) -> Option<ChessotericCtx> {
+        self.stop_signal
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let thread = self.thread.borrow_mut().take();
+        thread
+            .map(|thread| thread.join().unwrap())
+            .or_else(|| self.ctx.borrow_mut().take())
+    }
+}
+
+impl Ai for ChessotericAi {
+    fn name(&self) -> &str {
+        "chessoteric-ab"
+    }
+
+    fn authors(&self) -> &[&str] {
+        &["Guillaume Boyé"]
+    }
+
+    fn start(&self, board: &Board, limits: AiLimit, print: bool) -> AiType {
+        // The best moves of the previous search are kept, the killers are tied to the plies of a search
+        let best_moves = self
+            .join()
+            .map(|ctx| ctx.best_moves)
+            .unwrap_or_else(BestMoveTable::new);
+
+        self.print.set(print);
+        self.stop_signal
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        let mut ctx = ChessotericCtx::new(board, best_moves, self.stop_signal.clone());
+        let thread_handle = std::thread::Builder::new()
+            .name("ChessotericAiThread".to_string())
+            .spawn(move || {
+                ctx.run(limits, print);
+                ctx
+            })
+            .expect("Failed to spawn AI thread");
+
+        self.thread.borrow_mut().replace(thread_handle);
+        AiType::Async
+    }
+
+    fn stop(&self) -> Option<AiResult> {
+        let reported = self
+            .stop_signal
+            .swap(true, std::sync::atomic::Ordering::SeqCst);
+        let ctx = self.join()?;
+        let result = ctx.result.clone();
+        self.ctx.borrow_mut().replace(ctx);
+        if reported && self.print.get() {
+            return None;
+        }
+        if result.is_none() && self.print.get() {
+            println!("bestmove (none)");
+        }
+        result
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    fn reset(&self) {
+        self.join();
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::INFINITE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::random::RandomAi, board::Color, eval::simple_evaluation, outcome::GameOutcome,
+    };
+
+    fn search_to_depth(board: &Board, depth: u16) -> AiResult {
+        let limits = AiLimit {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        search(board, limits).unwrap()
+    }
+
+    fn is_legal(board: &Board, mv: Move) -> bool {
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(board, &mut moves, &mut currently_in_check);
+        moves.contains(&mv)
+    }
+
+    #[test]
+    fn test_legal_best_moves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let result = search_to_depth(&board, 3);
+            assert_eq!(result.depth, 3);
+            assert!(is_legal(&board, result.best_move), "\"{}\"", fen);
+
+            // The principal variation is a legal line
+            let mut board = board;
+            for mv in &result.pv {
+                assert!(is_legal(&board, *mv), "{} in \"{}\"", mv, board.fen());
+                mv.apply(&mut board);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finds_mate() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        let result = search_to_depth(&board, 4);
+        assert_eq!(result.best_move.uci().to_string(), "d1d8");
+        assert_eq!(result.mate_in(), Some(1));
+
+        let board = Board::from_fen("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").unwrap();
+        let result = search_to_depth(&board, 4);
+        assert_eq!(result.mate_in(), Some(2));
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let board = Board::default_position();
+        let mut ctx = ChessotericCtx::new(
+            &board,
+            BestMoveTable::new(),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let limits = AiLimit {
+            nodes: Some(1_000),
+            ..Default::default()
+        };
+        ctx.run(limits, false);
+
+        // The first iteration is always completed, the limit is checked right when it is reached
+        assert_eq!(ctx.stats.nodes, 1_000);
+        assert!(ctx.result.is_some());
+
+        let mut ctx = ChessotericCtx::new(
+            &board,
+            BestMoveTable::new(),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let limits = AiLimit {
+            nodes: Some(1),
+            ..Default::default()
+        };
+        ctx.run(limits, false);
+        assert_eq!(ctx.result.unwrap().depth, 1);
+    }
+
+    #[test]
+    fn test_mate_limit() {
+        // Rd8+ Rxd8 Rxd8#, mate in 3 plies
+        let board = Board::from_fen("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").unwrap();
+        let result = search(
+            &board,
+            AiLimit {
+                mate: Some(3),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.best_move.uci().to_string(), "d2d8");
+    }
+
+    #[test]
+    fn test_no_move_when_mated() {
+        let board = Board::from_fen("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let mut ctx = ChessotericCtx::new(
+            &board,
+            BestMoveTable::new(),
+            Arc::new(AtomicBool::new(false)),
+        );
+        ctx.run(AiLimit::default(), false);
+        assert!(ctx.result.is_none());
+    }
+
+    /// Wait for a depth limited search to complete before collecting its result.
+    fn search_with(ai: &ChessotericAi, board: &Board, limits: AiLimit) -> AiResult {
+        ai.start(board, limits, false);
+        while ai
+            .thread
+            .borrow()
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+        {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        ai.stop().unwrap()
+    }
+
+    #[test]
+    fn test_beats_random_ai() {
+        let limits = AiLimit {
+            depth: Some(2),
+            ..Default::default()
+        };
+
+        for color in [Color::White, Color::Black] {
+            let chessoteric = ChessotericAi::default();
+            let random = RandomAi::default();
+            let mut board = Board::default_position();
+
+            for _ in 0..80 {
+                if board.outcome().is_some() {
+                    break;
+                }
+                let result = if board.next_to_move() == color {
+                    search_with(&chessoteric, &board, limits.clone())
+                } else {
+                    random.start(&board, limits.clone(), false);
+                    random.stop().unwrap()
+                };
+                assert!(is_legal(&board, result.best_move));
+                result.best_move.apply(&mut board);
+            }
+
+            // Either the random player got mated, or it is far behind in material
+            let won = board.outcome() == Some(GameOutcome::Checkmate { winner: color });
+            let material = simple_evaluation(&board) * color.score_multiplier();
+            assert!(
+                won || material >= 5.0,
+                "Playing {:?}, ended in \"{}\"",
+                color,
+                board.fen()
+            );
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::moves::Move;
+
+/// Butterfly history table, indexed by the origin and destination squares of a move. Quiet moves causing a
+/// cutoff are credited with the square of the remaining depth, so that the moves refuting deep subtrees
+/// outweigh the ones refuting the leaves. The scores are only meaningful relative to each other, they are used
+/// to order the quiet moves.
+///
+/// Like the transposition table, the history is meant to outlive a single search and is reset between games.
+pub struct HistoryTable {
+    scores: Box<[[u32; 64]; 64]>,
+}
+
+impl HistoryTable {
+    pub fn new() -> Self {
+        HistoryTable {
+            scores: Box::new([[0; 64]; 64]),
+        }
+    }
+
+    /// Credit a quiet move that caused a cutoff with `depth` plies left to search.
+    pub fn add_cutoff(&mut self, mv: Move, depth: u16) {
+        let score = &mut self.scores[mv.from as usize][mv.to as usize];
+        *score = score.saturating_add(depth as u32 * depth as u32);
+    }
+
+    pub fn score(&self, mv: Move) -> u32 {
+        self.scores[mv.from as usize][mv.to as usize]
+    }
+
+    /// Whether no cutoff was recorded since the creation (or last reset) of the table.
+    pub fn is_empty(&self) -> bool {
+        self.scores.iter().flatten().all(|&score| score == 0)
+    }
+
+    pub fn reset(&mut self) {
+        self.scores.fill([0; 64]);
+    }
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        HistoryTable::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn test_cutoffs_accumulate() {
+        let board = Board::default_position();
+        let mut table = HistoryTable::new();
+        assert!(table.is_empty());
+
+        let mv = Move::from_uci("e2e4", &board).unwrap();
+        table.add_cutoff(mv, 3);
+        table.add_cutoff(mv, 2);
+        assert_eq!(table.score(mv), 13);
+        assert_eq!(table.score(Move::from_uci("e2e3", &board).unwrap()), 0);
+
+        table.reset();
+        assert!(table.is_empty());
+        assert_eq!(table.score(mv), 0);
+    }
+}
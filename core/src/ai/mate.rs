@@ -0,0 +1,99 @@
+use crate::{
+    board::Board,
+    moves::{Move, generate_moves},
+};
+
+/// Find a forced mate for the side to move in at most `max_moves` moves (of the side to move), for puzzle
+/// solving. Unlike the general search, positions are never evaluated: a line is either a forced mate or it
+/// is discarded, which allows pruning much more aggressively.
+///
+/// The shortest mate is returned as the full mating sequence, attacker and defender moves alternating and
+/// ending with the mating move. The defender always picks the reply resisting the longest, so the line is
+/// the one of a best defense. Returns `None` if there is no forced mate within `max_moves`.
+pub fn find_mate(board: &Board, max_moves: u16) -> Option<Vec<Move>> {
+    let mut board = *board;
+    (1..=max_moves).find_map(|moves_left| attacker_mates(&mut board, moves_left))
+}
+
+/// Attacker to move, looking for a move that forces mate within `moves_left` moves.
+fn attacker_mates(board: &mut Board, moves_left: u16) -> Option<Vec<Move>> {
+    let mut moves = Vec::new();
+    let mut currently_in_check = false;
+    generate_moves(board, &mut moves, &mut currently_in_check);
+
+    for mv in moves {
+        let undo = mv.make(board);
+        let line = defender_is_mated(board, moves_left);
+        mv.unmake(board, undo);
+
+        if let Some(mut line) = line {
+            line.insert(0, mv);
+            return Some(line);
+        }
+    }
+
+    None
+}
+
+/// Defender to move after an attacker move with `moves_left` moves including it, returns the longest
+/// line of defense if every reply still leads to mate.
+fn defender_is_mated(board: &mut Board, moves_left: u16) -> Option<Vec<Move>> {
+    let mut replies = Vec::new();
+    let mut currently_in_check = false;
+    generate_moves(board, &mut replies, &mut currently_in_check);
+
+    if replies.is_empty() {
+        // Checkmate, or a stalemate which is a failure for the attacker
+        return currently_in_check.then(Vec::new);
+    }
+    if moves_left == 1 {
+        // The attacker move was its last one and did not mate
+        return None;
+    }
+
+    let mut longest: Option<Vec<Move>> = None;
+    for reply in replies {
+        let undo = reply.make(board);
+        let line = (1..moves_left).find_map(|remaining| attacker_mates(board, remaining));
+        reply.unmake(board, undo);
+
+        let mut line = line?; // This reply escapes the mate
+        line.insert(0, reply);
+        if longest
+            .as_ref()
+            .is_none_or(|longest| line.len() > longest.len())
+        {
+            longest = Some(line);
+        }
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_mate_in_one() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        let line = find_mate(&board, 1).unwrap();
+        assert_eq!(line.len(), 1);
+        assert_eq!(line[0].uci().to_string(), "d1d8");
+    }
+
+    #[test]
+    fn test_find_mate_in_three() {
+        // 1. Nf7+ Rxf7 2. Qxd8+ Rf8 3. Qxf8#, with no mate in two
+        let board =
+            Board::from_fen("3r1r1k/1p3p1p/p2p4/4n1NN/6bQ/1BPq4/P3p1PP/1R5K w - - 0 1").unwrap();
+        assert!(find_mate(&board, 2).is_none());
+
+        let line = find_mate(&board, 3).unwrap();
+        let line = line
+            .iter()
+            .map(|mv| mv.uci().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(line, ["g5f7", "f8f7", "h4d8", "f7f8", "d8f8"]);
+    }
+}
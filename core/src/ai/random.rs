@@ -1,4 +1,7 @@
-use std::sync::Mutex;
+use std::sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+};
 
 use rand::prelude::*;
 
@@ -13,6 +16,7 @@ use super::AiResult;
 pub struct RandomAi {
     rng: Mutex<ThreadRng>,
     best_move: Mutex<Option<Move>>,
+    reported: AtomicBool, // Whether the best move was printed by start
 }
 
 impl std::default::Default for RandomAi {
@@ -20,6 +24,7 @@ impl std::default::Default for RandomAi {
         RandomAi {
             rng: Mutex::new(ThreadRng::default()),
             best_move: Mutex::new(None),
+            reported: AtomicBool::new(false),
         }
     }
 }
@@ -46,25 +51,38 @@ impl Ai for RandomAi {
             *self.best_move.lock().unwrap() = Some(moves[random_index]);
         }
 
-        if print && let Some(mv) = *self.best_move.lock().unwrap() {
-            println!("bestmove {}", mv.uci());
+        if print {
+            match *self.best_move.lock().unwrap() {
+                Some(mv) => println!("bestmove {}", mv.to_uci(board)),
+                None => println!("bestmove (none)"),
+            }
         }
+        self.reported.store(print, Ordering::SeqCst);
 
         AiType::Sync
     }
 
     fn stop(&self) -> Option<AiResult> {
         // For a random AI, we can return the best move immediately since there is no ongoing search
+        if self.reported.load(Ordering::SeqCst) {
+            return None;
+        }
         self.best_move.lock().unwrap().map(|mv| AiResult {
             best_move: mv,
             pv: vec![mv],
             depth: 1,
             nodes: 1,
             score: 0.0,
+            stats: None,
+            seldepth: 1,
+            time_ms: 0,
+            nps: 0,
+            hashfull: 0,
         })
     }
 
     fn reset(&self) {
         *self.best_move.lock().unwrap() = None;
+        self.reported.store(false, Ordering::SeqCst);
     }
 }
@@ -1,28 +1,51 @@
 use std::{
-    cell::RefCell,
-    sync::{Arc, atomic::AtomicBool},
+    cell::{Cell, RefCell},
+    sync::{Arc, Mutex, atomic::AtomicBool},
 };
 
 use bitflags::bitflags;
 use strum::{EnumIs, EnumTryAs};
 
 use crate::{
-    ai::{Ai, AiLimit, AiResult},
+    ai::{
+        Ai, AiLimit, AiResult, Capabilities, SearchStats, UciScore, history::HistoryTable,
+        mate_plies, mate_score, transposition::TranspositionTable,
+    },
     board::{Board, Color},
-    eval::evaluate,
+    eval::{Evaluator, evaluate},
     moves::{Move, generate_moves},
-    tree::{Tree, TreeNodeRef},
+    tree::{Tree, TreeNodeRef, TreeRef},
 };
 
 use super::AiType;
 
+/// Maximum depth (in plies) reached by the iterative deepening. Once every line of the tree has been
+/// explored up to this depth, the search stops deepening instead of spinning forever (e.g. under
+/// `go infinite` in a position where all lines are terminal).
+pub const MAX_PLY: u16 = 64;
+
+/// Number of nodes added to the tree between two checks of the stop signal and of the time and node limits.
+const LIMIT_CHECK_INTERVAL: usize = 2048;
+
+/// Late move reductions: the children of a node after the first [`LMR_FULL_DEPTH_MOVES`] are expanded two plies
+/// shallower (if at least [`LMR_MIN_DEPTH`] plies remain below the node), and searched again at full depth
+/// only if they turn out better than the moves searched before them. Captures, promotions, killers and moves
+/// in or into check are never reduced. There is no quiescence search, so reducing by a single ply would end the
+/// line on the other side's move and make every reduced score look better than the full depth ones.
+#[cfg(feature = "lmr")]
+pub const LMR_FULL_DEPTH_MOVES: usize = 3;
+#[cfg(feature = "lmr")]
+pub const LMR_MIN_DEPTH: u16 = 3;
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     struct TerminalFlags: u8 {
         const CHECKMATE_WHITE_WIN = 1 << 0;
         const CHECKMATE_BLACK_WIN = 1 << 1;
         const STALEMATE = 1 << 2;
-        const ANY_TERMINAL = Self::CHECKMATE_WHITE_WIN.bits() | Self::CHECKMATE_BLACK_WIN.bits() | Self::STALEMATE.bits();
+        const FIFTY_MOVE_DRAW = 1 << 3;
+        const REPETITION = 1 << 4;
+        const ANY_TERMINAL = Self::CHECKMATE_WHITE_WIN.bits() | Self::CHECKMATE_BLACK_WIN.bits() | Self::STALEMATE.bits() | Self::FIFTY_MOVE_DRAW.bits() | Self::REPETITION.bits();
     }
 }
 
@@ -30,7 +53,10 @@ struct TreeEntry {
     r#move: Option<Move>, // The move leading to this entry from its parent (None for the root)
     depth: u16,           // The depth of this entry in the search tree
     score: f32,           // Current evaluation score at provided depth
-    board: Board,         // The board state after applying the move sequence leading to this entry
+    static_score: f32, // Static evaluation of the board, breaks the ties between lines of the same score
+    epoch: u16, // Epoch in which the score was last backed up, 0 for the static evaluation of an unsearched node
+    horizon: u16, // Horizon of the search that backed up the score, below the epoch for the reduced late moves
+    board: Board, // The board state after applying the move sequence leading to this entry
     flags: TerminalFlags, // Flags to indicate if this entry is terminal and the type of terminal
 }
 
@@ -39,33 +65,279 @@ impl TreeEntry {
         (self.flags & TerminalFlags::ANY_TERMINAL) != TerminalFlags::empty()
     }
 
-    fn should_evaluate(&self, epoch: u16) -> bool {
+    fn should_evaluate(&self, horizon: u16) -> bool {
         // We should evaluate this entry if it's not terminal and we haven't evaluated it at this depth before
-        !self.is_terminal() && self.depth < epoch
+        !self.is_terminal() && self.depth < horizon
+    }
+}
+
+/// The evaluation of a search, shared with the [`SimpleAi`]. Only an incremental one (see
+/// [`Evaluator::is_incremental`]) is locked, to be notified of the moves of the search.
+#[derive(Clone, EnumIs)]
+enum SharedEvaluator {
+    Stateless(Arc<dyn Evaluator>),
+    Incremental(Arc<Mutex<Box<dyn Evaluator>>>), // Only used by one search at a time
+}
+
+impl SharedEvaluator {
+    fn new(evaluator: Box<dyn Evaluator>) -> Self {
+        match evaluator.is_incremental() {
+            true => SharedEvaluator::Incremental(Arc::new(Mutex::new(evaluator))),
+            false => SharedEvaluator::Stateless(Arc::from(evaluator)),
+        }
+    }
+
+    /// Run `f` on the incremental evaluator, still usable if a previous search panicked while evaluating.
+    fn notify(&self, f: impl FnOnce(&mut dyn Evaluator)) {
+        if let SharedEvaluator::Incremental(evaluator) = self {
+            f(evaluator.lock().unwrap_or_else(|e| e.into_inner()).as_mut());
+        }
+    }
+
+    fn evaluate(&self, board: &Board) -> f32 {
+        match self {
+            SharedEvaluator::Stateless(evaluator) => evaluator.evaluate(board),
+            SharedEvaluator::Incremental(evaluator) => evaluator
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .evaluate(board),
+        }
+    }
+}
+
+impl std::fmt::Display for TreeEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.r#move {
+            Some(mv) => write!(f, "Move: {}, score: {}", mv.uci(), self.score)?,
+            None => write!(f, "Root, score: {}", self.score)?,
+        }
+        write!(f, " -- {}", self.board.fen())
     }
 }
 
 struct SimpleAiCtx {
     tree: Tree<TreeEntry>,
+    order_moves: bool, // Try the captures first (MVV-LVA), only disabled to measure its effect
+    killers: [[Option<Move>; 2]; MAX_PLY as usize], // Quiet moves that caused a cutoff, per ply
+    use_killers: bool, // Try the killers right after the captures, only disabled to measure its effect
+    history: HistoryTable, // Cutoffs of the quiet moves, kept from one search to the next of a game
+    use_history: bool, // Order the remaining quiet moves by history, only disabled to measure its effect
+    stats: SearchStats,
+    pondering: Arc<AtomicBool>, // Set while a ponder search waits for its ponderhit
+    tt: TranspositionTable,     // Evaluations cache, kept from one search to the next of a game
+    evaluator: SharedEvaluator,
+    start_time: std::time::Instant, // Start of the search, or of the ponderhit for a ponder search
+    seldepth: u16,                  // Deepest node of the tree
+    multipv: usize,                 // Number of lines reported, see SimpleAi::with_multipv
+    game_keys: Vec<u64>, // Zobrist keys of the positions of the game before the root, see SimpleAi::set_position
+    #[cfg(feature = "lmr")]
+    reductions: bool, // Late move reductions, only disabled to measure their effect
 }
 
 impl SimpleAiCtx {
+    fn new(board: &Board, tt: TranspositionTable, evaluator: SharedEvaluator) -> Self {
+        evaluator.notify(|evaluator| evaluator.on_position(board));
+        let score = evaluator.evaluate(board);
+        SimpleAiCtx {
+            tree: Tree::new(TreeEntry {
+                r#move: None,
+                depth: 0,
+                score,
+                static_score: score,
+                epoch: 0,
+                horizon: 0,
+                board: *board,
+                flags: TerminalFlags::empty(),
+            }),
+            order_moves: true,
+            killers: [[None; 2]; MAX_PLY as usize],
+            use_killers: true,
+            history: HistoryTable::default(),
+            use_history: true,
+            stats: SearchStats::default(),
+            pondering: Arc::new(AtomicBool::new(false)),
+            tt,
+            evaluator,
+            start_time: std::time::Instant::now(),
+            seldepth: 0,
+            multipv: 1,
+            game_keys: Vec::new(),
+            #[cfg(feature = "lmr")]
+            reductions: true,
+        }
+    }
+
+    /// Context of a search of `board`, a position the game reached after this search (at most two plies after
+    /// its root). The transposition table and the history are carried over, and so is the part of the tree
+    /// below `board` when this search explored it: the scores of its nodes only have their mate distances
+    /// shortened to count from the new root.
+    fn follow(self, board: &Board) -> SimpleAiCtx {
+        let mut ctx = SimpleAiCtx::new(board, self.tt, self.evaluator.clone());
+        ctx.history = self.history;
+
+        let mut found = None;
+        let mut stack = vec![self.tree.root()];
+        while let Some(node) = stack.pop() {
+            if node.board == *board {
+                found = Some(node);
+                break;
+            }
+            if let Some(next) = node.next().filter(|_| node.depth > 0) {
+                stack.push(next);
+            }
+            if let Some(child) = node.child().filter(|_| node.depth < 2) {
+                stack.push(child);
+            }
+        }
+
+        // A terminal position is searched again, it may only be one because of the path that led to it
+        if let Some(found) = found.filter(|found| !found.is_terminal()) {
+            let shift = found.depth;
+            ctx.tree = self.tree.map_subtree(found.noderef(), |entry| {
+                let depth = entry.depth - shift;
+                ctx.seldepth = ctx.seldepth.max(depth);
+                TreeEntry {
+                    r#move: entry.r#move.filter(|_| depth > 0),
+                    depth,
+                    score: match mate_plies(entry.score) {
+                        Some(_) => entry.score + shift as f32 * entry.score.signum(),
+                        None => entry.score,
+                    },
+                    static_score: entry.static_score,
+                    epoch: 0,
+                    horizon: 0,
+                    board: entry.board,
+                    flags: entry.flags,
+                }
+            });
+        }
+        ctx
+    }
+
+    /// Tell the evaluator that the search goes down to this node, by the move leading to it (nothing for the
+    /// root).
+    fn enter_node(&self, noderef: TreeNodeRef) {
+        let node = self.tree.get(noderef);
+        if let (Some(mv), Some(parent)) = (node.r#move, node.parent()) {
+            self.evaluator
+                .notify(|evaluator| evaluator.on_make(&parent.board, mv));
+        }
+    }
+
+    /// Tell the evaluator that the search goes back up from this node, see [`SimpleAiCtx::enter_node`].
+    fn leave_node(&self, noderef: TreeNodeRef) {
+        let node = self.tree.get(noderef);
+        if let (Some(mv), Some(parent)) = (node.r#move, node.parent()) {
+            self.evaluator
+                .notify(|evaluator| evaluator.on_unmake(&parent.board, mv));
+        }
+    }
+
+    /// Horizon of the `index`-th child searched below a node whose horizon is `horizon`: two plies closer for the
+    /// late quiet moves (see [`LMR_FULL_DEPTH_MOVES`]) when the `lmr` feature is enabled.
+    fn child_horizon(&self, child: TreeNodeRef, index: usize, horizon: u16) -> u16 {
+        #[cfg(feature = "lmr")]
+        if self.reductions && self.is_reducible(child, index, horizon) {
+            return horizon - 2;
+        }
+        #[cfg(not(feature = "lmr"))]
+        let _ = (child, index);
+        horizon
+    }
+
+    #[cfg(feature = "lmr")]
+    fn is_reducible(&self, child: TreeNodeRef, index: usize, horizon: u16) -> bool {
+        let node = self.tree.get(child);
+        let (Some(mv), Some(parent)) = (node.r#move, node.parent()) else {
+            return false;
+        };
+
+        // The root moves keep their exact score when several lines are reported
+        index >= LMR_FULL_DEPTH_MOVES
+            && horizon >= parent.depth + LMR_MIN_DEPTH
+            && !(parent.depth == 0 && self.multipv > 1)
+            && !parent.board.is_check()
+            && mv.promotion.is_none()
+            && mv.mvv_lva_score(&parent.board) == 0
+            && !self.killers[parent.depth as usize].contains(&Some(mv))
+            && !node.board.is_check()
+    }
+
+    /// Static evaluation of the board, looked up in the transposition table first.
+    fn evaluate(&mut self, board: &Board) -> f32 {
+        self.stats.tt_probes += 1;
+        if let Some(score) = self.tt.probe(board.zobrist) {
+            self.stats.tt_hits += 1;
+            return score;
+        }
+
+        let score = self.evaluator.evaluate(board);
+        self.tt.store(board.zobrist, score);
+        score
+    }
+
     fn derive_results(&self) -> Option<AiResult> {
+        self.derive_line(&[])
+    }
+
+    /// The principal variations of the `multipv` best root moves, best first. Each line is the best one left
+    /// once the root moves of the previous lines are excluded.
+    fn derive_lines(&self) -> Vec<AiResult> {
+        let mut lines: Vec<AiResult> = Vec::new();
+        while lines.len() < self.multipv {
+            let excluded: Vec<Move> = lines.iter().map(|line| line.best_move).collect();
+            let Some(line) = self.derive_line(&excluded) else {
+                break;
+            };
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// The principal variation starting with the best root move that is not in `excluded`.
+    fn derive_line(&self, excluded: &[Move]) -> Option<AiResult> {
         let mut pv = Vec::new();
         let mut current = self.tree.get(TreeNodeRef::ROOT);
+        let mut score = current.score;
 
-        while let Some(mut child) = current.child() {
-            // Iterate over all siblings to find the one with the best score
+        while let Some(first_child) = current.child() {
+            // Iterate over the siblings to find the one with the best score, the exclusions only apply to the
+            // root moves. The siblings cut by the pruning in the epoch that scored the current node are left out,
+            // their score is the one of an earlier epoch
             let current_color = current.board.next_to_move();
-            let mut best_child = child;
-            while let Some(sibling) = child.next() {
-                if current_color.minmax_cmp(sibling.score, best_child.score) {
-                    best_child = sibling;
+            let at_root = pv.is_empty();
+            let mut best_child: Option<TreeRef<TreeEntry>> = None;
+            let mut child = Some(first_child);
+            while let Some(sibling) = child {
+                child = sibling.next();
+                if sibling.epoch < current.epoch
+                    || at_root && sibling.r#move.is_some_and(|mv| excluded.contains(&mv))
+                {
+                    continue;
+                }
+
+                // Between lines of the same value, a reduced late move (whose line is shorter) comes last, then
+                // the one that is ahead right after the move gets there sooner (mates already carry their
+                // distance in their score)
+                if best_child.is_none_or(|best_child| {
+                    current_color.minmax_cmp(sibling.score, best_child.score)
+                        || (sibling.score == best_child.score
+                            && (sibling.horizon > best_child.horizon
+                                || sibling.horizon == best_child.horizon
+                                    && current_color
+                                        .minmax_cmp(sibling.static_score, best_child.static_score)))
+                }) {
+                    best_child = Some(sibling);
                 }
-                child = sibling;
             }
 
             // Then we add the best_child as the next move in the principal variation and continue down the tree
+            let Some(best_child) = best_child else {
+                break;
+            };
+            if at_root && !excluded.is_empty() {
+                score = best_child.score;
+            }
             current = best_child;
             if let Some(mv) = current.r#move {
                 pv.push(mv);
@@ -78,22 +350,67 @@ impl SimpleAiCtx {
             return None;
         }
 
+        let elapsed = self.start_time.elapsed();
         Some(AiResult {
             best_move: pv[0],
             depth: pv.len() as u16,
+            seldepth: self.seldepth.max(pv.len() as u16),
             pv,
             nodes: self.tree.node_count(),
-            score: self.tree.get(TreeNodeRef::ROOT).score,
+            score,
+            stats: Some(SearchStats {
+                nodes: self.tree.node_count(),
+                ..self.stats.clone()
+            }),
+            time_ms: elapsed.as_millis() as u64,
+            nps: AiResult::nodes_per_second(self.tree.node_count(), elapsed),
+            hashfull: self.tt.hashfull(),
         })
     }
 
-    fn run(&mut self, limits: AiLimit, print: bool, stop_signal: Arc<AtomicBool>) {
+    /// Whether the search must stop, because of the stop signal or (unless pondering) of the time or node limit.
+    /// Schedules the next check, no later than the node limit. Never before the root moves are expanded and
+    /// scored, a search always has a move to report.
+    fn limit_reached(
+        &self,
+        limits: &AiLimit,
+        pondering: bool,
+        stop_signal: &AtomicBool,
+        next_limit_check: &mut usize,
+    ) -> bool {
+        let root = self.tree.root();
+        if root.child().is_none() && !root.is_terminal() {
+            return false;
+        }
+
+        let nodes = self.tree.node_count();
+        *next_limit_check = nodes + LIMIT_CHECK_INTERVAL;
+        if let Some(node_limit) = limits.nodes {
+            *next_limit_check = (*next_limit_check).min(node_limit.max(1) as usize);
+        }
+
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            return true;
+        }
+        !pondering
+            && (limits
+                .movetime
+                .is_some_and(|movetime| self.start_time.elapsed() >= movetime)
+                || limits
+                    .nodes
+                    .is_some_and(|node_limit| nodes as u64 >= node_limit))
+    }
+
+    /// Run the iterative deepening search until a limit is reached, returns the last epoch (depth) reached.
+    fn run(&mut self, limits: AiLimit, print: bool, stop_signal: Arc<AtomicBool>) -> u16 {
         // Stack for our iterative deepening search, which will contain references to tree nodes
         // alongside the phase
         struct Evaluating {
             noderef: TreeNodeRef,
             alpha: f32,
             beta: f32,
+            horizon: u16, // Depth down to which the tree is expanded below this node, the epoch unless reduced
+            index: usize, // Rank of the node amongst its siblings, in search order
         }
 
         struct Backtracking {
@@ -101,6 +418,8 @@ impl SimpleAiCtx {
             current_score: f32, // The score amongst the siblings that we have evaluated so far
             alpha: f32,
             beta: f32,
+            horizon: u16,
+            index: usize,
         }
 
         #[derive(EnumIs, EnumTryAs)]
@@ -111,35 +430,71 @@ impl SimpleAiCtx {
         let mut stack = Vec::new();
         let mut moves = Vec::new();
 
+        // Zobrist keys of the nodes having a backtracking entry on the stack, i.e. of the current search path
+        let mut path: Vec<u64> = Vec::new();
+
         let mut epoch = 0u16;
-        let start_time = std::time::Instant::now();
-        loop {
+        let mut next_limit_check = 0;
+        self.start_time = std::time::Instant::now();
+        let mut pondering =
+            limits.ponder && self.pondering.load(std::sync::atomic::Ordering::Relaxed);
+        'search: loop {
             // While we have time, we will perform a depth-limited search, increasing the depth limit (epoch) with each iteration
-            if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
-                break;
+
+            // A ponder search ignores its limits until the ponderhit, which is when the clock starts
+            if pondering && !self.pondering.load(std::sync::atomic::Ordering::Relaxed) {
+                pondering = false;
+                self.start_time = std::time::Instant::now();
             }
 
-            // If we have a time limit and we are close to it, we should stop the search to avoid overshooting
-            if let Some(movetime) = limits.movetime {
-                if start_time.elapsed() >= movetime {
-                    break;
-                }
+            // The stop signal and the time and node limits are checked every few thousand nodes, and at the end
+            // of every epoch
+            if self.tree.node_count() >= next_limit_check
+                && self.limit_reached(&limits, pondering, &stop_signal, &mut next_limit_check)
+            {
+                break;
             }
 
-            if let Some(depth_limit) = limits.depth {
-                if epoch >= depth_limit {
-                    break;
-                }
+            // The depth limit is checked once an epoch is completed, only a ponder search can go past it and
+            // its last epoch is then abandoned at the ponderhit
+            if let Some(depth_limit) = limits.depth
+                && !pondering
+                && epoch > depth_limit
+            {
+                break;
             }
 
             // Pop last element from the stack
             match stack.pop() {
                 Some(StackEntry::Evaluating(evaluating)) => {
+                    // The evaluator follows the search down to this node, and back up with its backtracking entry
+                    self.enter_node(evaluating.noderef);
+
                     // Get the tree entry for this node reference, then three possiblity
                     let mut entry = self.tree.get_mut(evaluating.noderef);
                     let next_to_move = entry.board.next_to_move();
 
-                    if let Some(child_noderef) = entry.child_noderef() {
+                    // A line coming back to a position of the current path is a draw, whoever seems to be
+                    // ahead: the other side can keep repeating it (e.g. with a perpetual check). So is a line
+                    // coming back to a position the game went through.
+                    if entry.child_noderef().is_none()
+                        && !entry.is_terminal()
+                        && (path.contains(&entry.board.zobrist)
+                            || (entry.depth > 0 && self.game_keys.contains(&entry.board.zobrist)))
+                    {
+                        entry.flags |= TerminalFlags::REPETITION;
+                        entry.score = 0.0;
+                    }
+
+                    // Every branch below pushes exactly one backtracking entry for this node
+                    path.push(entry.board.zobrist);
+
+                    // Below a reduced late move, the nodes expanded by the previous epochs end the line at the
+                    // reduced horizon all the same: their children would end it on the other side's move
+                    let reduced_leaf =
+                        evaluating.horizon < epoch && entry.depth >= evaluating.horizon;
+
+                    if let Some(child_noderef) = entry.child_noderef().filter(|_| !reduced_leaf) {
                         // Only the first child is pushed as it is responsible for pushing the next child
                         // during backtracking.
                         stack.push(StackEntry::Backtracking(Backtracking {
@@ -147,17 +502,22 @@ impl SimpleAiCtx {
                             current_score: next_to_move.minmax_ini(),
                             alpha: evaluating.alpha,
                             beta: evaluating.beta,
+                            horizon: evaluating.horizon,
+                            index: evaluating.index,
                         }));
 
                         stack.push(StackEntry::Evaluating(Evaluating {
                             noderef: child_noderef,
                             alpha: evaluating.alpha,
                             beta: evaluating.beta,
+                            horizon: evaluating.horizon,
+                            index: 0,
                         }));
-                    } else if entry.should_evaluate(epoch) {
+                    } else if entry.should_evaluate(evaluating.horizon) {
                         // Generate moves for this position and add them to the tree as children of the current node
                         let mut currently_in_check = false;
                         generate_moves(&entry.board, &mut moves, &mut currently_in_check);
+                        self.stats.expanded_nodes += 1;
 
                         // Handle terminal positions (checkmate or stalemate)
                         if moves.is_empty() {
@@ -171,9 +531,9 @@ impl SimpleAiCtx {
                                 TerminalFlags::STALEMATE
                             };
                             entry.score = if currently_in_check {
-                                // If next to move is white then black has won, therefore
-                                // negative infinity
-                                next_to_move.minmax_ini()
+                                // The mate distance is seeded here, and kept by the score as it is
+                                // propagated toward the root
+                                mate_score(next_to_move, entry.depth)
                             } else {
                                 0.0
                             };
@@ -184,22 +544,84 @@ impl SimpleAiCtx {
                                 current_score: entry.score,
                                 alpha: evaluating.alpha,
                                 beta: evaluating.beta,
+                                horizon: evaluating.horizon,
+                                index: evaluating.index,
+                            }));
+                        } else if entry.depth > 0 && entry.board.is_fifty_move_draw() {
+                            // Checkmate takes precedence over the fifty-move rule, hence this is only checked
+                            // once we know there are legal moves. The root is never scored as a draw since
+                            // we still have to pick a move there.
+                            moves.clear();
+                            entry.flags |= TerminalFlags::FIFTY_MOVE_DRAW;
+                            entry.score = 0.0;
+
+                            stack.push(StackEntry::Backtracking(Backtracking {
+                                noderef: evaluating.noderef,
+                                current_score: entry.score,
+                                alpha: evaluating.alpha,
+                                beta: evaluating.beta,
+                                horizon: evaluating.horizon,
+                                index: evaluating.index,
                             }));
                         } else {
-                            // let board = entry.board.clone();
+                            // Children are prepended to the tree (and searched in that order), hence the
+                            // ascending sort to get the most promising captures searched first. The sort is
+                            // stable so quiet moves keep their order.
+                            if self.order_moves {
+                                let killers = match self.use_killers {
+                                    true => self.killers[entry.depth as usize],
+                                    false => [None; 2],
+                                };
+                                let history = self.use_history.then_some(&self.history);
+                                moves.sort_by_key(|mv| match mv.mvv_lva_score(&entry.board) {
+                                    // Every capture scores above 2
+                                    0 if killers[0] == Some(*mv) => (2, 0),
+                                    0 if killers[1] == Some(*mv) => (1, 0),
+                                    0 => (0, history.map_or(0, |history| history.score(*mv))),
+                                    score => (score, 0),
+                                });
+                            }
+
                             // Add as many children as we have moves, and push them to the stack for evaluation
+                            let (board, depth) = (entry.board, entry.depth);
                             for mv in moves.drain(..) {
-                                let mut new_board = entry.board.clone();
+                                let mut new_board = board;
                                 mv.apply(&mut new_board);
 
-                                entry.push_child(TreeEntry {
+                                self.evaluator
+                                    .notify(|evaluator| evaluator.on_make(&board, mv));
+                                let score = self.evaluate(&new_board);
+                                self.evaluator
+                                    .notify(|evaluator| evaluator.on_unmake(&board, mv));
+                                self.tree.get_mut(evaluating.noderef).push_child(TreeEntry {
                                     r#move: Some(mv),
-                                    depth: entry.depth + 1,
-                                    score: evaluate(&new_board),
+                                    depth: depth + 1,
+                                    score,
+                                    static_score: score,
+                                    epoch: 0,
+                                    horizon: 0,
                                     board: new_board,
                                     flags: TerminalFlags::empty(),
                                 });
+
+                                // A costly evaluation can make a single expansion long, the search may end in
+                                // its middle: the children added so far are dropped, a node with children is
+                                // taken as fully expanded by a later search of the tree (see SimpleAiCtx::follow).
+                                // The root moves are all kept, they are the ones to report.
+                                if evaluating.noderef != TreeNodeRef::ROOT
+                                    && self.tree.node_count() >= next_limit_check
+                                    && self.limit_reached(
+                                        &limits,
+                                        pondering,
+                                        &stop_signal,
+                                        &mut next_limit_check,
+                                    )
+                                {
+                                    self.tree.get_mut(evaluating.noderef).drop_children();
+                                    break 'search;
+                                }
                             }
+                            self.seldepth = self.seldepth.max(depth + 1);
 
                             // Push the first child for evaluation, the rest will be pushed when we backtrack
                             let first_child_noderef =
@@ -209,24 +631,60 @@ impl SimpleAiCtx {
                                 current_score: next_to_move.minmax_ini(),
                                 alpha: evaluating.alpha,
                                 beta: evaluating.beta,
+                                horizon: evaluating.horizon,
+                                index: evaluating.index,
                             }));
                             stack.push(StackEntry::Evaluating(Evaluating {
                                 noderef: first_child_noderef,
                                 alpha: evaluating.alpha,
                                 beta: evaluating.beta,
+                                horizon: evaluating.horizon,
+                                index: 0,
                             }));
                         }
                     } else {
                         stack.push(StackEntry::Backtracking(Backtracking {
                             noderef: evaluating.noderef,
-                            current_score: entry.score,
+                            current_score: match reduced_leaf && !entry.is_terminal() {
+                                true => entry.static_score,
+                                false => entry.score,
+                            },
                             alpha: evaluating.alpha,
                             beta: evaluating.beta,
+                            horizon: evaluating.horizon,
+                            index: evaluating.index,
                         }));
                     }
                 }
                 #[allow(unused_mut)]
                 Some(StackEntry::Backtracking(mut backtracking)) => {
+                    path.pop();
+                    self.leave_node(backtracking.noderef);
+
+                    // A reduced late move turning out better than the moves searched before it (or than alpha)
+                    // is searched again at full depth, its parent being left as if it was not searched yet
+                    #[cfg(feature = "lmr")]
+                    if let Some(parent) = stack.last().and_then(StackEntry::try_as_backtracking_ref)
+                        && backtracking.horizon < parent.horizon
+                    {
+                        let parent_color = self.tree.get(parent.noderef).board.next_to_move();
+                        let threshold = match parent_color {
+                            Color::White => parent.current_score.max(backtracking.alpha),
+                            Color::Black => parent.current_score.min(backtracking.beta),
+                        };
+                        if parent_color.minmax_cmp(backtracking.current_score, threshold) {
+                            self.stats.lmr_researches += 1;
+                            stack.push(StackEntry::Evaluating(Evaluating {
+                                noderef: backtracking.noderef,
+                                alpha: backtracking.alpha,
+                                beta: backtracking.beta,
+                                horizon: parent.horizon,
+                                index: backtracking.index,
+                            }));
+                            continue;
+                        }
+                    }
+
                     let mut entry = self.tree.get_mut(backtracking.noderef);
                     #[cfg(feature = "alpha_beta_soft_pruning")]
                     let mut prunned = false;
@@ -235,10 +693,17 @@ impl SimpleAiCtx {
                     let next_sibling_noderef = entry.next_noderef();
 
                     entry.score = backtracking.current_score;
+                    entry.epoch = epoch;
+                    entry.horizon = backtracking.horizon;
 
-                    // Update the backtracking alpha/beta values based on the current score
+                    // Update the backtracking alpha/beta values based on the current score. With several lines
+                    // to report, the root moves all keep the full window so that their scores are exact
+                    // rather than bounds
+                    #[cfg(feature = "alpha_beta_soft_pruning")]
+                    let full_window = entry.depth == 1 && self.multipv > 1;
                     #[cfg(feature = "alpha_beta_soft_pruning")]
                     match current_color.opposite() {
+                        _ if full_window => {}
                         Color::White => {
                             if backtracking.current_score > backtracking.alpha {
                                 backtracking.alpha = backtracking.current_score;
@@ -260,6 +725,13 @@ impl SimpleAiCtx {
                             }
                         }
                     }
+                    #[cfg(feature = "alpha_beta_soft_pruning")]
+                    let cutoff_move = if prunned && next_sibling_noderef.is_some() {
+                        self.stats.beta_cutoffs += 1;
+                        entry.r#move
+                    } else {
+                        None
+                    };
 
                     // Update the parent score based on the current score and the color to move at
                     // parent node (if needed)
@@ -267,11 +739,22 @@ impl SimpleAiCtx {
                         let parent_backtracking =
                             parent_stack_entry.try_as_backtracking_mut().unwrap();
 
-                        let parent_color = self
-                            .tree
-                            .get(parent_backtracking.noderef)
-                            .board
-                            .next_to_move();
+                        let parent = self.tree.get(parent_backtracking.noderef);
+                        let parent_color = parent.board.next_to_move();
+
+                        // The move that caused the cutoff is played at the parent, quiet ones become killers
+                        // and are credited in the history table
+                        #[cfg(feature = "alpha_beta_soft_pruning")]
+                        if let Some(mv) = cutoff_move
+                            && mv.mvv_lva_score(&parent.board) == 0
+                            && mv.promotion.is_none()
+                        {
+                            let killers = &mut self.killers[parent.depth as usize];
+                            if killers[0] != Some(mv) {
+                                *killers = [Some(mv), killers[0]];
+                            }
+                            self.history.add_cutoff(mv, epoch - parent.depth);
+                        }
 
                         parent_backtracking.current_score = parent_color.minmax(
                             parent_backtracking.current_score,
@@ -293,119 +776,182 @@ impl SimpleAiCtx {
                             }
                         }
                     {
+                        // Only the root has no parent, and no sibling either
+                        let parent_horizon = stack
+                            .last()
+                            .unwrap()
+                            .try_as_backtracking_ref()
+                            .unwrap()
+                            .horizon;
+                        let index = backtracking.index + 1;
                         stack.push(StackEntry::Evaluating(Evaluating {
                             noderef: sibling_noderef,
                             alpha: backtracking.alpha,
                             beta: backtracking.beta,
+                            horizon: self.child_horizon(sibling_noderef, index, parent_horizon),
+                            index,
                         }));
                     }
                 }
                 None => {
-                    // If the stack is empty, we need to start a new search from the root
+                    // The epoch N just completed has searched every line N plies deep, report it (there is
+                    // nothing to report before the first one). The multipv field is only given when several
+                    // lines are reported
+                    if print && epoch > 0 {
+                        let lines = self.derive_lines();
+                        for (index, result) in lines.iter().enumerate() {
+                            let multipv = if self.multipv > 1 {
+                                format!(" multipv {}", index + 1)
+                            } else {
+                                String::new()
+                            };
+                            println!(
+                                "info depth {} seldepth {}{} score {} nodes {} nps {} hashfull {} time {} pv {}",
+                                epoch,
+                                result.seldepth,
+                                multipv,
+                                UciScore::from_score(
+                                    result.score,
+                                    self.tree.root().board.next_to_move(),
+                                ),
+                                result.nodes,
+                                result.nps,
+                                result.hashfull,
+                                result.time_ms,
+                                result.pv_uci(&self.tree.root().board).join(" ")
+                            );
+                        }
+                    }
+
+                    if let Some(depth_limit) = limits.depth
+                        && !pondering
+                        && epoch >= depth_limit
+                    {
+                        break;
+                    }
+
+                    // Never deepen past MAX_PLY, the tree would otherwise keep growing (or the epoch overflow)
+                    if epoch >= MAX_PLY {
+                        break;
+                    }
+
+                    // The completed epoch may have found a mate short enough for the mate limit
+                    let root = self.tree.root();
+                    let root_plies =
+                        mate_plies(root.score * root.board.next_to_move().score_multiplier());
+                    if let Some(mate_limit) = limits.mate
+                        && !pondering
+                        && root_plies.is_some_and(|plies| plies > 0 && plies <= mate_limit as i32)
+                    {
+                        break;
+                    }
+
+                    if self.limit_reached(&limits, pondering, &stop_signal, &mut next_limit_check) {
+                        break;
+                    }
+
+                    // Start the next epoch from the root, one ply deeper
+                    debug_assert!(path.is_empty());
                     epoch += 1;
                     stack.push(StackEntry::Evaluating(Evaluating {
                         noderef: TreeNodeRef::ROOT,
                         alpha: f32::NEG_INFINITY,
                         beta: f32::INFINITY,
+                        horizon: epoch,
+                        index: 0,
                     }));
-
-                    // Print some debug info about the current search
-                    if print && let Some(result) = self.derive_results() {
-                        println!(
-                            "info depth {} score {} nodes {} time {} pv {}",
-                            epoch,
-                            self.tree.get(TreeNodeRef::ROOT).score,
-                            self.tree.node_count(),
-                            start_time.elapsed().as_millis(),
-                            result
-                                .pv
-                                .iter()
-                                .map(|mv| mv.to_string())
-                                .collect::<Vec<_>>()
-                                .join(" ")
-                        );
-                    }
                 }
             }
         }
 
-        if print {
+        // The best move of a ponder search must not be reported before the ponderhit (or stop)
+        while self.pondering.load(std::sync::atomic::Ordering::Relaxed)
+            && !stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+        {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        // Raising the stop signal tells whether the search was interrupted, in which case reporting its best move
+        // is left to the caller of stop
+        if print && !stop_signal.swap(true, std::sync::atomic::Ordering::SeqCst) {
             if let Some(result) = self.derive_results() {
-                if result.pv.len() < 2 {
-                    println!("bestmove {}", result.best_move.uci(),);
-                } else {
-                    println!(
-                        "bestmove {} ponder {}",
-                        result.best_move.uci(),
-                        result.pv[1].uci()
-                    );
-                }
+                println!("{}", result.bestmove_line(&self.tree.root().board));
             } else {
                 println!("bestmove (none)");
             }
         }
+
+        epoch
     }
 }
 
 pub struct SimpleAi {
     ctx: RefCell<Option<SimpleAiCtx>>,
     stop_signal: Arc<AtomicBool>,
-    thread: RefCell<Option<std::thread::JoinHandle<SimpleAiCtx>>>,
+    pondering: Arc<AtomicBool>,
+    thread: RefCell<Option<std::thread::JoinHandle<Option<SimpleAiCtx>>>>,
+    evaluator: SharedEvaluator,
+    print: Cell<bool>, // Whether the search in flight reports to the standard output
+    multipv: usize,
+    game: RefCell<Option<(Board, Vec<u64>)>>, // Current position of the game and keys of the ones before it
 }
 
-impl std::default::Default for SimpleAi {
-    fn default() -> Self {
+impl SimpleAi {
+    /// A search using the given evaluation of the positions instead of [`evaluate`].
+    pub fn with_evaluator(evaluator: Box<dyn Evaluator>) -> Self {
         SimpleAi {
             ctx: RefCell::new(None),
             stop_signal: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
             thread: RefCell::new(None),
+            evaluator: SharedEvaluator::new(evaluator),
+            print: Cell::new(false),
+            multipv: 1,
+            game: RefCell::new(None),
         }
     }
-}
 
-#[allow(dead_code)]
-fn display_tree(tree: crate::tree::TreeRef<'_, TreeEntry>, indent: usize, depth: usize) {
-    if let Some(mv) = tree.r#move {
-        println!("{}Move: {}, score: {}", "  ".repeat(indent), mv, tree.score);
-    } else {
-        println!("{}Root -- {}", "  ".repeat(indent), tree.board.fen());
+    /// Report the best `multipv` lines (at least one) instead of only the principal variation, each with its
+    /// own first move. The `info` output then gives the rank of each line with `multipv 1`, `multipv 2`, ...
+    /// and the lines of the last search are available from [`SimpleAi::lines`]. The root moves are searched
+    /// with the full window to get their exact score, which makes the search slower.
+    pub fn with_multipv(mut self, multipv: usize) -> Self {
+        self.multipv = multipv.max(1);
+        self
     }
 
-    if depth > 0 {
-        let mut child_opt = tree.child();
-        while let Some(child) = child_opt {
-            display_tree(child, indent + 1, depth - 1);
-            child_opt = child.next();
-        }
+    /// The lines found by the last stopped search, best first, see [`SimpleAi::with_multipv`]. There may be
+    /// fewer lines than requested when the position has fewer legal moves.
+    pub fn lines(&self) -> Vec<AiResult> {
+        self.ctx
+            .borrow()
+            .as_ref()
+            .map_or_else(Vec::new, SimpleAiCtx::derive_lines)
     }
-}
 
-impl Ai for SimpleAi {
-    fn name(&self) -> &str {
-        "chessoteric"
-    }
-
-    fn authors(&self) -> &[&str] {
-        &["Guillaume Boyé"]
-    }
-
-    fn start(&self, board: &Board, limits: AiLimit, print: bool) -> AiType {
-        if self.thread.borrow().is_some() {
+    /// Stop the search in flight, if any, and take the context of the last search.
+    fn take_ctx(&self) -> Option<SimpleAiCtx> {
+        let thread = self.thread.borrow_mut().take();
+        let previous = thread.and_then(|thread| {
             self.stop_signal
                 .store(true, std::sync::atomic::Ordering::SeqCst);
-            self.thread.borrow_mut().take().unwrap().join().unwrap();
-        }
+            self.join_thread(thread)
+        });
+        previous.or_else(|| self.ctx.borrow_mut().take())
+    }
 
-        // We will spawn a new thread for the AI to run in, and store the context in the main struct so that we can communicate with it
-        let ctx = SimpleAiCtx {
-            tree: Tree::new(TreeEntry {
-                r#move: None,
-                depth: 0,
-                score: evaluate(board),
-                board: board.clone(),
-                flags: TerminalFlags::empty(),
-            }),
-        };
+    /// Run the search of `ctx` in its own thread, the context being stored back once the search is stopped.
+    fn launch(&self, mut ctx: SimpleAiCtx, limits: AiLimit, print: bool) -> AiType {
+        ctx.multipv = self.multipv;
+        if let Some((position, keys)) = self.game.borrow().as_ref()
+            && *position == ctx.tree.root().board
+        {
+            ctx.game_keys = keys.clone();
+        }
+        self.print.set(print);
+        self.pondering
+            .store(limits.ponder, std::sync::atomic::Ordering::SeqCst);
+        ctx.pondering = self.pondering.clone();
 
         // Create a new thread
         self.stop_signal
@@ -414,9 +960,27 @@ impl Ai for SimpleAi {
         let thread_handle = std::thread::Builder::new()
             .name("SimpleAiThread".to_string())
             .spawn(move || {
+                // A panic of the search (e.g. while evaluating a malformed board) is reported from this thread,
+                // a `go depth` or `go movetime` search is never joined before the next command
                 let mut ctx = ctx;
-                ctx.run(limits, print, stop_signal.clone());
-                ctx
+                let run = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    ctx.run(limits, print, stop_signal.clone())
+                }));
+                match run {
+                    Ok(_) => Some(ctx),
+                    Err(payload) => {
+                        if print {
+                            println!(
+                                "info string error: the search thread panicked: {}",
+                                panic_message(&*payload)
+                            );
+                            if !stop_signal.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                                println!("bestmove (none)");
+                            }
+                        }
+                        None
+                    }
+                }
             })
             .expect("Failed to spawn AI thread");
 
@@ -425,20 +989,109 @@ impl Ai for SimpleAi {
         AiType::Async
     }
 
+    /// Wait for the search thread to finish and return its context. The context of a search that panicked is
+    /// lost, the panic having already been reported by the search thread so that the engine stays alive.
+    fn join_thread(
+        &self,
+        thread: std::thread::JoinHandle<Option<SimpleAiCtx>>,
+    ) -> Option<SimpleAiCtx> {
+        thread.join().ok().flatten()
+    }
+}
+
+/// Message of a panic payload, as given to `panic!`.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+impl std::default::Default for SimpleAi {
+    fn default() -> Self {
+        SimpleAi::with_evaluator(Box::new(evaluate))
+    }
+}
+
+impl Ai for SimpleAi {
+    fn name(&self) -> &str {
+        "chessoteric"
+    }
+
+    fn authors(&self) -> &[&str] {
+        &["Guillaume Boyé"]
+    }
+
+    fn set_position(&self, board: &Board, history: &[Move]) {
+        let mut position = *board;
+        let mut keys = Vec::with_capacity(history.len());
+        for mv in history {
+            keys.push(position.zobrist);
+            mv.apply(&mut position);
+        }
+        self.game.replace(Some((position, keys)));
+    }
+
+    fn start(&self, board: &Board, limits: AiLimit, print: bool) -> AiType {
+        // The transposition table and the history of the previous search are carried over
+        let (tt, history) = self
+            .take_ctx()
+            .map(|ctx| (ctx.tt, ctx.history))
+            .unwrap_or_default();
+        let mut ctx = SimpleAiCtx::new(board, tt, self.evaluator.clone());
+        ctx.history = history;
+        self.launch(ctx, limits, print)
+    }
+
+    fn ponder(&self, board: &Board, expected_move: Move, limits: AiLimit, print: bool) -> AiType {
+        let mut position = *board;
+        expected_move.apply(&mut position);
+
+        // The previous search usually went through the expected move already, what it explored below it is kept
+        let ctx = match self.take_ctx() {
+            Some(previous) => previous.follow(&position),
+            None => SimpleAiCtx::new(&position, Default::default(), self.evaluator.clone()),
+        };
+        let limits = AiLimit {
+            ponder: true,
+            ..limits
+        };
+        self.launch(ctx, limits, print)
+    }
+
     fn stop(&self) -> Option<super::AiResult> {
         // Signal the thread to stop and wait for it to finish, then return the best move found
-        self.stop_signal
-            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let reported = self
+            .stop_signal
+            .swap(true, std::sync::atomic::Ordering::SeqCst);
 
-        let ctx = self.thread.borrow_mut().take().unwrap().join().unwrap();
+        let thread = self.thread.borrow_mut().take()?;
+        let ctx = self.join_thread(thread);
+        self.pondering
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        let Some(ctx) = ctx else {
+            // The search never got to report its best move, unless its thread did so when it panicked
+            if !reported && self.print.get() {
+                println!("bestmove (none)");
+            }
+            return None;
+        };
         self.ctx.borrow_mut().replace(ctx);
         let ctx = self.ctx.borrow();
 
         // if let Some(ctx) = ctx.as_ref() {
-        //     // display_tree(ctx.tree.get(TreeNodeRef::ROOT), 0, 3);
+        //     eprintln!("{}", ctx.tree.debug_print(3));
         // }
 
-        ctx.as_ref().unwrap().derive_results()
+        if reported && self.print.get() {
+            return None;
+        }
+        let result = ctx.as_ref().unwrap().derive_results();
+        if result.is_none() && self.print.get() {
+            println!("bestmove (none)");
+        }
+        result
     }
 
     fn is_ready(&self) -> bool {
@@ -450,13 +1103,1036 @@ impl Ai for SimpleAi {
         }
     }
 
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::PONDER | Capabilities::MULTI_PV | Capabilities::INFINITE
+    }
+
+    fn ponderhit(&self) {
+        self.pondering
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+
     fn reset(&self) {
-        // We can simply stop the current thread and clear the context, the next time start is called a new search will be launched from scratch
+        // Stop any search in flight (ponder searches included), then clear the context: its tree (and thus
+        // the last principal variation), its transposition table and its history, as well as the game. The next
+        // time start is called a new search will be launched from scratch
         self.stop_signal
             .store(true, std::sync::atomic::Ordering::SeqCst);
-        if let Some(thread) = self.thread.borrow_mut().take() {
-            thread.join().unwrap();
+        self.pondering
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        let thread = self.thread.borrow_mut().take();
+        if let Some(thread) = thread {
+            self.join_thread(thread);
         }
         self.ctx.borrow_mut().take();
+        self.game.borrow_mut().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::Piece,
+        moves::{MoveFlags, perft},
+    };
+
+    fn new_ctx(board: &Board) -> SimpleAiCtx {
+        SimpleAiCtx::new(
+            board,
+            TranspositionTable::default(),
+            SharedEvaluator::new(Box::new(evaluate)),
+        )
+    }
+
+    #[test]
+    fn test_infinite_search_plateaus_at_max_ply() {
+        // Black is already checkmated, every epoch is instantaneous so an unbounded search would spin forever
+        let board = Board::from_fen("7k/6Q1/5K2/8/8/8/8/8 b - - 0 1").unwrap();
+        let mut ctx = new_ctx(&board);
+
+        let epoch = ctx.run(AiLimit::default(), false, Arc::new(AtomicBool::new(false)));
+        assert_eq!(epoch, MAX_PLY);
+        assert!(ctx.derive_results().is_none());
+    }
+
+    fn search_to_depth(fen: &str, depth: u16) -> AiResult {
+        let board = Board::from_fen(fen).unwrap();
+        let mut ctx = new_ctx(&board);
+        let limits = AiLimit {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        ctx.derive_results().unwrap()
+    }
+
+    #[test]
+    fn test_depth_limit_is_in_plies() {
+        // Depth 1 expands the root only: its children are the whole tree and each one is a line of one ply
+        let board = Board::default_position();
+        let mut ctx = new_ctx(&board);
+        let limits = AiLimit {
+            depth: Some(1),
+            ..Default::default()
+        };
+        let epoch = ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        assert_eq!(epoch, 1);
+        let result = ctx.derive_results().unwrap();
+        assert_eq!(result.pv.len(), 1);
+        assert_eq!(result.depth, 1);
+        assert_eq!(result.nodes as u64, 1 + perft(&board, 1));
+
+        // Each further epoch goes one ply deeper
+        for depth in 2..=4 {
+            let mut ctx = new_ctx(&board);
+            let limits = AiLimit {
+                depth: Some(depth),
+                ..Default::default()
+            };
+            assert_eq!(
+                ctx.run(limits, false, Arc::new(AtomicBool::new(false))),
+                depth
+            );
+            assert_eq!(ctx.seldepth, depth);
+            let result = ctx.derive_results().unwrap();
+            assert_eq!(result.pv.len(), depth as usize);
+            assert_eq!(result.depth, depth);
+        }
+    }
+
+    #[test]
+    fn test_serial_search_is_deterministic() {
+        // Reference for any multi-threaded search: with a single thread it must reproduce exactly the
+        // best move, principal variation and node count of the serial search
+        const FEN: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let reference = search_to_depth(FEN, 3);
+        for _ in 0..2 {
+            let result = search_to_depth(FEN, 3);
+            assert_eq!(result.best_move, reference.best_move);
+            assert_eq!(result.pv, reference.pv);
+            assert_eq!(result.nodes, reference.nodes);
+            assert_eq!(result.score, reference.score);
+        }
+    }
+
+    #[test]
+    fn test_multipv_lines() {
+        let board = Board::default_position();
+        let mut ctx = new_ctx(&board);
+        ctx.multipv = 3;
+        let limits = AiLimit {
+            depth: Some(2),
+            ..Default::default()
+        };
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+
+        let lines = ctx.derive_lines();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].pv, ctx.derive_results().unwrap().pv);
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line.best_move, line.pv[0]);
+            assert!(
+                lines[..index]
+                    .iter()
+                    .all(|other| other.best_move != line.best_move)
+            );
+        }
+        assert!(
+            lines.windows(2).all(|pair| pair[0].score >= pair[1].score),
+            "{:?}",
+            lines.iter().map(|line| line.score).collect::<Vec<_>>()
+        );
+
+        // With the full window at the root, the score of every line is the one of a search of its position
+        for line in &lines {
+            let mut after = board;
+            line.best_move.apply(&mut after);
+            let reply = search_to_depth(&after.fen().to_string(), 1);
+            assert_eq!(line.score, reply.score, "{}", line.best_move.uci());
+        }
+
+        // There are no more lines than legal moves
+        let board = Board::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let mut ctx = new_ctx(&board);
+        ctx.multipv = 5;
+        ctx.run(
+            AiLimit {
+                depth: Some(1),
+                ..Default::default()
+            },
+            false,
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert_eq!(ctx.derive_lines().len(), 3);
+    }
+
+    #[test]
+    fn test_node_limit() {
+        for node_limit in [2_000, 20_000] {
+            let board = Board::default_position();
+            let mut ctx = new_ctx(&board);
+            let limits = AiLimit {
+                nodes: Some(node_limit),
+                ..Default::default()
+            };
+            ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+
+            // Every check of the limit is at most a few dozen expansions apart, and the expansion the limit falls
+            // in is dropped (a position has at most 218 moves)
+            let nodes = ctx.tree.node_count() as u64;
+            assert!(
+                (node_limit - 218..node_limit + 2_000).contains(&nodes),
+                "{} nodes searched for a limit of {}",
+                nodes,
+                node_limit
+            );
+            assert!(ctx.derive_results().is_some());
+        }
+    }
+
+    #[test]
+    fn test_movetime_is_honored() {
+        // A busy midgame, with plenty of captures and checks in every line
+        let board =
+            Board::from_fen("r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R2QK2R w KQ - 0 8")
+                .unwrap();
+        let movetime = std::time::Duration::from_millis(50);
+        let mut ctx = new_ctx(&board);
+        let limits = AiLimit {
+            movetime: Some(movetime),
+            ..Default::default()
+        };
+
+        let start = std::time::Instant::now();
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed < movetime + std::time::Duration::from_millis(30),
+            "Searched for {:?}",
+            elapsed
+        );
+        assert!(ctx.derive_results().is_some());
+
+        // Same for the stop signal raised from another thread
+        let mut ctx = new_ctx(&board);
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let stop_signal = stop_signal.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(movetime);
+                stop_signal.store(true, std::sync::atomic::Ordering::SeqCst);
+            })
+        };
+        let start = std::time::Instant::now();
+        ctx.run(AiLimit::default(), false, stop_signal);
+        let elapsed = start.elapsed();
+        stopper.join().unwrap();
+        assert!(
+            elapsed < movetime + std::time::Duration::from_millis(30),
+            "Stopped after {:?}",
+            elapsed
+        );
+        assert!(ctx.derive_results().is_some());
+    }
+
+    #[test]
+    fn test_mate_limit() {
+        // Kb6 and Rh8#, a mate in two moves (three plies) found once the mating node is expanded, at the fourth
+        // epoch
+        let board = Board::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let mut ctx = new_ctx(&board);
+        #[cfg(feature = "lmr")]
+        {
+            ctx.reductions = false; // The quiet Kb6 would otherwise be found a ply later
+        }
+        let limits = AiLimit {
+            mate: Some(3),
+            ..Default::default()
+        };
+        let epoch = ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        assert_eq!(epoch, 4);
+        assert_eq!(ctx.derive_results().unwrap().mate_in(), Some(2));
+
+        // A mate in one is asked for, the mate in two doesn't stop the search
+        let mut ctx = new_ctx(&board);
+        let limits = AiLimit {
+            depth: Some(5),
+            mate: Some(1),
+            ..Default::default()
+        };
+        let epoch = ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        assert_eq!(epoch, 5);
+    }
+
+    #[test]
+    fn test_fifty_move_draw_is_terminal() {
+        // White is a queen up, but no capture nor pawn move is available: every move hits the fifty-move rule
+        let result = search_to_depth("7k/8/8/8/8/8/8/KQ6 w - - 99 80", 2);
+        assert_eq!(result.score, 0.0);
+
+        let result = search_to_depth("7k/8/8/8/8/8/8/KQ6 w - - 0 80", 2);
+        assert!(result.score > 5.0);
+    }
+
+    #[test]
+    fn test_debug_print_tree() {
+        let fen = "6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let mut ctx = new_ctx(&board);
+        let limits = AiLimit {
+            depth: Some(1),
+            ..Default::default()
+        };
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+
+        let root_only = ctx.tree.debug_print(0).to_string();
+        assert_eq!(root_only.lines().count(), 1);
+        assert!(root_only.starts_with("Root"));
+        assert!(root_only.contains(&board.fen().to_string()));
+
+        let output = ctx.tree.debug_print(1).to_string();
+        assert!(output.contains(&board.fen().to_string()));
+        assert!(output.contains("Move: d1d8"));
+        assert!(
+            output
+                .lines()
+                .skip(1)
+                .all(|line| line.starts_with("  Move: "))
+        );
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        // Rd8+ Rxd8 Rxd8#, the mating node sits at ply 3 and is only recognized once expanded
+        let result = search_to_depth("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 4);
+        assert!(result.is_forced_mate());
+        assert_eq!(result.pv_length(), 3);
+        assert_eq!(result.mate_in(), Some(2));
+
+        let result = search_to_depth("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 3);
+        assert!(!result.is_forced_mate());
+        assert_eq!(result.mate_in(), None);
+    }
+
+    #[test]
+    fn test_mate_scores_carry_distance() {
+        // Back rank mate in one, the mating node is expanded at the second epoch
+        let result = search_to_depth("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1", 2);
+        assert_eq!(result.best_move.uci().to_string(), "d1d8");
+        assert_eq!(
+            UciScore::from_score(result.score, Color::White),
+            UciScore::Mate(1)
+        );
+
+        let result = search_to_depth("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 4);
+        assert_eq!(
+            format!("score {}", UciScore::from_score(result.score, Color::White)),
+            "score mate 2"
+        );
+
+        // The same mate from the point of view of the side getting mated
+        let result = search_to_depth("2rR2k1/5ppp/8/8/8/8/5PPP/3R2K1 b - - 1 1", 3);
+        assert_eq!(
+            format!("score {}", UciScore::from_score(result.score, Color::Black)),
+            "score mate -1"
+        );
+    }
+
+    #[test]
+    fn test_prefers_shortest_mate() {
+        // Both rooks mate in one, and there are plenty of slower mates
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").unwrap();
+        let result = search_to_depth(&board.fen().to_string(), 4);
+        assert_eq!(result.mate_in(), Some(1));
+        assert!(result.best_move.gives_checkmate(&board));
+
+        // A mate in two and a mate in four as the only root children, the tree order must not matter
+        let board = Board::default_position();
+        let mut moves = Vec::new();
+        generate_moves(&board, &mut moves, &mut false);
+        for mate_plies in [[3, 7], [7, 3]] {
+            let mut ctx = new_ctx(&board);
+            for (mv, plies) in moves.iter().zip(mate_plies) {
+                let mut new_board = board;
+                mv.apply(&mut new_board);
+                ctx.tree.get_mut(TreeNodeRef::ROOT).push_child(TreeEntry {
+                    r#move: Some(*mv),
+                    depth: 1,
+                    score: mate_score(Color::Black, plies),
+                    static_score: evaluate(&new_board),
+                    epoch: 0,
+                    horizon: 0,
+                    board: new_board,
+                    flags: TerminalFlags::empty(),
+                });
+            }
+
+            let result = ctx.derive_results().unwrap();
+            let expected = moves[mate_plies.iter().position(|plies| *plies == 3).unwrap()];
+            assert_eq!(result.best_move, expected);
+        }
+    }
+
+    #[test]
+    fn test_prefers_faster_material_gain() {
+        // The bishop is stuck behind its pawn and lost anyway, taking it now or in two moves reaches the
+        // same material balance
+        let board = Board::from_fen("7b/1k4p1/6P1/8/8/8/8/1K5R w - - 0 1").unwrap();
+        let limits = AiLimit {
+            depth: Some(3),
+            ..Default::default()
+        };
+
+        let mut ctx = SimpleAiCtx::new(
+            &board,
+            TranspositionTable::default(),
+            SharedEvaluator::new(Box::new(crate::eval::simple_evaluation)),
+        );
+        ctx.order_moves = false; // Captures would otherwise be tried (and kept on ties) first
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        let result = ctx.derive_results().unwrap();
+        assert_eq!(result.best_move.uci().to_string(), "h1h8");
+    }
+
+    /// A search evaluating with [`crate::eval::larry_kaufman_evaluation`] whatever the evaluation features: with the
+    /// material evaluation alone nearly every quiet move scores the same, the first one searched refutes every
+    /// line and the move ordering heuristics have nothing to improve on.
+    #[cfg(feature = "alpha_beta_soft_pruning")]
+    fn new_positional_ctx(board: &Board) -> SimpleAiCtx {
+        SimpleAiCtx::new(
+            board,
+            TranspositionTable::default(),
+            SharedEvaluator::new(Box::new(crate::eval::larry_kaufman_evaluation)),
+        )
+    }
+
+    #[cfg(feature = "alpha_beta_soft_pruning")]
+    #[test]
+    fn test_killer_moves_reduce_nodes() {
+        // A back rank mate in two, and the opening where quiet moves dominate
+        for (fen, depth) in [
+            ("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 4),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                3,
+            ),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let limits = AiLimit {
+                depth: Some(depth),
+                ..Default::default()
+            };
+
+            let mut nodes = [0; 2];
+            for (use_killers, nodes) in [true, false].into_iter().zip(nodes.iter_mut()) {
+                let mut ctx = new_positional_ctx(&board);
+                ctx.use_killers = use_killers;
+                ctx.use_history = false; // The history would otherwise catch most of the killers
+                #[cfg(feature = "lmr")]
+                {
+                    ctx.reductions = false; // Killers are never reduced, they would be searched deeper
+                }
+                ctx.run(limits.clone(), false, Arc::new(AtomicBool::new(false)));
+                assert!(ctx.killers.iter().any(|killers| killers[0].is_some()));
+                *nodes = ctx.tree.node_count();
+            }
+
+            let [with_killers, without_killers] = nodes;
+            assert!(
+                with_killers < without_killers,
+                "{} nodes with killer moves, {} without in \"{}\"",
+                with_killers,
+                without_killers,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_perpetual_check_is_a_draw() {
+        // Black is way ahead in material but can only shuffle its king between g8 and h7 while the queen checks it
+        // from h5 and e8, the position is back after four plies
+        let fen = "8/6pk/8/7Q/8/3K4/rrr5/8 b - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert!(evaluate(&board) < -5.0);
+
+        let result = search_to_depth(fen, 4);
+        assert_eq!(result.best_move.uci().to_string(), "h7g8");
+        assert_eq!(result.score, 0.0, "{:?}", result.pv);
+        assert_eq!(
+            result.pv[..4]
+                .iter()
+                .map(|mv| mv.uci().to_string())
+                .collect::<Vec<_>>(),
+            ["h7g8", "h5e8", "g8h7", "e8h5"]
+        );
+    }
+
+    #[cfg(feature = "alpha_beta_soft_pruning")]
+    #[test]
+    fn test_history_is_populated_by_cutoffs() {
+        let board = Board::default_position();
+        let mut ctx = new_positional_ctx(&board);
+        let limits = AiLimit {
+            depth: Some(3),
+            ..Default::default()
+        };
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        assert!(!ctx.history.is_empty());
+
+        // The root is the most searched node, some of its replies at least must have been refuted
+        let mut moves = Vec::new();
+        generate_moves(&board, &mut moves, &mut false);
+        assert!(moves.iter().any(|mv| ctx.history.score(*mv) > 0));
+    }
+
+    #[test]
+    fn test_history_orders_quiet_moves() {
+        let board = Board::default_position();
+        let pawn_move = Move::from_uci("e2e4", &board).unwrap();
+        let limits = AiLimit {
+            depth: Some(1),
+            ..Default::default()
+        };
+
+        let first_child = |ctx: &SimpleAiCtx| ctx.tree.root().child().unwrap().r#move;
+
+        let mut ctx = new_ctx(&board);
+        ctx.run(limits.clone(), false, Arc::new(AtomicBool::new(false)));
+        assert_ne!(first_child(&ctx), Some(pawn_move));
+
+        // The quiet move with the best history is searched first
+        let mut ctx = new_ctx(&board);
+        ctx.history.add_cutoff(pawn_move, 8);
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        assert_eq!(first_child(&ctx), Some(pawn_move));
+    }
+
+    #[cfg(feature = "alpha_beta_soft_pruning")]
+    #[test]
+    fn test_move_ordering_reduces_nodes() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let limits = AiLimit {
+            depth: Some(3),
+            ..Default::default()
+        };
+
+        let mut nodes = [0; 2];
+        for (order_moves, nodes) in [true, false].into_iter().zip(nodes.iter_mut()) {
+            let mut ctx = new_ctx(&board);
+            ctx.order_moves = order_moves;
+            ctx.run(limits.clone(), false, Arc::new(AtomicBool::new(false)));
+            *nodes = ctx.tree.node_count();
+        }
+
+        let [ordered, unordered] = nodes;
+        assert!(
+            ordered < unordered,
+            "Ordered search expanded {} nodes, unordered {}",
+            ordered,
+            unordered
+        );
+    }
+
+    #[test]
+    fn test_search_stats() {
+        let result = search_to_depth(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            2,
+        );
+        let stats = result.stats.unwrap();
+        assert_eq!(stats.nodes, result.nodes);
+        assert!(stats.expanded_nodes > 0 && stats.expanded_nodes < stats.nodes);
+        assert!(stats.average_branching_factor() > 1.0);
+        assert!(stats.tt_probes > 0 && stats.tt_hits <= stats.tt_probes);
+
+        #[cfg(feature = "alpha_beta_soft_pruning")]
+        assert!(stats.beta_cutoffs > 0);
+    }
+
+    #[test]
+    fn test_uci_info_fields() {
+        let result = search_to_depth(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        );
+        assert!(result.seldepth >= result.depth);
+        assert!(result.hashfull > 0 && result.hashfull <= 1000);
+
+        // The millisecond count is truncated, hence the loose bounds
+        assert!(result.time_ms > 0, "Search too fast to measure nps");
+        let expected = result.nodes as f64 * 1000.0 / result.time_ms as f64;
+        let ratio = result.nps as f64 / expected;
+        assert!(
+            (0.5..=1.5).contains(&ratio),
+            "nps {} for {} nodes in {}ms",
+            result.nps,
+            result.nodes,
+            result.time_ms
+        );
+    }
+
+    /// Runs a full search on the AI thread (without stopping it early) and returns its result.
+    fn search_with(ai: &SimpleAi, board: &Board, depth: u16) -> AiResult {
+        try_search_with(ai, board, depth).unwrap()
+    }
+
+    fn try_search_with(ai: &SimpleAi, board: &Board, depth: u16) -> Option<AiResult> {
+        let limits = AiLimit {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        ai.start(board, limits, false);
+        while ai
+            .thread
+            .borrow()
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+        {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        ai.stop()
+    }
+
+    #[test]
+    fn test_transposition_table_survives_searches() {
+        let mut board = Board::default_position();
+        let ai = SimpleAi::default();
+        ai.set_position(&board, &[]);
+        let first = search_with(&ai, &board, 4);
+
+        // The positions two plies further along the principal variation were evaluated during the first
+        // search (other lines may have been cut before reaching them). Two plies deep nothing transposes, the
+        // hits can only come from the previous search
+        for mv in &first.pv[..2] {
+            mv.apply(&mut board);
+        }
+        let kept = search_with(&ai, &board, 2).stats.unwrap();
+        assert!(kept.tt_hit_rate() > 0.0);
+
+        ai.reset();
+        let cleared = search_with(&ai, &board, 2).stats.unwrap();
+        assert!(
+            kept.tt_hits > cleared.tt_hits,
+            "{} hits with the previous table, {} from scratch",
+            kept.tt_hits,
+            cleared.tt_hits
+        );
+    }
+
+    #[test]
+    fn test_set_position_scores_game_repetitions() {
+        // Both knights out and back: black taking its knight back repeats the starting position
+        let start = Board::default_position();
+        let mut board = start;
+        let history: Vec<Move> = ["g1f3", "g8f6", "f3g1"]
+            .iter()
+            .map(|uci| {
+                let mv = Move::from_uci(uci, &board).unwrap();
+                mv.apply(&mut board);
+                mv
+            })
+            .collect();
+        let repeats = |ai: &SimpleAi| {
+            search_with(ai, &board, 1);
+            let ctx = ai.ctx.borrow();
+            let root = ctx.as_ref().unwrap().tree.root();
+            let mut child = root.child();
+            while let Some(node) = child {
+                if node.r#move.unwrap().uci().to_string() == "f6g8" {
+                    return node.flags.contains(TerminalFlags::REPETITION);
+                }
+                child = node.next();
+            }
+            unreachable!("f6g8 is legal");
+        };
+
+        let ai = SimpleAi::default();
+        ai.set_position(&start, &history);
+        assert!(repeats(&ai));
+
+        // The game is forgotten on reset
+        ai.reset();
+        assert!(!repeats(&ai));
+    }
+
+    #[test]
+    fn test_reset_clears_search_state() {
+        // Positions two plies deep cannot transpose, a fresh table has no hit at that depth
+        let board = Board::default_position();
+        let ai = SimpleAi::default();
+        search_with(&ai, &board, 3);
+        let warm = search_with(&ai, &board, 2).stats.unwrap();
+        assert!(warm.tt_hits > 0);
+
+        // ucinewgame
+        ai.reset();
+        assert!(ai.ctx.borrow().is_none());
+        let fresh = search_with(&ai, &board, 2).stats.unwrap();
+        assert_eq!(fresh.tt_hits, 0);
+
+        // An infinite (pondering) search in flight is stopped
+        ai.start(
+            &board,
+            AiLimit {
+                ponder: true,
+                ..Default::default()
+            },
+            false,
+        );
+        ai.reset();
+        assert!(ai.thread.borrow().is_none());
+        assert!(ai.ctx.borrow().is_none());
+    }
+
+    #[test]
+    fn test_ponderhit_keeps_the_tree() {
+        // Rd8+ Rxd8 Rxd8#, the opponent is expected to take back on d8
+        let board = Board::from_fen("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").unwrap();
+        let ai = SimpleAi::default();
+        let first = search_with(&ai, &board, 4);
+        assert_eq!(first.mate_in(), Some(2));
+
+        let mut after = board;
+        first.pv[0].apply(&mut after);
+        let limits = AiLimit {
+            depth: Some(1),
+            ..Default::default()
+        };
+        ai.ponder(&after, first.pv[1], limits, false);
+
+        // The limits only apply from the ponderhit on
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(!ai.thread.borrow().as_ref().unwrap().is_finished());
+        ai.ponderhit();
+        while !ai.thread.borrow().as_ref().unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let result = ai.stop().unwrap();
+        assert_eq!(result.best_move, first.pv[2]);
+        assert_eq!(result.mate_in(), Some(1));
+
+        // Without searching, the kept tree already knows the mate, one move closer
+        let mut ctx = new_ctx(&board);
+        ctx.run(
+            AiLimit {
+                depth: Some(4),
+                ..Default::default()
+            },
+            false,
+            Arc::new(AtomicBool::new(false)),
+        );
+        let mut position = after;
+        first.pv[1].apply(&mut position);
+        let ctx = ctx.follow(&position);
+        assert_eq!(ctx.tree.root().board, position);
+        assert!(ctx.tree.root().r#move.is_none());
+        let kept = ctx.derive_results().unwrap();
+        assert_eq!(kept.pv, first.pv[2..]);
+        assert_eq!(kept.mate_in(), Some(1));
+
+        // Positions the search did not go through start from a new tree
+        let ctx = ctx.follow(&board);
+        assert_eq!(ctx.tree.node_count(), 1);
+    }
+
+    #[test]
+    fn test_node_limit_within_the_root_expansion() {
+        // The root and its 20 children make 21 nodes, the root expansion goes past the limit
+        let board = Board::default_position();
+        for node_limit in [1, 10, 20] {
+            let mut ctx = new_ctx(&board);
+            let limits = AiLimit {
+                nodes: Some(node_limit),
+                ..Default::default()
+            };
+            ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+            assert_eq!(ctx.tree.node_count() as u64, 1 + perft(&board, 1));
+            let mut moves = Vec::new();
+            generate_moves(&board, &mut moves, &mut false);
+            let result = ctx.derive_results().unwrap();
+            assert!(moves.contains(&result.best_move), "{}", node_limit);
+        }
+    }
+
+    #[test]
+    fn test_ponder_after_a_stop_in_an_expansion() {
+        // The root and its 20 children make 21 nodes, the limit falls in the expansion of the first move searched
+        let board = Board::default_position();
+        let ai = SimpleAi::default();
+        ai.start(
+            &board,
+            AiLimit {
+                nodes: Some(30),
+                ..Default::default()
+            },
+            false,
+        );
+        while !ai.thread.borrow().as_ref().unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        ai.stop();
+        let expected_move = {
+            let ctx = ai.ctx.borrow();
+            let tree = &ctx.as_ref().unwrap().tree;
+            assert_eq!(tree.node_count(), 21);
+            tree.root().child().unwrap().r#move.unwrap()
+        };
+
+        // The kept tree is searched again from that move, which gets all its replies
+        ai.ponder(
+            &board,
+            expected_move,
+            AiLimit {
+                depth: Some(1),
+                ..Default::default()
+            },
+            false,
+        );
+        ai.ponderhit();
+        while !ai.thread.borrow().as_ref().unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let result = ai.stop().unwrap();
+        let mut position = board;
+        expected_move.apply(&mut position);
+        assert_eq!(result.nodes as u64, 1 + perft(&position, 1));
+    }
+
+    #[test]
+    fn test_ponderhit_then_stop() {
+        let board = Board::default_position();
+        let ai = SimpleAi::default();
+        let first = search_with(&ai, &board, 3);
+
+        // An unbounded ponder search, interrupted right after the ponderhit
+        let mut after = board;
+        first.pv[0].apply(&mut after);
+        ai.ponder(&after, first.pv[1], AiLimit::default(), false);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        ai.ponderhit();
+        let result = ai.stop().unwrap();
+
+        // The principal variation is played from the position after the expected move
+        let mut position = after;
+        first.pv[1].apply(&mut position);
+        for mv in &result.pv {
+            let mut moves = Vec::new();
+            generate_moves(&position, &mut moves, &mut false);
+            assert!(moves.contains(mv), "{} in {}", mv.uci(), position.fen());
+            mv.apply(&mut position);
+        }
+        assert_eq!(result.best_move, result.pv[0]);
+    }
+
+    #[test]
+    fn test_search_panic_is_contained() {
+        // The root is evaluated when the search starts, the first position evaluated by the search thread
+        // makes it panic
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let evaluator_calls = calls.clone();
+        let ai = SimpleAi::with_evaluator(Box::new(move |board: &Board| {
+            if evaluator_calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 1 {
+                panic!("malformed board");
+            }
+            evaluate(board)
+        }));
+
+        let board = Board::default_position();
+        assert!(try_search_with(&ai, &board, 2).is_none());
+        assert!(ai.thread.borrow().is_none());
+
+        // The engine is still usable afterwards
+        assert!(calls.load(std::sync::atomic::Ordering::SeqCst) >= 2);
+        assert!(try_search_with(&ai, &board, 2).is_some());
+
+        // A printed search reports the panic and its best move itself, it may never be joined
+        calls.store(0, std::sync::atomic::Ordering::SeqCst);
+        let limits = AiLimit {
+            depth: Some(2),
+            ..Default::default()
+        };
+        ai.start(&board, limits, true);
+        while !ai.thread.borrow().as_ref().unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(ai.stop_signal.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(ai.stop().is_none());
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        let ai = SimpleAi::with_evaluator(Box::new(|_: &Board| 0.0));
+        assert!(ai.evaluator.is_stateless()); // Nothing to notify, the search does not lock it
+
+        // Without any evaluation, only the mates found by the search tell the moves apart
+        let result = search_with(&ai, &Board::default_position(), 2);
+        assert_eq!(result.score, 0.0);
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        let result = search_with(&ai, &board, 2);
+        assert_eq!(result.best_move.uci().to_string(), "d1d8");
+        assert!(result.is_forced_mate());
+    }
+
+    #[cfg(feature = "lmr")]
+    fn search_with_reductions(board: &Board, depth: u16, reductions: bool) -> AiResult {
+        let mut ctx = new_ctx(board);
+        ctx.reductions = reductions;
+        let limits = AiLimit {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+        ctx.derive_results().unwrap()
+    }
+
+    #[cfg(feature = "lmr")]
+    #[test]
+    fn test_lmr_keeps_tactics() {
+        // The key move is a quiet move or a sacrifice found late in the move ordering, the re-search of the
+        // backtracking must bring it back
+        for fen in [
+            "2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "6k1/5ppp/8/8/8/8/5PPP/1R4K1 w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let reduced = search_with_reductions(&board, 4, true);
+            let full = search_with_reductions(&board, 4, false);
+            assert_eq!(reduced.best_move, full.best_move, "{}", fen);
+            assert_eq!(reduced.mate_in(), full.mate_in(), "{}", fen);
+        }
+    }
+
+    #[cfg(feature = "lmr")]
+    #[test]
+    fn test_lmr_reduces_quiet_positions() {
+        // Quiet positions, the start position and a slow Italian game: the reductions must not change the best
+        // move whatever the evaluation
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let reduced = search_with_reductions(&board, 3, true);
+            let full = search_with_reductions(&board, 3, false);
+            assert_eq!(reduced.best_move, full.best_move, "{}", fen);
+            assert!(
+                reduced.nodes < full.nodes,
+                "{}: {} nodes with reductions, {} without",
+                fen,
+                reduced.nodes,
+                full.nodes
+            );
+        }
+    }
+
+    /// Material count updated from the moves notified by the search rather than recomputed, checked against a
+    /// count from scratch whenever it is used.
+    struct IncrementalMaterial {
+        followed: Vec<(u64, i32)>, // Zobrist key and material (in centipawns) of the positions down the path
+        evaluations: Arc<std::sync::atomic::AtomicUsize>,
+        errors: Arc<std::sync::atomic::AtomicUsize>, // Evaluations out of sync or wrong, unbalanced unmakes
+    }
+
+    impl Evaluator for IncrementalMaterial {
+        fn is_incremental(&self) -> bool {
+            true
+        }
+
+        fn evaluate(&self, board: &Board) -> f32 {
+            let (zobrist, material) = *self.followed.last().unwrap();
+            let material = material as f32 / 100.0;
+            self.evaluations
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if zobrist != board.zobrist || material != crate::eval::simple_evaluation(board) {
+                self.errors
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            material
+        }
+
+        fn on_position(&mut self, board: &Board) {
+            let material = (crate::eval::simple_evaluation(board) * 100.0).round() as i32;
+            self.followed = vec![(board.zobrist, material)];
+        }
+
+        fn on_make(&mut self, board: &Board, mv: Move) {
+            let captured = if mv.flags.contains(MoveFlags::EN_PASSANT) {
+                Piece::Pawn.value()
+            } else if mv.flags.contains(MoveFlags::CASTLE) {
+                0 // The king "takes" its own rook
+            } else {
+                board.piece_at(mv.to).map_or(0, Piece::value)
+            };
+            let promoted = mv
+                .promotion
+                .map_or(0, |piece| piece.value() - Piece::Pawn.value());
+            let gain = (captured + promoted) * board.next_to_move().score_multiplier() as i32;
+
+            let mut after = *board;
+            mv.apply(&mut after);
+            let (_, material) = *self.followed.last().unwrap();
+            self.followed.push((after.zobrist, material + gain));
+        }
+
+        fn on_unmake(&mut self, board: &Board, _mv: Move) {
+            self.followed.pop();
+            if self
+                .followed
+                .last()
+                .is_none_or(|(zobrist, _)| *zobrist != board.zobrist)
+            {
+                self.errors
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_evaluator() {
+        // Captures, promotions, castling and en passant within a few plies
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let evaluations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let errors = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let evaluator = IncrementalMaterial {
+                followed: Vec::new(),
+                evaluations: evaluations.clone(),
+                errors: errors.clone(),
+            };
+            let mut ctx = SimpleAiCtx::new(
+                &board,
+                TranspositionTable::default(),
+                SharedEvaluator::new(Box::new(evaluator)),
+            );
+            let limits = AiLimit {
+                depth: Some(3),
+                ..Default::default()
+            };
+            assert!(ctx.evaluator.is_incremental());
+            ctx.run(limits, false, Arc::new(AtomicBool::new(false)));
+
+            // Every evaluation of the search was of the position followed, and the search is back to the root
+            assert!(evaluations.load(std::sync::atomic::Ordering::SeqCst) > 1000);
+            assert_eq!(
+                errors.load(std::sync::atomic::Ordering::SeqCst),
+                0,
+                "{}",
+                fen
+            );
+            assert_eq!(
+                ctx.evaluator.evaluate(&board),
+                crate::eval::simple_evaluation(&board)
+            );
+        }
     }
 }
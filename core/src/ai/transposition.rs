@@ -0,0 +1,91 @@
+/// Fixed-size hash table caching the static evaluation of positions, indexed by their zobrist key. The table
+/// is meant to outlive a single search: the positions reached after a couple of moves were most likely
+/// already evaluated while searching the previous move of the game.
+///
+/// Entries are always replaced on collision, the full key is kept to tell positions sharing a slot apart.
+pub struct TranspositionTable {
+    entries: Vec<Option<(u64, f32)>>,
+}
+
+impl TranspositionTable {
+    /// Default number of slots (about 4MB of memory).
+    pub const DEFAULT_CAPACITY: usize = 1 << 18;
+
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "Transposition table capacity must be positive"
+        );
+        TranspositionTable {
+            entries: vec![None; capacity],
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// The cached evaluation of the position with the given key, if any.
+    pub fn probe(&self, key: u64) -> Option<f32> {
+        match self.entries[self.slot(key)] {
+            Some((entry_key, score)) if entry_key == key => Some(score),
+            _ => None,
+        }
+    }
+
+    pub fn store(&mut self, key: u64, score: f32) {
+        let slot = self.slot(key);
+        self.entries[slot] = Some((key, score));
+    }
+
+    /// Number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Occupancy in permille for the UCI `hashfull` field, estimated on the first thousand slots.
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample.iter().filter(|entry| entry.is_some()).count();
+        (used * 1000 / sample.len()) as u16
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_and_replace() {
+        let mut table = TranspositionTable::new(16);
+        assert!(table.is_empty());
+        assert_eq!(table.probe(3), None);
+
+        table.store(3, 1.5);
+        assert_eq!(table.probe(3), Some(1.5));
+
+        // Same slot, different key: the entry is replaced and the old key no longer matches
+        table.store(19, -2.0);
+        assert_eq!(table.probe(3), None);
+        assert_eq!(table.probe(19), Some(-2.0));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.hashfull(), 62);
+
+        table.clear();
+        assert!(table.is_empty());
+    }
+}
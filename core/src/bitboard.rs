@@ -1,5 +1,9 @@
 use rand::rand_core::block;
 
+use std::sync::OnceLock;
+
+use strum::{EnumIter, FromRepr};
+
 use crate::board::Color;
 
 /// Bitboard representation of a chess position. Each bit represents a square on the chessboard, with the
@@ -187,6 +191,9 @@ impl Bitboard {
     pub const RANK_7: u64 = 0x00FF000000000000;
     pub const RANK_8: u64 = 0xFF00000000000000;
 
+    pub const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+    pub const DARK_SQUARES: u64 = 0xAA55AA55AA55AA55;
+
     pub const AVOID_WRAP: [u64; 8] = [
         0xfefefefefefefe00,
         0xfefefefefefefefe,
@@ -200,6 +207,31 @@ impl Bitboard {
 
     pub const SHIFT: [u32; 8] = [9, 1, 57, 56, 55, 63, 7, 8];
 
+    /// The rank the pieces of the given color start on (rank 1 for white, rank 8 for black).
+    pub const fn back_rank(color: Color) -> Self {
+        match color {
+            Color::White => Bitboard(Bitboard::RANK_1),
+            Color::Black => Bitboard(Bitboard::RANK_8),
+        }
+    }
+
+    /// The rank on which the pawns of the given color promote (rank 8 for white, rank 1 for black).
+    pub const fn promotion_rank(color: Color) -> Self {
+        match color {
+            Color::White => Bitboard(Bitboard::RANK_8),
+            Color::Black => Bitboard(Bitboard::RANK_1),
+        }
+    }
+
+    /// The rank a pawn of the given color reaches with the first step of a double push (rank 3 for white,
+    /// rank 6 for black), i.e. the single pushes landing there may be followed by a second step.
+    pub const fn double_push_rank(color: Color) -> Self {
+        match color {
+            Color::White => Bitboard(Bitboard::RANK_3),
+            Color::Black => Bitboard(Bitboard::RANK_6),
+        }
+    }
+
     /// Find the pawn connected mask for a bitboard (basically expand the bitboard in the north and south directions based on color).
     pub const fn connected_mask(self, color: Color) -> Self {
         match color {
@@ -221,6 +253,41 @@ impl Bitboard {
             | ((self.0 & !Bitboard::FILE_H) >> 7) | ((self.0 & !Bitboard::FILE_A) << 7), // NorthWest and SouthEast
         )
     }
+
+    /// Fill every file containing at least one bit of the bitboard (north and south fill).
+    pub const fn file_fill(self) -> Self {
+        let mut fill = self.0;
+        fill |= fill << 8;
+        fill |= fill << 16;
+        fill |= fill << 32;
+        fill |= fill >> 8;
+        fill |= fill >> 16;
+        fill |= fill >> 32;
+        Bitboard(fill)
+    }
+
+    /// Files that do not contain any bit of the given bitboard (e.g. the open files when given all the pawns).
+    pub const fn open_files(pawns: Bitboard) -> Self {
+        Bitboard(!pawns.file_fill().0)
+    }
+
+    /// Flip the bitboard vertically, rank 1 becomes rank 8 and so on (a1 <-> a8).
+    pub const fn flip_vertical(self) -> Self {
+        Bitboard(self.0.swap_bytes())
+    }
+
+    /// Mirror the bitboard horizontally, the files are reversed within each rank (a1 <-> h1).
+    pub const fn mirror_horizontal(self) -> Self {
+        const K1: u64 = 0x5555555555555555;
+        const K2: u64 = 0x3333333333333333;
+        const K4: u64 = 0x0F0F0F0F0F0F0F0F;
+        let mut x = self.0;
+        x = ((x >> 1) & K1) | ((x & K1) << 1);
+        x = ((x >> 2) & K2) | ((x & K2) << 2);
+        x = ((x >> 4) & K4) | ((x & K4) << 4);
+        Bitboard(x)
+    }
+
     /// Generate an empty bitboard (i.e., a bitboard with all bits set to 0).
     pub const fn empty() -> Self {
         Bitboard(0)
@@ -236,7 +303,9 @@ impl Bitboard {
         self.0 == 0
     }
 
-    /// Shifts the bitboard in the specified direction, while preventing wrap-around across the edges of the board.
+    /// Shifts the bitboard by one square in the specified direction, bits leaving the board (including
+    /// across the A and H files) are dropped. This is the single entry point for one-step shifts, the
+    /// `shift_<direction>` methods being shortcuts for it.
     pub const fn shift_one(self, direction: Direction) -> Self {
         let direction = direction as u8 as usize;
         Bitboard(self.0.rotate_left(Bitboard::SHIFT[direction]) & Bitboard::AVOID_WRAP[direction])
@@ -278,6 +347,42 @@ impl Bitboard {
             | self.sliding_attack(occ, Direction::SouthWest)
     }
 
+    /// Rook attacks from a single square, looked up in the global magic tables.
+    pub fn rook_attacks(square: u8, occ: Bitboard) -> Self {
+        #[cfg(test)]
+        if tests::LEGACY_SLIDERS.get() {
+            return Bitboard(1 << square).rook_raycast(occ);
+        }
+        crate::magic::Magic::global().rook_raycast(square, occ)
+    }
+
+    /// Bishop attacks from a single square, looked up in the global magic tables.
+    pub fn bishop_attacks(square: u8, occ: Bitboard) -> Self {
+        #[cfg(test)]
+        if tests::LEGACY_SLIDERS.get() {
+            return Bitboard(1 << square).bishop_raycast(occ);
+        }
+        crate::magic::Magic::global().bishop_raycast(square, occ)
+    }
+
+    /// The squares from `a` to `b` (both included), which are expected to be on the same rank.
+    pub const fn rank_span(a: u8, b: u8) -> Self {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        Bitboard((u64::MAX >> (63 - high)) & (u64::MAX << low))
+    }
+
+    /// The squares strictly between `a` and `b`, empty if they are not on a common rank, file or diagonal
+    /// (or are the same square).
+    pub fn between(a: u8, b: u8) -> Self {
+        LineTables::global().between[a as usize][b as usize]
+    }
+
+    /// The full rank, file or diagonal going through `a` and `b` (both included), empty if they are not aligned
+    /// (or are the same square).
+    pub fn line(a: u8, b: u8) -> Self {
+        LineTables::global().line[a as usize][b as usize]
+    }
+
     pub fn rook_raycast(self, occ: Bitboard) -> Self {
         self.sliding_attack(occ, Direction::North)
             | self.sliding_attack(occ, Direction::East)
@@ -285,36 +390,38 @@ impl Bitboard {
             | self.sliding_attack(occ, Direction::West)
     }
 
-    pub fn shift_south(self) -> Self {
-        Bitboard(self.0 >> 8)
+    // Directional shortcuts for `shift_one`, which is the single implementation of one-step shifts
+
+    pub const fn shift_south(self) -> Self {
+        self.shift_one(Direction::South)
     }
 
-    pub fn shift_north(self) -> Self {
-        Bitboard(self.0 << 8)
+    pub const fn shift_north(self) -> Self {
+        self.shift_one(Direction::North)
     }
 
     pub const fn shift_east(self) -> Self {
-        Bitboard((self.0 << 1) & !Bitboard::FILE_A)
+        self.shift_one(Direction::East)
     }
 
     pub const fn shift_west(self) -> Self {
-        Bitboard((self.0 >> 1) & !Bitboard::FILE_H)
+        self.shift_one(Direction::West)
     }
 
     pub const fn shift_north_east(self) -> Self {
-        Bitboard((self.0 << 9) & !Bitboard::FILE_A)
+        self.shift_one(Direction::NorthEast)
     }
 
     pub const fn shift_north_west(self) -> Self {
-        Bitboard((self.0 << 7) & !Bitboard::FILE_H)
+        self.shift_one(Direction::NorthWest)
     }
 
     pub const fn shift_south_east(self) -> Self {
-        Bitboard((self.0 >> 7) & !Bitboard::FILE_A)
+        self.shift_one(Direction::SouthEast)
     }
 
     pub const fn shift_south_west(self) -> Self {
-        Bitboard((self.0 >> 9) & !Bitboard::FILE_H)
+        self.shift_one(Direction::SouthWest)
     }
 
     pub fn count_ones(&self) -> u32 {
@@ -366,14 +473,17 @@ impl Bitboard {
     }
 
     pub fn get(&self, index: u8) -> bool {
+        debug_assert!(index < 64, "Bitboard index {} out of range", index);
         (self.0 & (1 << index)) != 0
     }
 
     pub fn set(&mut self, index: u8) {
+        debug_assert!(index < 64, "Bitboard index {} out of range", index);
         self.0 |= 1 << index;
     }
 
     pub fn unset(&mut self, index: u8) {
+        debug_assert!(index < 64, "Bitboard index {} out of range", index);
         self.0 &= !(1 << index);
     }
 
@@ -386,6 +496,206 @@ impl Bitboard {
     }
 }
 
+/// Tables of [`Bitboard::between`] and [`Bitboard::line`] for every pair of squares.
+struct LineTables {
+    between: [[Bitboard; 64]; 64],
+    line: [[Bitboard; 64]; 64],
+}
+
+static LINE_TABLES: OnceLock<Box<LineTables>> = OnceLock::new();
+
+impl LineTables {
+    /// The shared tables, generated on first use.
+    fn global() -> &'static LineTables {
+        LINE_TABLES.get_or_init(LineTables::generate)
+    }
+
+    fn generate() -> Box<LineTables> {
+        let mut tables = Box::new(LineTables {
+            between: [[Bitboard::empty(); 64]; 64],
+            line: [[Bitboard::empty(); 64]; 64],
+        });
+
+        for a in 0..64u8 {
+            let from_a = Bitboard(1 << a);
+            for b in 0..64u8 {
+                let from_b = Bitboard(1 << b);
+                if a == b {
+                    continue;
+                }
+
+                // Two squares are aligned when a slider on one of them attacks the other on an empty board
+                let raycast = if from_a.rook_raycast(Bitboard::empty()).get(b) {
+                    Bitboard::rook_raycast
+                } else if from_a.bishop_raycast(Bitboard::empty()).get(b) {
+                    Bitboard::bishop_raycast
+                } else {
+                    continue;
+                };
+
+                tables.between[a as usize][b as usize] =
+                    raycast(from_a, from_b) & raycast(from_b, from_a);
+                tables.line[a as usize][b as usize] = (raycast(from_a, Bitboard::empty())
+                    & raycast(from_b, Bitboard::empty()))
+                    | from_a
+                    | from_b;
+            }
+        }
+
+        tables
+    }
+}
+
+/// A file of the board, from a to h.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, FromRepr)]
+#[repr(u8)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+impl File {
+    /// The file of the given letter, either case.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            c @ 'a'..='h' => File::from_repr(c as u8 - b'a'),
+            _ => None,
+        }
+    }
+
+    /// The lowercase letter of the file.
+    pub fn to_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+
+    pub fn bitboard(self) -> Bitboard {
+        Bitboard(Bitboard::FILE[self as usize])
+    }
+}
+
+/// A rank of the board, from the first (white's back rank) to the eighth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, FromRepr)]
+#[repr(u8)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+impl Rank {
+    /// The rank of the given digit, from '1' to '8'.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '1'..='8' => Rank::from_repr(c as u8 - b'1'),
+            _ => None,
+        }
+    }
+
+    /// The digit of the rank.
+    pub fn to_char(self) -> char {
+        (b'1' + self as u8) as char
+    }
+
+    pub fn bitboard(self) -> Bitboard {
+        Bitboard(Bitboard::RANK[self as usize])
+    }
+}
+
+/// A square of the board, always in range: a1 is 0, b1 is 1 and h8 is 63, the indices used by the bitboards.
+/// The `u8`-based APIs remain, converting with [`Square::index`] and `TryFrom<u8>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    pub const fn new(file: File, rank: Rank) -> Self {
+        Square(rank as u8 * 8 + file as u8)
+    }
+
+    /// The square of the given index, `None` if it is out of range (such as [`Board::NO_EN_PASSANT_SQUARE`]).
+    ///
+    /// [`Board::NO_EN_PASSANT_SQUARE`]: crate::board::Board::NO_EN_PASSANT_SQUARE
+    pub const fn from_index(index: u8) -> Option<Self> {
+        if index < 64 {
+            Some(Square(index))
+        } else {
+            None
+        }
+    }
+
+    /// Parse a square in algebraic notation, such as `e4`.
+    pub fn from_algebraic(algebraic: &str) -> Option<Self> {
+        let mut chars = algebraic.chars();
+        let file = File::from_char(chars.next()?)?;
+        let rank = Rank::from_char(chars.next()?)?;
+        if chars.next().is_some() {
+            return None;
+        }
+        Some(Square::new(file, rank))
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn file(self) -> File {
+        // The index is always in range, so are the file and the rank
+        File::from_repr(self.0 % 8).unwrap()
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::from_repr(self.0 / 8).unwrap()
+    }
+
+    pub const fn bitboard(self) -> Bitboard {
+        Bitboard(1 << self.0)
+    }
+}
+
+impl std::fmt::Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file().to_char(), self.rank().to_char())
+    }
+}
+
+impl std::str::FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Square::from_algebraic(s).ok_or_else(|| format!("Invalid square: {}", s))
+    }
+}
+
+impl TryFrom<u8> for Square {
+    type Error = String;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Square::from_index(index).ok_or_else(|| format!("Square index {} out of range", index))
+    }
+}
+
+impl From<Square> for u8 {
+    fn from(square: Square) -> Self {
+        square.0
+    }
+}
+
+impl From<Square> for Bitboard {
+    fn from(square: Square) -> Self {
+        square.bitboard()
+    }
+}
+
 pub fn square_to_algebraic(square: u8) -> String {
     let file = (square % 8) as u8;
     let rank = (square / 8) as u8;
@@ -393,15 +703,239 @@ pub fn square_to_algebraic(square: u8) -> String {
 }
 
 pub fn algebraic_to_square(algebraic: &str) -> Option<u8> {
-    if algebraic.len() != 2 {
-        return None;
+    Square::from_algebraic(algebraic).map(Square::index)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    thread_local! {
+        /// Makes `rook_attacks`/`bishop_attacks` use the iterative raycasts on the current thread, to check
+        /// the magic tables against them.
+        pub(crate) static LEGACY_SLIDERS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    }
+
+    #[test]
+    fn test_shift_one_matches_hand_written_masks() {
+        // Reference implementation of each direction, written with explicit shifts and file masks
+        let reference = |bitboard: u64, direction: Direction| match direction {
+            Direction::North => bitboard << 8,
+            Direction::South => bitboard >> 8,
+            Direction::East => (bitboard << 1) & !Bitboard::FILE_A,
+            Direction::West => (bitboard >> 1) & !Bitboard::FILE_H,
+            Direction::NorthEast => (bitboard << 9) & !Bitboard::FILE_A,
+            Direction::NorthWest => (bitboard << 7) & !Bitboard::FILE_H,
+            Direction::SouthEast => (bitboard >> 7) & !Bitboard::FILE_A,
+            Direction::SouthWest => (bitboard >> 9) & !Bitboard::FILE_H,
+        };
+
+        for square in 0..64 {
+            let bitboard = Bitboard(1 << square);
+            for direction in [
+                Direction::NorthEast,
+                Direction::East,
+                Direction::SouthEast,
+                Direction::South,
+                Direction::SouthWest,
+                Direction::West,
+                Direction::NorthWest,
+                Direction::North,
+            ] {
+                assert_eq!(
+                    bitboard.shift_one(direction),
+                    Bitboard(reference(bitboard.0, direction)),
+                    "{:?} from square {}",
+                    direction,
+                    square
+                );
+            }
+            assert_eq!(
+                bitboard.shift_one(Direction::NorthEast),
+                bitboard.shift_north_east()
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_rank_masks() {
+        assert_eq!(Bitboard::back_rank(Color::White), Bitboard(0xFF));
+        assert_eq!(Bitboard::back_rank(Color::Black), Bitboard(0xFF << 56));
+        assert_eq!(Bitboard::promotion_rank(Color::White), Bitboard(0xFF << 56));
+        assert_eq!(Bitboard::promotion_rank(Color::Black), Bitboard(0xFF));
+        assert_eq!(
+            Bitboard::double_push_rank(Color::White),
+            Bitboard(0xFF << 16)
+        );
+        assert_eq!(
+            Bitboard::double_push_rank(Color::Black),
+            Bitboard(0xFF << 40)
+        );
+
+        // Each color's masks are the other's, flipped
+        for color in [Color::White, Color::Black] {
+            let other = color.opposite();
+            assert_eq!(
+                Bitboard::back_rank(color).flip_vertical(),
+                Bitboard::back_rank(other)
+            );
+            assert_eq!(Bitboard::promotion_rank(color), Bitboard::back_rank(other));
+            assert_eq!(
+                Bitboard::double_push_rank(color).flip_vertical(),
+                Bitboard::double_push_rank(other)
+            );
+        }
     }
-    let file = algebraic.chars().nth(0)?.to_ascii_lowercase();
-    let rank = algebraic.chars().nth(1)?.to_digit(10)? as u8;
 
-    if file < 'a' || file > 'h' || rank < 1 || rank > 8 {
-        return None;
+    #[test]
+    fn test_flip_vertical_and_mirror_horizontal() {
+        let a1 = Bitboard(1);
+        assert_eq!(a1.flip_vertical(), Bitboard(1 << 56));
+        assert_eq!(a1.mirror_horizontal(), Bitboard(1 << 7));
+        assert_eq!(
+            Bitboard(Bitboard::RANK_2).flip_vertical(),
+            Bitboard(Bitboard::RANK_7)
+        );
+        assert_eq!(
+            Bitboard(Bitboard::FILE_B).mirror_horizontal(),
+            Bitboard(Bitboard::FILE_G)
+        );
+
+        let pattern = Bitboard(0x0123_4567_89AB_CDEF);
+        assert_eq!(pattern.flip_vertical().flip_vertical(), pattern);
+        assert_eq!(pattern.mirror_horizontal().mirror_horizontal(), pattern);
+        assert_eq!(
+            pattern.flip_vertical().mirror_horizontal(),
+            Bitboard(pattern.0.reverse_bits())
+        );
     }
 
-    Some((rank - 1) * 8 + (file as u8 - b'a'))
+    #[test]
+    fn test_between_and_line() {
+        let square = |name: &str| algebraic_to_square(name).unwrap();
+        let squares = |names: &[&str]| {
+            names.iter().fold(Bitboard::empty(), |bitboard, name| {
+                bitboard | Bitboard(1 << square(name))
+            })
+        };
+
+        // Same rank and same file
+        assert_eq!(
+            Bitboard::between(square("a1"), square("e1")),
+            squares(&["b1", "c1", "d1"])
+        );
+        assert_eq!(
+            Bitboard::line(square("a1"), square("e1")),
+            Bitboard(Bitboard::RANK_1)
+        );
+        assert_eq!(
+            Bitboard::between(square("d7"), square("d3")),
+            squares(&["d4", "d5", "d6"])
+        );
+        assert_eq!(
+            Bitboard::line(square("d7"), square("d3")),
+            Bitboard(Bitboard::FILE_D)
+        );
+
+        // Diagonals, in both orders
+        assert_eq!(
+            Bitboard::between(square("b2"), square("f6")),
+            squares(&["c3", "d4", "e5"])
+        );
+        assert_eq!(
+            Bitboard::between(square("f6"), square("b2")),
+            squares(&["c3", "d4", "e5"])
+        );
+        assert_eq!(
+            Bitboard::line(square("b2"), square("f6")),
+            squares(&["a1", "b2", "c3", "d4", "e5", "f6", "g7", "h8"])
+        );
+        assert_eq!(
+            Bitboard::line(square("g2"), square("f3")),
+            squares(&["h1", "g2", "f3", "e4", "d5", "c6", "b7", "a8"])
+        );
+
+        // Adjacent squares have nothing in between, but still a line
+        assert_eq!(
+            Bitboard::between(square("e4"), square("f5")),
+            Bitboard::empty()
+        );
+        assert_eq!(
+            Bitboard::line(square("e4"), square("f5")),
+            squares(&["b1", "c2", "d3", "e4", "f5", "g6", "h7"])
+        );
+
+        // Not aligned (a knight jump, wrapping around the board) or the same square
+        for (a, b) in [("e4", "f6"), ("h1", "a2"), ("a1", "b3"), ("c3", "c3")] {
+            assert_eq!(Bitboard::between(square(a), square(b)), Bitboard::empty());
+            assert_eq!(Bitboard::line(square(a), square(b)), Bitboard::empty());
+        }
+    }
+
+    #[test]
+    fn test_square_conversions() {
+        let e4 = Square::new(File::E, Rank::Fourth);
+        assert_eq!(e4.index(), 28);
+        assert_eq!(e4.file(), File::E);
+        assert_eq!(e4.rank(), Rank::Fourth);
+        assert_eq!(e4.to_string(), "e4");
+        assert_eq!(e4.bitboard(), Bitboard(Bitboard::FILE_E & Bitboard::RANK_4));
+        assert_eq!(Square::from_algebraic("e4"), Some(e4));
+        assert_eq!(Square::from_algebraic("E4"), Some(e4));
+        assert_eq!("e4".parse::<Square>(), Ok(e4));
+        assert_eq!(u8::from(e4), 28);
+        assert_eq!(Square::try_from(28), Ok(e4));
+
+        // Every index goes through the file and rank and back, and matches the algebraic helpers
+        for index in 0..64 {
+            let square = Square::from_index(index).unwrap();
+            assert_eq!(Square::new(square.file(), square.rank()), square);
+            assert_eq!(square.to_string(), square_to_algebraic(index));
+            assert_eq!(algebraic_to_square(&square.to_string()), Some(index));
+        }
+        assert_eq!(Square::new(File::A, Rank::First).index(), 0);
+        assert_eq!(Square::new(File::H, Rank::Eighth).index(), 63);
+    }
+
+    #[test]
+    fn test_square_bounds() {
+        assert_eq!(Square::from_index(64), None);
+        assert_eq!(Square::from_index(u8::MAX), None);
+        assert!(Square::try_from(64).is_err());
+        for invalid in ["", "e", "i4", "e0", "e9", "e44", "4e", "-1"] {
+            assert_eq!(Square::from_algebraic(invalid), None, "{:?}", invalid);
+            assert!(invalid.parse::<Square>().is_err());
+        }
+        assert_eq!(File::from_char('i'), None);
+        assert_eq!(Rank::from_char('0'), None);
+        assert_eq!(File::from_char('C'), Some(File::C));
+        assert_eq!(Rank::Eighth.bitboard(), Bitboard(Bitboard::RANK_8));
+    }
+
+    #[test]
+    fn test_file_fill() {
+        assert_eq!(Bitboard(1 << 12).file_fill(), Bitboard(Bitboard::FILE_E));
+        assert_eq!(
+            Bitboard((1 << 0) | (1 << 63)).file_fill(),
+            Bitboard(Bitboard::FILE_A | Bitboard::FILE_H)
+        );
+        assert_eq!(
+            Bitboard::open_files(Bitboard(Bitboard::RANK_2 & !Bitboard::FILE_D)),
+            Bitboard(Bitboard::FILE_D)
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "out of range")]
+    fn test_get_out_of_range_panics_in_debug() {
+        Bitboard::empty().get(64);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "out of range")]
+    fn test_set_out_of_range_panics_in_debug() {
+        Bitboard::empty().set(64);
+    }
 }
@@ -1,4 +1,7 @@
-use crate::bitboard::{Bitboard, square_to_algebraic};
+use crate::{
+    bitboard::{Bitboard, Square, algebraic_to_square, square_to_algebraic},
+    zobrist::ZOBRIST,
+};
 use bitflags::bitflags;
 use strum::{EnumIter, FromRepr, IntoEnumIterator};
 
@@ -40,38 +43,83 @@ pub struct Board {
     /// Flags representing the state of the board, such as which player's turn it is and castling rights.
     pub flags: BoardFlags,
 
-    /// The file index (0-7) of the en passant target square, or any value outside that range
-    /// if there is no en passant target square. For example, if a white pawn moves from e2 to e4,
-    /// the en passant target square is e3, which corresponds to file index 4 (since files are
-    /// indexed from 0 for 'a' to 7 for 'h').
+    /// The en passant target square (0-63), or [`Board::NO_EN_PASSANT_SQUARE`] if there is none. For
+    /// example, if a white pawn moves from e2 to e4, the en passant target square is e3 (square 20).
     pub en_passant_square: u8,
+
+    /// Zobrist key of the position, maintained incrementally as moves are applied (see [`crate::zobrist`]).
+    pub zobrist: u64,
+
+    /// Number of halfmoves since the last capture or pawn move, used for the fifty-move rule.
+    pub halfmove_clock: u16,
+
+    /// Initial squares of the castling rooks, indexed by color then by castling side (see
+    /// [`Board::castling_rook_square`]). These are the corners of the board in standard chess, while they
+    /// depend on the starting position in Chess960.
+    pub castling_rooks: [[u8; 2]; 2],
 }
 
 impl Board {
     pub const DEFAULT_POSITION_FEN: &'static str =
         "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+    /// Value of `en_passant_square` when there is no en passant target square.
+    pub const NO_EN_PASSANT_SQUARE: u8 = 64;
+
+    /// Value of `castling_rooks` in standard chess: h1, a1, h8 and a8.
+    pub const STANDARD_CASTLING_ROOKS: [[u8; 2]; 2] = [[7, 0], [63, 56]];
+
     pub fn verify(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Same as [`Board::verify`], with the reason why the board is invalid: overlapping pieces, a side
+    /// without exactly one king, or pawns on the first or last rank.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_bitboards()?;
+
+        // The move generation relies on each side having its king
+        for (king, color) in [(Piece::WhiteKing, "white"), (Piece::BlackKing, "black")] {
+            let count = self.piece_count(king);
+            if count != 1 {
+                return Err(format!(
+                    "expected exactly one {} king, found {}",
+                    color, count
+                ));
+            }
+        }
+
+        if *self.get(Piece::Pawn) & Bitboard(Bitboard::RANK_1 | Bitboard::RANK_8)
+            != Bitboard::empty()
+        {
+            return Err("pawns on the first or last rank".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Consistency of the bitboards with each other, regardless of whether the position could occur in a game.
+    fn validate_bitboards(&self) -> Result<(), String> {
         // Check no collision between board
         let mut a = Bitboard::empty();
         for bitboard in &self.bitboards {
             if (a & *bitboard).0 != 0 {
-                return false;
+                return Err("overlapping pieces".to_string());
             }
             a |= *bitboard;
         }
 
         // Check occupied is correct
         if a != self.occupied {
-            return false;
+            return Err("occupied squares out of sync with the pieces".to_string());
         }
 
         // Check white is a subset of occupied
         if (self.white & self.occupied) != self.white {
-            return false;
+            return Err("white pieces on empty squares".to_string());
         }
 
-        true
+        Ok(())
     }
 
     pub const fn empty() -> Self {
@@ -80,12 +128,104 @@ impl Board {
             white: Bitboard::empty(),
             occupied: Bitboard::empty(),
             flags: BoardFlags::empty(),
-            en_passant_square: 8,
+            en_passant_square: Board::NO_EN_PASSANT_SQUARE,
+            zobrist: 0,
+            halfmove_clock: 0,
+            castling_rooks: Board::STANDARD_CASTLING_ROOKS,
         }
     }
 
+    /// Computes the Zobrist key of the position from scratch.
+    pub fn compute_zobrist(&self) -> u64 {
+        let mut key = ZOBRIST.castling(self.flags)
+            ^ ZOBRIST.en_passant(self.en_passant_square)
+            ^ ZOBRIST.side_to_move(self.flags);
+
+        for piece in Piece::colorless_iter() {
+            for square in self.get(piece).scan() {
+                let color = Color::from_boolean_is_white(self.white.get(square));
+                key ^= ZOBRIST.piece(piece.with_color(color), square);
+            }
+        }
+
+        key
+    }
+
+    /// Parse a FEN, leniently: castling rights without the king and the rook on their initial squares are
+    /// dropped rather than rejected (which [`Board::from_fen_strict`] does), and the clocks may be omitted.
+    /// Chess960 positions give their castling rights as the files of the rooks (Shredder-FEN), see
+    /// [`SquareCentricBoard::castling_right_from_fen_char`].
     pub fn from_fen(fen: &str) -> Result<Self, String> {
-        SquareCentricBoard::parse_fen(fen).map(|square_centric| square_centric.into())
+        let board: Board = SquareCentricBoard::parse_fen(fen)?.into();
+        board
+            .validate()
+            .map_err(|reason| format!("Invalid FEN: {}", reason))?;
+        Ok(board)
+    }
+
+    /// Parse a FEN like [`Board::from_fen`], but reject what the lenient parser lets through: all six fields
+    /// are required (with a fullmove number of at least 1), every castling right must match a king and a rook
+    /// on their initial squares, and the en passant square must sit behind an enemy pawn that just made a
+    /// double move.
+    pub fn from_fen_strict(fen: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(format!(
+                "Invalid FEN: expected 6 fields, got {}",
+                fields.len()
+            ));
+        }
+        let board = Self::from_fen(fen)?;
+        let has = |piece: Piece, color: Color, square: u8| {
+            board.get(piece).get(square) && board.color_bitboard(color).get(square)
+        };
+
+        match fields[5].parse::<u32>() {
+            Ok(fullmove) if fullmove >= 1 => {}
+            _ => {
+                return Err(format!(
+                    "Invalid FEN: invalid fullmove number '{}'",
+                    fields[5]
+                ));
+            }
+        }
+
+        let castling = fields[2];
+        if castling != "-" {
+            for (index, c) in castling.char_indices() {
+                if castling[..index].contains(c) {
+                    return Err(format!("Invalid FEN: castling right '{}' is repeated", c));
+                }
+            }
+            let square_centric = board.square_centric();
+            for c in castling.chars() {
+                if square_centric.castling_right_from_fen_char(c).is_none() {
+                    return Err(format!(
+                        "Invalid FEN: castling right '{}' without the king and rook on their initial squares",
+                        c
+                    ));
+                }
+            }
+        }
+
+        if board.en_passant_square < 64 {
+            // The pawn that just moved stands in front of the target square, coming from the square behind it
+            let (pushed, origin) = match board.next_to_move() {
+                Color::White => (board.en_passant_square - 8, board.en_passant_square + 8),
+                Color::Black => (board.en_passant_square + 8, board.en_passant_square - 8),
+            };
+            if !has(Piece::Pawn, board.next_to_move().opposite(), pushed)
+                || board.occupied.get(board.en_passant_square)
+                || board.occupied.get(origin)
+            {
+                return Err(format!(
+                    "Invalid FEN: en passant square '{}' does not follow a pawn double move",
+                    fields[3]
+                ));
+            }
+        }
+
+        Ok(board)
     }
 
     pub fn fen(&self) -> impl std::fmt::Display + 'static {
@@ -98,6 +238,77 @@ impl Board {
             .expect("Default position FEN should always be valid")
     }
 
+    /// The same position with the given side to move, meant to be chained with the other `with`/`without`
+    /// helpers when setting up a puzzle (e.g. `board.with_side_to_move(Color::White).without_castling()`).
+    pub fn with_side_to_move(mut self, color: Color) -> Self {
+        self.flags
+            .set(BoardFlags::WHITE_TO_MOVE, color == Color::White);
+        self.zobrist = self.compute_zobrist();
+        self
+    }
+
+    /// The same position without any castling right left.
+    pub fn without_castling(mut self) -> Self {
+        for side in CastleSide::iter() {
+            for color in [Color::White, Color::Black] {
+                self.flags.remove(BoardFlags::castling_right(color, side));
+            }
+        }
+        self.zobrist = self.compute_zobrist();
+        self
+    }
+
+    /// The same position without an en passant target square.
+    pub fn without_en_passant(mut self) -> Self {
+        self.en_passant_square = Self::NO_EN_PASSANT_SQUARE;
+        self.zobrist = self.compute_zobrist();
+        self
+    }
+
+    /// The same position seen from the other side: the board is flipped vertically and the colors of the
+    /// pieces, the castling rights and the side to move are swapped. A symmetric evaluation satisfies
+    /// `evaluate(board) == -evaluate(board.flip_colors())`.
+    pub fn flip_colors(&self) -> Self {
+        let mut mirrored = *self;
+        for bitboard in mirrored.bitboards.iter_mut() {
+            *bitboard = bitboard.flip_vertical();
+        }
+        mirrored.occupied = self.occupied.flip_vertical();
+        mirrored.white = (self.occupied ^ self.white).flip_vertical();
+
+        mirrored.flags = BoardFlags::empty();
+        mirrored.flags.set(
+            BoardFlags::WHITE_TO_MOVE,
+            self.next_to_move() == Color::Black,
+        );
+        for side in CastleSide::iter() {
+            for color in [Color::White, Color::Black] {
+                if self.has_castling_right(color, side) {
+                    mirrored.flags |= BoardFlags::castling_right(color.opposite(), side);
+                }
+            }
+        }
+
+        for (color, rooks) in self.castling_rooks.iter().enumerate() {
+            mirrored.castling_rooks[1 - color] = rooks.map(|square| square ^ 56);
+        }
+
+        if self.en_passant_square < 64 {
+            mirrored.en_passant_square = self.en_passant_square ^ 56;
+        }
+        mirrored.zobrist = mirrored.compute_zobrist();
+        mirrored
+    }
+
+    /// Builds the square-centric view of this board.
+    ///
+    /// The conversion walks every bitboard, so callers that need the view after each move (e.g. a GUI
+    /// re-rendering every frame) should keep a `SquareCentricBoard` alongside the board and update both
+    /// with [`crate::moves::Move::apply_to_both`] instead of converting again.
+    pub fn square_centric(&self) -> SquareCentricBoard {
+        (*self).into()
+    }
+
     pub fn get(&self, piece: Piece) -> &Bitboard {
         debug_assert!(
             piece.is_white(),
@@ -106,6 +317,17 @@ impl Board {
         &self.bitboards[piece.colorless() as usize]
     }
 
+    /// The piece (with its color) standing on the square, `None` if the square is empty.
+    pub fn piece_at(&self, square: u8) -> Option<Piece> {
+        if !self.occupied.get(square) {
+            return None;
+        }
+        let color = Color::from_boolean_is_white(self.white.get(square));
+        Piece::colorless_iter()
+            .find(|piece| self.get(*piece).get(square))
+            .map(|piece| piece.with_color(color))
+    }
+
     pub fn get_mut(&mut self, piece: Piece) -> &mut Bitboard {
         debug_assert!(
             piece.is_white(),
@@ -137,6 +359,287 @@ impl Board {
             Color::Black
         }
     }
+
+    /// The en passant target square, if any.
+    pub fn en_passant(&self) -> Option<Square> {
+        Square::from_index(self.en_passant_square)
+    }
+
+    /// Bitboard of the en passant target square, empty if there is none. Use this instead of indexing
+    /// a bitboard with `en_passant_square` directly, since it holds an out of range sentinel when unset.
+    pub fn en_passant_bitboard(&self) -> Bitboard {
+        self.en_passant()
+            .map_or(Bitboard::empty(), Square::bitboard)
+    }
+
+    /// Explains, for each color and direction, whether castling is possible and if not why. The conditions
+    /// are checked in the same order as in [`crate::moves::generate_moves`]: castling rights, check, empty
+    /// squares between the king and the rook, then attacked squares crossed by the king.
+    ///
+    /// The report is computed for both colors regardless of the side to move, a `Legal` status for the side
+    /// not to move meaning that castling would be legal if it was its turn.
+    pub fn castling_status(&self) -> CastlingReport {
+        let status = |color: Color, side: CastleSide| {
+            if !self.has_castling_right(color, side) {
+                return CastlingStatus::RightsLost;
+            }
+
+            let attacked = crate::moves::attacked_squares(self, color.opposite());
+            if !(attacked & *self.get(Piece::King) & self.color_bitboard(color)).is_empty() {
+                return CastlingStatus::InCheck;
+            }
+            if let Some(square) = (self.occupied & self.castling_between_mask(color, side))
+                .scan()
+                .next()
+            {
+                return CastlingStatus::SquareOccupied(square);
+            }
+            if let Some(square) = (attacked & self.castling_transit_mask(color, side))
+                .scan()
+                .next()
+            {
+                return CastlingStatus::SquareAttacked(square);
+            }
+            if self.castling_uncovers_king(color, side) {
+                return CastlingStatus::SquareAttacked(side.king_destination(color));
+            }
+            CastlingStatus::Legal
+        };
+
+        CastlingReport {
+            white_king_side: status(Color::White, CastleSide::KingSide),
+            white_queen_side: status(Color::White, CastleSide::QueenSide),
+            black_king_side: status(Color::Black, CastleSide::KingSide),
+            black_queen_side: status(Color::Black, CastleSide::QueenSide),
+        }
+    }
+
+    /// King safety metric: number of enemy pieces (knights, bishops, rooks and queens) attacking the zone
+    /// around the king of the given color (the king square and its surrounding squares), and the sum of
+    /// their weights depending on the piece type, each piece being counted once.
+    pub fn king_zone_attack(&self, color: Color) -> (u32, u32) {
+        use crate::moves::{
+            generate_bishop_movement, generate_knight_movement, generate_queen_movement,
+            generate_rook_movement,
+        };
+
+        let king = *self.get(Piece::King) & self.color_bitboard(color);
+        let zone = king | king.surrounding_mask();
+        let enemy = self.color_bitboard(color.opposite());
+
+        let mut attackers = 0;
+        let mut attack_weight = 0;
+        for (piece, weight) in [
+            (Piece::Knight, 2),
+            (Piece::Bishop, 2),
+            (Piece::Rook, 3),
+            (Piece::Queen, 5),
+        ] {
+            for square in (*self.get(piece) & enemy).scan_bitboard() {
+                let attacks = match piece {
+                    Piece::Knight => generate_knight_movement(square),
+                    Piece::Bishop => generate_bishop_movement(self.occupied, square),
+                    Piece::Rook => generate_rook_movement(self.occupied, square),
+                    _ => generate_queen_movement(self.occupied, square),
+                };
+                if !(attacks & zone).is_empty() {
+                    attackers += 1;
+                    attack_weight += weight;
+                }
+            }
+        }
+
+        (attackers, attack_weight)
+    }
+
+    /// Whether the fifty-move rule applies, i.e. no capture nor pawn move happened in the last 100 halfmoves.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// Whether the given color still has the right to castle on the given side (this does not check
+    /// that castling is currently possible, see [`Board::castling_status`]).
+    pub fn has_castling_right(&self, color: Color, side: CastleSide) -> bool {
+        self.flags.contains(BoardFlags::castling_right(color, side))
+    }
+
+    /// Initial square of the rook the king of the given color castles with on the given side. Only meaningful
+    /// while the corresponding castling right is held.
+    pub fn castling_rook_square(&self, color: Color, side: CastleSide) -> u8 {
+        self.castling_rooks[color as usize][side as usize]
+    }
+
+    /// Squares that must be empty for the king of the given color to castle on the given side: the squares the
+    /// king and the rook go through or land on, save for the ones they start from.
+    pub fn castling_between_mask(&self, color: Color, side: CastleSide) -> Bitboard {
+        let Some(king_square) = self.king_square(color) else {
+            return Bitboard::empty();
+        };
+        let rook_square = self.castling_rook_square(color, side);
+        (Bitboard::rank_span(king_square, side.king_destination(color))
+            | Bitboard::rank_span(rook_square, side.rook_destination(color)))
+            & !Bitboard((1 << king_square) | (1 << rook_square))
+    }
+
+    /// Squares the king of the given color goes through or lands on when castling on the given side, which must
+    /// not be attacked.
+    pub fn castling_transit_mask(&self, color: Color, side: CastleSide) -> Bitboard {
+        let Some(king_square) = self.king_square(color) else {
+            return Bitboard::empty();
+        };
+        Bitboard::rank_span(king_square, side.king_destination(color)) & !Bitboard(1 << king_square)
+    }
+
+    /// Whether, once the rook of the given color left its square to castle on the given side, an enemy rook or
+    /// queen would attack the destination of the king along the back rank. This only happens in Chess960, where
+    /// the rook can shield the destination of the king (e.g. king on c1, rook on b1 and enemy rook on a1).
+    pub fn castling_uncovers_king(&self, color: Color, side: CastleSide) -> bool {
+        let Some(king_square) = self.king_square(color) else {
+            return false;
+        };
+        let occupied = self.occupied
+            & !Bitboard((1 << king_square) | (1 << self.castling_rook_square(color, side)));
+        let rook_like_enemy = (*self.get(Piece::Rook) | *self.get(Piece::Queen))
+            & self.color_bitboard(color.opposite());
+        !(Bitboard::rook_attacks(side.king_destination(color), occupied) & rook_like_enemy)
+            .is_empty()
+    }
+
+    /// Square of the king of the given color, `None` on a board without it.
+    pub fn king_square(&self, color: Color) -> Option<u8> {
+        (*self.get(Piece::King) & self.color_bitboard(color))
+            .scan()
+            .next()
+    }
+
+    /// Whether the position is quiet, i.e. the side to move is not in check and has no capture that does
+    /// not lose material according to the static exchange evaluation (see [`crate::moves::Move::see`]).
+    pub fn is_quiet(&self) -> bool {
+        let mut captures = Vec::new();
+        let mut currently_in_check = false;
+        crate::moves::generate_captures(self, &mut captures, &mut currently_in_check);
+        !currently_in_check && captures.iter().all(|mv| mv.see(self) < 0)
+    }
+
+    /// Number of pieces of the given colored type on the board (e.g. the white knights for
+    /// [`Piece::WhiteKnight`]).
+    pub fn piece_count(&self, piece: Piece) -> u32 {
+        (self.bitboards[piece.colorless() as usize] & self.color_bitboard(piece.color()))
+            .count_ones()
+    }
+
+    /// All of the pieces of the given color.
+    pub fn color_bitboard(&self, color: Color) -> Bitboard {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.occupied ^ self.white,
+        }
+    }
+
+    /// All of the pieces of the given color attacking the given square, with the current occupancy of the
+    /// board blocking sliding attacks.
+    pub fn attackers_to(&self, square: u8, by: Color) -> Bitboard {
+        crate::moves::attackers_to(self, square, self.occupied) & self.color_bitboard(by)
+    }
+
+    /// Whether any piece of the given color attacks the given square.
+    pub fn is_square_attacked(&self, square: u8, by: Color) -> bool {
+        !self.attackers_to(square, by).is_empty()
+    }
+
+    /// Whether the side to move is in check, without generating its moves.
+    pub fn is_check(&self) -> bool {
+        let king = *self.get(Piece::King) & self.friendly_bitboard();
+        self.is_square_attacked(king.square(), self.next_to_move().opposite())
+    }
+}
+
+impl BoardFlags {
+    /// The castling right flag of the given color and side.
+    pub fn castling_right(color: Color, side: CastleSide) -> BoardFlags {
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => BoardFlags::WHITE_KING_SIDE_CASTLE,
+            (Color::White, CastleSide::QueenSide) => BoardFlags::WHITE_QUEEN_SIDE_CASTLE,
+            (Color::Black, CastleSide::KingSide) => BoardFlags::BLACK_KING_SIDE_CASTLE,
+            (Color::Black, CastleSide::QueenSide) => BoardFlags::BLACK_QUEEN_SIDE_CASTLE,
+        }
+    }
+}
+
+/// The side of the board toward which the king castles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastleSide {
+    fn home_rank_offset(color: Color) -> u8 {
+        match color {
+            Color::White => 0,
+            Color::Black => 56,
+        }
+    }
+
+    /// Square the king lands on when castling.
+    pub fn king_destination(self, color: Color) -> u8 {
+        let file = match self {
+            CastleSide::KingSide => 6,
+            CastleSide::QueenSide => 2,
+        };
+        file + Self::home_rank_offset(color)
+    }
+
+    /// Square the rook lands on when castling, next to the king on the side of the center.
+    pub fn rook_destination(self, color: Color) -> u8 {
+        let file = match self {
+            CastleSide::KingSide => 5,
+            CastleSide::QueenSide => 3,
+        };
+        file + Self::home_rank_offset(color)
+    }
+
+    /// The side of a castling move given the destination of the king, `None` if no castling lands there.
+    pub fn from_king_destination(square: u8) -> Option<CastleSide> {
+        match square {
+            6 | 62 => Some(CastleSide::KingSide),
+            2 | 58 => Some(CastleSide::QueenSide),
+            _ => None,
+        }
+    }
+}
+
+/// Why castling in a given direction is (or is not) possible, see [`Board::castling_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastlingStatus {
+    Legal,
+
+    /// The king or the corresponding rook has already moved (or the rook was captured).
+    RightsLost,
+
+    /// The king is currently in check.
+    InCheck,
+
+    /// A square between the king and the rook is occupied (the lowest such square).
+    SquareOccupied(u8),
+
+    /// A square the king would move through or land on is attacked (the lowest such square).
+    SquareAttacked(u8),
+}
+
+impl CastlingStatus {
+    pub fn is_legal(&self) -> bool {
+        *self == CastlingStatus::Legal
+    }
+}
+
+/// Castling diagnostic for both colors and both directions, mainly meant for user interfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CastlingReport {
+    pub white_king_side: CastlingStatus,
+    pub white_queen_side: CastlingStatus,
+    pub black_king_side: CastlingStatus,
+    pub black_queen_side: CastlingStatus,
 }
 
 impl std::fmt::Display for Board {
@@ -330,15 +833,30 @@ impl Piece {
         // either 0 or 6 will always yield a valid piece variant.
         unsafe { Self::from_repr_unsafe((self as u8 % 6) + color_bit) }
     }
+
+    /// Nominal value of the piece in centipawns, used to weigh exchanges (not for evaluation). The king is
+    /// given a value larger than everything else combined.
+    pub fn value(self) -> i32 {
+        match self.colorless() {
+            Piece::Pawn => 100,
+            Piece::Knight | Piece::Bishop => 300,
+            Piece::Rook => 500,
+            Piece::Queen => 900,
+            _ => 20000,
+        }
+    }
 }
 
 impl std::str::FromStr for Piece {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Piece::iter()
-            .find(|piece| piece.symbol() == s)
-            .ok_or_else(|| format!("Invalid piece symbol: '{}'", s))
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Piece::from_fen_char(c),
+            _ => None,
+        }
+        .ok_or_else(|| format!("Invalid piece symbol: '{}'", s))
     }
 }
 
@@ -407,6 +925,28 @@ impl Piece {
             Piece::WhiteKing => "K",
         }
     }
+
+    /// FEN letter of the piece, uppercase for white and lowercase for black (same as [`Piece::symbol`]).
+    pub fn to_fen_char(self) -> char {
+        self.symbol().as_bytes()[0] as char
+    }
+
+    /// Piece of the given FEN letter, the inverse of [`Piece::to_fen_char`].
+    pub fn from_fen_char(c: char) -> Option<Piece> {
+        Piece::iter().find(|piece| piece.to_fen_char() == c)
+    }
+
+    /// Index of the piece type regardless of its color, from 1 (pawn) through knight, bishop, rook and
+    /// queen to 6 (king). This is the numbering used by most other chess libraries.
+    pub fn type_index(self) -> u8 {
+        self.colorless() as u8 + 1
+    }
+
+    /// Piece of the given color and type index, the inverse of [`Piece::type_index`].
+    pub fn from_type_index(index: u8, color: Color) -> Option<Piece> {
+        let piece = Piece::from_repr(index.checked_sub(1)?).filter(|_| index <= 6)?;
+        Some(piece.with_color(color))
+    }
 }
 
 impl std::fmt::Display for Piece {
@@ -423,6 +963,8 @@ pub struct SquareCentricBoard {
     pub squares: [Option<Piece>; 64],
     pub flags: BoardFlags,
     pub en_passant_square: u8,
+    pub halfmove_clock: u16,
+    pub castling_rooks: [[u8; 2]; 2],
 }
 
 impl From<SquareCentricBoard> for Board {
@@ -439,16 +981,18 @@ impl From<SquareCentricBoard> for Board {
         }
         board.flags = value.flags;
         board.en_passant_square = value.en_passant_square;
+        board.halfmove_clock = value.halfmove_clock;
+        board.castling_rooks = value.castling_rooks;
+        board.zobrist = board.compute_zobrist();
         board
     }
 }
 
 impl From<Board> for SquareCentricBoard {
     fn from(value: Board) -> Self {
-        assert!(
-            value.verify(),
-            "Invalid board state: overlapping pieces detected"
-        );
+        if let Err(reason) = value.validate_bitboards() {
+            panic!("Invalid board state: {}", reason);
+        }
 
         let mut squares = [None; 64];
         for piece in Piece::colorless_iter() {
@@ -464,6 +1008,8 @@ impl From<Board> for SquareCentricBoard {
             squares,
             en_passant_square: value.en_passant_square,
             flags: value.flags,
+            halfmove_clock: value.halfmove_clock,
+            castling_rooks: value.castling_rooks,
         }
     }
 }
@@ -503,8 +1049,10 @@ impl SquareCentricBoard {
     pub const fn empty() -> Self {
         Self {
             squares: [None; 64],
-            en_passant_square: 8,
+            en_passant_square: Board::NO_EN_PASSANT_SQUARE,
             flags: BoardFlags::empty(),
+            halfmove_clock: 0,
+            castling_rooks: Board::STANDARD_CASTLING_ROOKS,
         }
     }
 
@@ -518,6 +1066,8 @@ impl SquareCentricBoard {
         let mut rank = 7;
         let mut file = 0;
         let mut meta_index = 0;
+        let mut castling_field = String::new();
+        let mut en_passant_field = String::new();
 
         for c in fen.chars() {
             if meta_index >= 1 {
@@ -535,12 +1085,9 @@ impl SquareCentricBoard {
                         }
                     }
                 } else if meta_index == 2 {
-                    // Castling rights
+                    // Castling rights, resolved once the pieces are known
                     match c {
-                        'K' => board.flags |= BoardFlags::WHITE_KING_SIDE_CASTLE,
-                        'Q' => board.flags |= BoardFlags::WHITE_QUEEN_SIDE_CASTLE,
-                        'k' => board.flags |= BoardFlags::BLACK_KING_SIDE_CASTLE,
-                        'q' => board.flags |= BoardFlags::BLACK_QUEEN_SIDE_CASTLE,
+                        'K' | 'Q' | 'k' | 'q' | 'A'..='H' | 'a'..='h' => castling_field.push(c),
                         '-' => {} // No castling rights
                         _ => {
                             return Err(format!(
@@ -550,24 +1097,22 @@ impl SquareCentricBoard {
                         }
                     }
                 } else if meta_index == 3 {
-                    // En passant target square
-                    if c == '-' {
-                        board.en_passant_square = 64;
-                    } else if c.is_ascii_alphabetic() && c.is_ascii_lowercase() {
-                        board.en_passant_square = (c as u8 - b'a') % 8; // Convert file character to index (0-7)
-                        if board.flags.contains(BoardFlags::WHITE_TO_MOVE) {
-                            board.en_passant_square += 40;
-                        } else {
-                            board.en_passant_square += 16;
-                        }
-                    } else if c.is_digit(10) {
-                    } else {
-                        return Err(format!(
-                            "Invalid FEN: unexpected character '{}' in en passant section",
+                    // En passant target square, parsed once the whole field is read
+                    en_passant_field.push(c);
+                } else if meta_index == 4 {
+                    // Halfmove clock
+                    let digit = c.to_digit(10).ok_or_else(|| {
+                        format!(
+                            "Invalid FEN: unexpected character '{}' in halfmove clock section",
                             c
-                        ));
-                    }
-                } else if meta_index >= 4 {
+                        )
+                    })?;
+                    board.halfmove_clock = board
+                        .halfmove_clock
+                        .checked_mul(10)
+                        .and_then(|clock| clock.checked_add(digit as u16))
+                        .ok_or_else(|| "Invalid FEN: halfmove clock is too large".to_string())?;
+                } else if meta_index >= 5 {
                     continue;
                 } else {
                     return Err(format!(
@@ -601,16 +1146,81 @@ impl SquareCentricBoard {
                     if file >= 8 {
                         return Err(format!("Invalid FEN: too many files in rank {}", 7 - rank));
                     }
-                    let piece = c.to_string().parse::<Piece>()?;
+                    let piece = Piece::from_fen_char(c)
+                        .ok_or_else(|| format!("Invalid piece symbol: '{}'", c))?;
                     board.squares[(rank * 8 + file) as usize] = Some(piece);
                     file += 1;
                 }
             }
         }
 
+        // The target square is behind a pawn that just made a double move, i.e. on the 6th rank when white is
+        // to move and on the 3rd rank when black is to move
+        if !en_passant_field.is_empty() && en_passant_field != "-" {
+            let square = algebraic_to_square(&en_passant_field).ok_or_else(|| {
+                format!(
+                    "Invalid FEN: invalid en passant square '{}'",
+                    en_passant_field
+                )
+            })?;
+            let expected_rank = if board.flags.contains(BoardFlags::WHITE_TO_MOVE) {
+                5
+            } else {
+                2
+            };
+            if square / 8 != expected_rank {
+                return Err(format!(
+                    "Invalid FEN: en passant square '{}' does not match the side to move",
+                    en_passant_field
+                ));
+            }
+            board.en_passant_square = square;
+        }
+
+        // Castling rights are dropped unless the king and the rook stand on their initial squares, as the castling
+        // moves would otherwise move pieces that are not there
+        for c in castling_field.chars() {
+            if let Some((color, side, rook_square)) = board.castling_right_from_fen_char(c) {
+                board.flags |= BoardFlags::castling_right(color, side);
+                board.castling_rooks[color as usize][side as usize] = rook_square;
+            }
+        }
+
         Ok(board)
     }
 
+    /// The castling right given by a character of the castling field of a FEN, with the initial square of its
+    /// rook. `KQkq` stand for the rooks in the corners with the king on e1 or e8, as in standard chess, while
+    /// Chess960 positions give the file of the rook instead (Shredder-FEN, e.g. `HAha` or `GBgb`), uppercase
+    /// for white. Returns `None` if the king and the rook are not where the right says they are.
+    pub fn castling_right_from_fen_char(&self, c: char) -> Option<(Color, CastleSide, u8)> {
+        let color = Color::from_boolean_is_white(c.is_ascii_uppercase());
+        let back_rank = if color == Color::White { 0 } else { 56 };
+        let king_square = (back_rank..back_rank + 8)
+            .find(|&square| self.squares[square as usize] == Some(Piece::King.with_color(color)))?;
+
+        let (side, rook_square) = match c.to_ascii_lowercase() {
+            'k' if king_square == back_rank + 4 => (CastleSide::KingSide, back_rank + 7),
+            'q' if king_square == back_rank + 4 => (CastleSide::QueenSide, back_rank),
+            file @ 'a'..='h' => {
+                let rook_square = back_rank + (file as u8 - b'a');
+                let side = match rook_square.cmp(&king_square) {
+                    std::cmp::Ordering::Greater => CastleSide::KingSide,
+                    std::cmp::Ordering::Less => CastleSide::QueenSide,
+                    std::cmp::Ordering::Equal => return None,
+                };
+                (side, rook_square)
+            }
+            _ => return None,
+        };
+
+        (self.squares[rook_square as usize] == Some(Piece::Rook.with_color(color))).then_some((
+            color,
+            side,
+            rook_square,
+        ))
+    }
+
     pub fn fen(self) -> impl std::fmt::Display + 'static {
         struct Fmt {
             board: SquareCentricBoard,
@@ -648,34 +1258,36 @@ impl SquareCentricBoard {
                     write!(f, " b ")?;
                 }
 
-                // Castling rights
-                if self
-                    .board
-                    .flags
-                    .contains(BoardFlags::WHITE_KING_SIDE_CASTLE)
-                {
-                    write!(f, "K")?;
-                }
-                if self
-                    .board
-                    .flags
-                    .contains(BoardFlags::WHITE_QUEEN_SIDE_CASTLE)
-                {
-                    write!(f, "Q")?;
-                }
-                if self
-                    .board
-                    .flags
-                    .contains(BoardFlags::BLACK_KING_SIDE_CASTLE)
-                {
-                    write!(f, "k")?;
-                }
-                if self
-                    .board
-                    .flags
-                    .contains(BoardFlags::BLACK_QUEEN_SIDE_CASTLE)
-                {
-                    write!(f, "q")?;
+                // Castling rights, with the file of the rook (Shredder-FEN) unless castling is standard
+                for (color, side) in [
+                    (Color::White, CastleSide::KingSide),
+                    (Color::White, CastleSide::QueenSide),
+                    (Color::Black, CastleSide::KingSide),
+                    (Color::Black, CastleSide::QueenSide),
+                ] {
+                    if !self
+                        .board
+                        .flags
+                        .contains(BoardFlags::castling_right(color, side))
+                    {
+                        continue;
+                    }
+
+                    let rook_square = self.board.castling_rooks[color as usize][side as usize];
+                    let back_rank = rook_square / 8 * 8;
+                    let standard = rook_square
+                        == Board::STANDARD_CASTLING_ROOKS[color as usize][side as usize]
+                        && self.board.squares[back_rank as usize + 4]
+                            == Some(Piece::King.with_color(color));
+                    let symbol = match (standard, side) {
+                        (true, CastleSide::KingSide) => 'k',
+                        (true, CastleSide::QueenSide) => 'q',
+                        (false, _) => (b'a' + rook_square % 8) as char,
+                    };
+                    match color {
+                        Color::White => write!(f, "{}", symbol.to_ascii_uppercase())?,
+                        Color::Black => write!(f, "{}", symbol)?,
+                    }
                 }
                 if !self.board.flags.intersects(BoardFlags::CASTLE) {
                     write!(f, "-")?;
@@ -695,3 +1307,425 @@ impl SquareCentricBoard {
         Fmt { board: self }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::{MoveFlags, generate_moves};
+
+    fn assert_consistent_with_generate_moves(board: &Board, report: &CastlingReport) {
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(board, &mut moves, &mut currently_in_check);
+
+        let (king_side, queen_side, king_side_to, queen_side_to) = match board.next_to_move() {
+            Color::White => (report.white_king_side, report.white_queen_side, 6, 2),
+            Color::Black => (report.black_king_side, report.black_queen_side, 62, 58),
+        };
+        let has_castle = |to: u8| {
+            moves
+                .iter()
+                .any(|mv| mv.flags.contains(MoveFlags::CASTLE) && mv.to == to)
+        };
+        assert_eq!(king_side.is_legal(), has_castle(king_side_to));
+        assert_eq!(queen_side.is_legal(), has_castle(queen_side_to));
+    }
+
+    #[test]
+    fn test_piece_at() {
+        let board = Board::default_position();
+        let back_rank = [
+            Piece::Rook,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Queen,
+            Piece::King,
+            Piece::Bishop,
+            Piece::Knight,
+            Piece::Rook,
+        ];
+        for file in 0..8 {
+            assert_eq!(board.piece_at(file), Some(back_rank[file as usize]));
+            assert_eq!(board.piece_at(8 + file), Some(Piece::Pawn));
+            assert_eq!(board.piece_at(48 + file), Some(Piece::BlackPawn));
+            assert_eq!(
+                board.piece_at(56 + file),
+                Some(back_rank[file as usize].with_color(Color::Black))
+            );
+        }
+        for square in 16..48 {
+            assert_eq!(board.piece_at(square), None);
+        }
+
+        // Agrees with the square-centric view on a busier position
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let square_centric = board.square_centric();
+        for square in 0..64 {
+            assert_eq!(
+                board.piece_at(square),
+                square_centric.squares[square as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn test_en_passant_sentinel_is_never_indexed() {
+        let board = Board::from_fen(Board::DEFAULT_POSITION_FEN).unwrap();
+        assert_eq!(board.en_passant_square, 64);
+        assert_eq!(board.en_passant_bitboard(), Bitboard::empty());
+        assert_eq!(board.en_passant(), None);
+
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(board.en_passant_bitboard(), Bitboard(1 << 43));
+        assert_eq!(board.en_passant(), Square::from_algebraic("d6"));
+
+        // Generating and applying moves back and forth between positions with and without an en passant
+        // square would trip the bitboard bounds assertions (in debug) if the sentinel was ever indexed
+        let mut moves = Vec::new();
+        let mut next_moves = Vec::new();
+        let mut currently_in_check = false;
+        for fen in [
+            Board::DEFAULT_POSITION_FEN,
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            for mv in &moves {
+                let mut new_board = board;
+                mv.apply(&mut new_board);
+                generate_moves(&new_board, &mut next_moves, &mut currently_in_check);
+                for next in &next_moves {
+                    let mut next_board = new_board;
+                    next.apply(&mut next_board);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_king_zone_attack() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1").unwrap();
+        assert_eq!(board.king_zone_attack(Color::Black), (0, 0));
+
+        // A queen next to the king, supported by the rook, weights much more
+        let board = Board::from_fen("6k1/5ppp/7Q/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        let (attackers, weight) = board.king_zone_attack(Color::Black);
+        assert_eq!((attackers, weight), (1, 5));
+        let board = Board::from_fen("3R2k1/5ppp/7Q/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        assert_eq!(board.king_zone_attack(Color::Black), (2, 8));
+        assert_eq!(board.king_zone_attack(Color::White), (0, 0));
+    }
+
+    #[test]
+    fn test_puzzle_setup_helpers() {
+        let board =
+            Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2")
+                .unwrap()
+                .with_side_to_move(Color::Black)
+                .without_castling()
+                .without_en_passant();
+        assert_eq!(
+            board.fen().to_string(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR b - -"
+        );
+        assert_eq!(board.zobrist, board.compute_zobrist());
+
+        let board = Board::default_position().with_side_to_move(Color::White);
+        assert_eq!(board, Board::default_position());
+    }
+
+    #[test]
+    fn test_flip_colors() {
+        let board = Board::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K3 w Qkq d6 0 1").unwrap();
+        let mirrored = board.flip_colors();
+        assert_eq!(
+            mirrored,
+            Board::from_fen("r3k3/8/8/8/3Pp3/8/8/R3K2R b KQq d3 0 1").unwrap()
+        );
+        assert_eq!(mirrored.flip_colors(), board);
+    }
+
+    #[test]
+    fn test_has_castling_right() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        assert!(board.has_castling_right(Color::White, CastleSide::KingSide));
+        assert!(!board.has_castling_right(Color::White, CastleSide::QueenSide));
+        assert!(!board.has_castling_right(Color::Black, CastleSide::KingSide));
+        assert!(board.has_castling_right(Color::Black, CastleSide::QueenSide));
+
+        // The FEN output goes through the same accessor
+        assert_eq!(board.fen().to_string(), "r3k2r/8/8/8/8/8/8/R3K2R w Kq -");
+    }
+
+    #[test]
+    fn test_castling_status_reasons() {
+        // White: g1 attacked by the knight on h3, b1 occupied by the knight
+        // Black: in check from the bishop on b5, queen side rights lost
+        let board = Board::from_fen("4k2r/8/8/1B6/8/7n/8/RN2K2R b KQk - 0 1").unwrap();
+        let report = board.castling_status();
+        assert_eq!(report.white_king_side, CastlingStatus::SquareAttacked(6));
+        assert_eq!(report.white_queen_side, CastlingStatus::SquareOccupied(1));
+        assert_eq!(report.black_king_side, CastlingStatus::InCheck);
+        assert_eq!(report.black_queen_side, CastlingStatus::RightsLost);
+        assert_consistent_with_generate_moves(&board, &report);
+
+        // The rook on b8 attacks b1, which the white king doesn't cross, but occupies b8
+        let board = Board::from_fen("rr2k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let report = board.castling_status();
+        assert_eq!(report.white_king_side, CastlingStatus::Legal);
+        assert_eq!(report.white_queen_side, CastlingStatus::Legal);
+        assert_eq!(report.black_king_side, CastlingStatus::Legal);
+        assert_eq!(report.black_queen_side, CastlingStatus::SquareOccupied(57));
+        assert_consistent_with_generate_moves(&board, &report);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let board = Board::default_position();
+        // f3 is covered by the g1 knight and the e2/g2 pawns
+        let f3 = algebraic_to_square("f3").unwrap();
+        assert_eq!(
+            board.attackers_to(f3, Color::White),
+            Bitboard((1 << 6) | (1 << 12) | (1 << 14))
+        );
+        assert!(board.attackers_to(f3, Color::Black).is_empty());
+        assert!(!board.is_square_attacked(algebraic_to_square("e4").unwrap(), Color::White));
+
+        // e4 is attacked by the d5 pawn, the h7 bishop, the e8 rook and the g3 knight, while the a4 queen
+        // attack is blocked by the c4 pawn
+        let board = Board::from_fen("4r1k1/7b/8/3p4/q1P5/6n1/8/4K3 w - - 0 1").unwrap();
+        let e4 = algebraic_to_square("e4").unwrap();
+        let expected = ["d5", "h7", "e8", "g3"].iter().fold(0, |acc, square| {
+            acc | 1 << algebraic_to_square(square).unwrap()
+        });
+        assert_eq!(board.attackers_to(e4, Color::Black), Bitboard(expected));
+        assert_eq!(
+            board.attackers_to(algebraic_to_square("d5").unwrap(), Color::White),
+            Bitboard(1 << algebraic_to_square("c4").unwrap())
+        );
+
+        // The white king covers d2 and is checked by the e8 rook down the open file
+        assert!(board.is_square_attacked(algebraic_to_square("d2").unwrap(), Color::White));
+        assert_eq!(
+            board.attackers_to(algebraic_to_square("e1").unwrap(), Color::Black),
+            Bitboard(1 << algebraic_to_square("e8").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_piece_count() {
+        let board = Board::default_position();
+        for (piece, count) in [
+            (Piece::WhitePawn, 8),
+            (Piece::BlackPawn, 8),
+            (Piece::WhiteKnight, 2),
+            (Piece::BlackRook, 2),
+            (Piece::WhiteQueen, 1),
+            (Piece::BlackKing, 1),
+        ] {
+            assert_eq!(board.piece_count(piece), count, "{:?}", piece);
+        }
+
+        // Kiwipete, no piece has been traded yet
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for (piece, count) in [
+            (Piece::WhitePawn, 8),
+            (Piece::BlackPawn, 8),
+            (Piece::WhiteKnight, 2),
+            (Piece::BlackKnight, 2),
+            (Piece::WhiteBishop, 2),
+            (Piece::BlackBishop, 2),
+            (Piece::WhiteQueen, 1),
+            (Piece::BlackQueen, 1),
+        ] {
+            assert_eq!(board.piece_count(piece), count, "{:?}", piece);
+        }
+
+        // A lone king against a queen and two rooks
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/RQ2K2R w - - 0 1").unwrap();
+        assert_eq!(board.piece_count(Piece::WhiteRook), 2);
+        assert_eq!(board.piece_count(Piece::WhiteQueen), 1);
+        assert_eq!(board.piece_count(Piece::BlackRook), 0);
+        assert_eq!(board.piece_count(Piece::BlackPawn), 0);
+        assert_eq!(board.piece_count(Piece::BlackKing), 1);
+    }
+
+    #[test]
+    fn test_is_check() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+        assert!(!board.is_check());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert!(board.is_check());
+
+        // Agrees with the move generator on a position with a knight check
+        let board = Board::from_fen("4k3/8/3N4/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&board, &mut moves, &mut currently_in_check);
+        assert!(currently_in_check);
+        assert!(board.is_check());
+    }
+
+    #[test]
+    fn test_is_quiet() {
+        // The black queen is en prise
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        assert!(!board.is_quiet());
+
+        // Locked pawns, nothing to capture
+        let board = Board::from_fen("4k3/8/8/3p4/3P4/8/8/4K3 w - - 0 1").unwrap();
+        assert!(board.is_quiet());
+
+        // The only capture loses the queen for a pawn
+        let board = Board::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert!(board.is_quiet());
+
+        // In check
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap();
+        assert!(!board.is_quiet());
+    }
+
+    #[test]
+    fn test_piece_conversions_round_trip() {
+        for piece in Piece::iter() {
+            let c = piece.to_fen_char();
+            assert_eq!(Piece::from_fen_char(c), Some(piece));
+            assert_eq!(c.to_string().parse::<Piece>(), Ok(piece));
+            assert_eq!(c.is_ascii_uppercase(), piece.is_white());
+
+            let index = piece.type_index();
+            assert!((1..=6).contains(&index));
+            assert_eq!(Piece::from_type_index(index, piece.color()), Some(piece));
+            assert_eq!(Piece::from_repr(piece as u8), Some(piece));
+        }
+
+        assert_eq!(Piece::BlackKnight.to_fen_char(), 'n');
+        assert_eq!(Piece::WhitePawn.type_index(), 1);
+        assert_eq!(Piece::BlackKing.type_index(), 6);
+        assert_eq!(Piece::from_type_index(0, Color::White), None);
+        assert_eq!(Piece::from_type_index(7, Color::Black), None);
+        assert_eq!(Piece::from_fen_char('x'), None);
+    }
+
+    #[test]
+    fn test_en_passant_field() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.en_passant_square, 20);
+        assert_eq!(board.fen().to_string(), fen);
+
+        // The en passant capture is found from the parsed square
+        let board = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&board, &mut moves, &mut currently_in_check);
+        assert!(
+            moves.iter().any(
+                |mv| mv.flags.contains(MoveFlags::EN_PASSANT) && mv.uci().to_string() == "d4e3"
+            )
+        );
+
+        // The rank has to match the side to move, and the square has to be complete
+        assert!(Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 w - e3 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e9 0 1").is_err());
+
+        assert_eq!(
+            Board::empty().en_passant_square,
+            Board::NO_EN_PASSANT_SQUARE
+        );
+        assert_eq!(Board::empty().en_passant_bitboard(), Bitboard::empty());
+    }
+
+    #[test]
+    fn test_from_fen_strict() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(
+            Board::from_fen_strict(fen).unwrap(),
+            Board::from_fen(fen).unwrap()
+        );
+
+        // Five fields are fine for the lenient parser only
+        let five_fields = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0";
+        assert!(Board::from_fen(five_fields).is_ok());
+        assert!(Board::from_fen_strict(five_fields).is_err());
+        assert!(Board::from_fen_strict(&format!("{} 0", five_fields)).is_err());
+
+        // Castling rights without the king or rook in place, or repeated
+        for fen in [
+            "r3k2r/8/8/8/8/8/8/R4K1R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/1R2K2R w KQ - 0 1",
+            "r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w KKq - 0 1",
+        ] {
+            assert!(Board::from_fen(fen).is_ok(), "\"{}\"", fen);
+            assert!(Board::from_fen_strict(fen).is_err(), "\"{}\"", fen);
+        }
+
+        // En passant squares that no double push could have produced
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+            "4k3/8/8/8/4P3/4N3/8/4K3 b - e3 0 1",
+            "4k3/8/8/8/4p3/8/8/4K3 b - e3 0 1",
+        ] {
+            assert!(Board::from_fen(fen).is_ok(), "\"{}\"", fen);
+            assert!(Board::from_fen_strict(fen).is_err(), "\"{}\"", fen);
+        }
+    }
+
+    #[test]
+    fn test_from_fen_drops_unbacked_castling_rights() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K3 w KQkq - 0 1").unwrap();
+        assert!(!board.has_castling_right(Color::White, CastleSide::KingSide));
+        assert!(board.has_castling_right(Color::White, CastleSide::QueenSide));
+        assert!(board.has_castling_right(Color::Black, CastleSide::KingSide));
+        assert_eq!(board.fen().to_string(), "r3k2r/8/8/8/8/8/8/R3K3 w Qkq -");
+        assert_eq!(board.zobrist, board.compute_zobrist());
+
+        // A knight on h1 or a king off e1 gives nothing to castle with, the empty squares notwithstanding
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K2N w K - 0 1",
+            "r3k2r/8/8/8/8/8/8/R4K1R w KQkq - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(
+                !board.flags.intersects(BoardFlags::WHITE_CASTLE),
+                "\"{}\"",
+                fen
+            );
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            crate::moves::generate_moves(&board, &mut moves, &mut currently_in_check);
+            assert!(
+                !moves
+                    .iter()
+                    .any(|mv| mv.flags.contains(crate::moves::MoveFlags::CASTLE)),
+                "\"{}\"",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_fen_rejects_illegal_boards() {
+        assert!(Board::default_position().verify());
+
+        for (fen, reason) in [
+            ("8/8/8/8/8/8/8/8 w - - 0 1", "white king"),
+            ("4k3/8/8/8/8/8/8/8 w - - 0 1", "white king"),
+            ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", "white king, found 2"),
+            ("8/8/8/8/8/8/8/4K3 b - - 0 1", "black king, found 0"),
+            ("P3k3/8/8/8/8/8/8/4K3 w - - 0 1", "pawns"),
+            ("4k3/8/8/8/8/8/8/4K2p b - - 0 1", "pawns"),
+        ] {
+            let error = Board::from_fen(fen).unwrap_err();
+            assert!(error.contains(reason), "\"{}\": {}", fen, error);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::board::Board;
+
+/// Error raised while parsing an EPD record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpdError {
+    /// The four position fields could not be parsed.
+    InvalidFen(String),
+
+    /// An operation without an opcode, with an unterminated string operand or not ended by a `;`.
+    InvalidOperation(String),
+
+    /// A perft operation (`D1` to `Dn`) whose operand is not a single node count.
+    InvalidPerft(String),
+
+    /// The file could not be read.
+    Io(String),
+
+    /// An error on a line of an EPD file, `line` is counted from 1.
+    Line { line: usize, error: Box<EpdError> },
+}
+
+impl std::fmt::Display for EpdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EpdError::InvalidFen(e) => write!(f, "Invalid EPD position: {}", e),
+            EpdError::InvalidOperation(op) => write!(f, "Invalid EPD operation: {}", op),
+            EpdError::InvalidPerft(op) => write!(f, "Invalid perft operation: {}", op),
+            EpdError::Io(e) => write!(f, "Failed to read EPD file: {}", e),
+            EpdError::Line { line, error } => write!(f, "Line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+/// A record of an EPD file: a position and its operations, keyed by opcode (`bm`, `am`, `id`, `D1`, ...).
+/// The operands are kept as written, with the quotes of string operands removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: BTreeMap<String, Vec<String>>,
+}
+
+impl Epd {
+    /// The operands of the operation `opcode`, if the record has it.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.get(opcode).map(Vec::as_slice)
+    }
+
+    /// The `id` operation naming the record.
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")?.first().map(String::as_str)
+    }
+
+    /// The expected perft node counts of the `Dn` operations, as `(depth, nodes)` sorted by depth.
+    pub fn perft_counts(&self) -> Vec<(u32, u64)> {
+        // The operands were checked when parsing
+        self.operations
+            .iter()
+            .filter_map(|(opcode, operands)| {
+                Some((perft_depth(opcode)?, operands.first()?.parse().ok()?))
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The depth of a perft opcode, `D` followed by a positive number.
+fn perft_depth(opcode: &str) -> Option<u32> {
+    opcode
+        .strip_prefix('D')?
+        .parse()
+        .ok()
+        .filter(|depth| *depth > 0)
+}
+
+/// Parse a single EPD record: the four first fields of a FEN, then the operations, each one an opcode followed by
+/// its operands and ended by a `;`. The `hmvc` and `fmvn` operations, if present, set the clocks of the board.
+pub fn parse_epd_line(line: &str) -> Result<Epd, EpdError> {
+    let line = line.trim();
+    let mut fields = Vec::new();
+    let mut rest = line;
+    for _ in 0..4 {
+        let (field, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if field.is_empty() {
+            return Err(EpdError::InvalidFen(format!(
+                "expected 4 fields, got {}",
+                fields.len()
+            )));
+        }
+        fields.push(field);
+        rest = remainder.trim_start();
+    }
+
+    let operations = parse_operations(rest)?;
+    for opcode in operations.keys() {
+        if perft_depth(opcode).is_some() {
+            let operands = &operations[opcode];
+            if operands.len() != 1 || operands[0].parse::<u64>().is_err() {
+                return Err(EpdError::InvalidPerft(format!(
+                    "{} {}",
+                    opcode,
+                    operands.join(" ")
+                )));
+            }
+        }
+    }
+
+    let clock = |opcode: &str, default: &str| {
+        operations
+            .get(opcode)
+            .and_then(|operands| operands.first())
+            .map_or(default.to_string(), String::clone)
+    };
+    let fen = format!(
+        "{} {} {}",
+        fields.join(" "),
+        clock("hmvc", "0"),
+        clock("fmvn", "1")
+    );
+    let board = Board::from_fen(&fen).map_err(EpdError::InvalidFen)?;
+
+    Ok(Epd { board, operations })
+}
+
+/// Split the operations of a record, string operands may contain spaces and semicolons.
+fn parse_operations(text: &str) -> Result<BTreeMap<String, Vec<String>>, EpdError> {
+    let mut operations = BTreeMap::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                let Some((opcode, operands)) = tokens.split_first() else {
+                    return Err(EpdError::InvalidOperation(text.to_string()));
+                };
+                operations.insert(opcode.clone(), operands.to_vec());
+                tokens.clear();
+            }
+            '"' => {
+                let mut operand = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => operand.push(c),
+                        None => return Err(EpdError::InvalidOperation(text.to_string())),
+                    }
+                }
+                tokens.push(operand);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    if !tokens.is_empty() {
+        return Err(EpdError::InvalidOperation(text.to_string()));
+    }
+    Ok(operations)
+}
+
+/// Parse the records of an EPD file, one per line. Empty lines and lines starting with `#` are skipped.
+pub fn parse_epd(text: &str) -> Result<Vec<Epd>, EpdError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(index, line)| {
+            parse_epd_line(line).map_err(|error| EpdError::Line {
+                line: index + 1,
+                error: Box::new(error),
+            })
+        })
+        .collect()
+}
+
+/// Load the records of an EPD file, see [`parse_epd`].
+pub fn load_epd(path: impl AsRef<Path>) -> Result<Vec<Epd>, EpdError> {
+    let text = std::fs::read_to_string(path).map_err(|e| EpdError::Io(e.to_string()))?;
+    parse_epd(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::perft;
+
+    #[test]
+    fn test_parse_epd_line() {
+        let epd = parse_epd_line(
+            r#"1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id "BK.01";"#,
+        )
+        .unwrap();
+        assert_eq!(
+            epd.board,
+            Board::from_fen("1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1").unwrap()
+        );
+        assert_eq!(epd.operation("bm"), Some(&["Qd1+".to_string()][..]));
+        assert_eq!(epd.id(), Some("BK.01"));
+        assert_eq!(epd.operation("am"), None);
+
+        // String operands may hold spaces and semicolons, and the clocks come from hmvc and fmvn
+        let epd = parse_epd_line(
+            r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - hmvc 2; fmvn 3; c0 "a; b c"; am Ng5 Nc3;"#,
+        )
+        .unwrap();
+        assert_eq!(epd.board.halfmove_clock, 2);
+        assert_eq!(epd.operation("c0"), Some(&["a; b c".to_string()][..]));
+        assert_eq!(
+            epd.operation("am"),
+            Some(&["Ng5".to_string(), "Nc3".to_string()][..])
+        );
+        assert_eq!(epd.id(), None);
+    }
+
+    #[test]
+    fn test_parse_epd_perft() {
+        let text = "\
+# Perft suite
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - D1 20; D2 400; D3 8902;
+
+r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - D1 48; D2 2039; D3 97862;
+";
+        let epds = parse_epd(text).unwrap();
+        assert_eq!(epds.len(), 2);
+        assert_eq!(epds[0].perft_counts(), vec![(1, 20), (2, 400), (3, 8902)]);
+        for epd in &epds {
+            for (depth, nodes) in epd.perft_counts() {
+                assert_eq!(
+                    perft(&epd.board, depth),
+                    nodes,
+                    "{:?} at depth {}",
+                    epd.board,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_epd_errors() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        assert!(matches!(
+            parse_epd_line("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"),
+            Err(EpdError::InvalidFen(_))
+        ));
+        assert!(matches!(
+            parse_epd_line(&format!("{} bm e4", start)),
+            Err(EpdError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            parse_epd_line(&format!(r#"{} id "unterminated;"#, start)),
+            Err(EpdError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            parse_epd_line(&format!("{} D1 twenty;", start)),
+            Err(EpdError::InvalidPerft(_))
+        ));
+        assert_eq!(
+            parse_epd(&format!("{} D1 20;\n{} D2;", start, start)),
+            Err(EpdError::Line {
+                line: 2,
+                error: Box::new(EpdError::InvalidPerft("D2 ".to_string())),
+            })
+        );
+    }
+}
@@ -1,8 +1,46 @@
 use crate::{
-    bitboard::Bitboard,
+    bitboard::{Bitboard, Direction},
     board::{Color, Piece},
+    moves::{Move, generate_bishop_movement, generate_rook_movement},
 };
 
+/// A static evaluation of positions, in pawns from white's point of view (see [`evaluate`]). Implemented by
+/// every function with the signature of [`evaluate`], such as [`simple_evaluation`] or
+/// [`larry_kaufman_evaluation`], so that the search can be run with a custom evaluation.
+///
+/// The search can also report the moves it plays and takes back, so that an incremental evaluation (e.g. the
+/// accumulator of a NNUE) can follow the position instead of computing everything from scratch. Between
+/// [`Evaluator::on_position`] and the end of the search, the position reached by the moves notified is the
+/// one given to [`Evaluator::evaluate`].
+pub trait Evaluator: Send + Sync {
+    fn evaluate(&self, board: &crate::board::Board) -> f32;
+
+    /// Whether the evaluation follows the moves of the search. Otherwise (the default) the search does not
+    /// notify it and shares it between threads without locking it.
+    fn is_incremental(&self) -> bool {
+        false
+    }
+
+    /// A search starts from `board`, which is the position followed from now on. Does nothing by default.
+    fn on_position(&mut self, _board: &crate::board::Board) {}
+
+    /// The search plays `mv` from `board`. Does nothing by default.
+    fn on_make(&mut self, _board: &crate::board::Board, _mv: Move) {}
+
+    /// The search takes back `mv`, played from `board` by the matching [`Evaluator::on_make`]. Does nothing by
+    /// default.
+    fn on_unmake(&mut self, _board: &crate::board::Board, _mv: Move) {}
+}
+
+impl<F> Evaluator for F
+where
+    F: Fn(&crate::board::Board) -> f32 + Send + Sync,
+{
+    fn evaluate(&self, board: &crate::board::Board) -> f32 {
+        self(board)
+    }
+}
+
 pub fn simple_evaluation(board: &crate::board::Board) -> f32 {
     // A very simple evaluation function that just counts material
     let mut score: f32 = 0.0;
@@ -27,16 +65,56 @@ pub fn simple_evaluation(board: &crate::board::Board) -> f32 {
     score
 }
 
+/// The terms of [`larry_kaufman_evaluation`], in pawns from white's point of view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KaufmanComponents {
+    /// Pieces other than pawns, valued according to the phase of the game.
+    pub material: f32,
+
+    /// Pawns, valued according to their structure (isolated, doubled, connected, passed) and advancement.
+    pub pawns: f32,
+
+    /// Activity of the sliding pieces, 0 unless the `eval_mobility` feature is enabled.
+    pub mobility: f32,
+
+    /// Pawn shield of the kings and open files next to them, see [`pawn_shield_score`].
+    pub king_shelter: f32,
+
+    /// Rooks on open and semi-open files, see [`rook_file_score`].
+    pub rook_files: f32,
+
+    /// Enemy pieces attacking the surroundings of the kings, see [`king_safety_score`].
+    pub king_safety: f32,
+}
+
+impl KaufmanComponents {
+    pub fn total(&self) -> f32 {
+        self.material
+            + self.pawns
+            + self.mobility
+            + self.king_shelter
+            + self.rook_files
+            + self.king_safety
+    }
+}
+
 pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
+    larry_kaufman_components(board).total()
+}
+
+/// [`larry_kaufman_evaluation`] split into its terms.
+pub fn larry_kaufman_components(board: &crate::board::Board) -> KaufmanComponents {
+    const DOUBLED_PAWN_PENALTY: f32 = 0.15;
+
     // Split between middlegame, threshold, and endgame
-    let num_white_queens = (*board.get(Piece::Queen) & board.white).count_ones();
-    let num_black_queens = (*board.get(Piece::Queen) & !board.white).count_ones();
-    // let num_white_rooks = (*board.get(Piece::Rook) & board.white).count_ones();
-    // let num_black_rooks = (*board.get(Piece::Rook) & !board.white).count_ones();
-    let num_white_bishops = (*board.get(Piece::Bishop) & board.white).count_ones();
-    let num_black_bishops = (*board.get(Piece::Bishop) & !board.white).count_ones();
-    let num_white_knights = (*board.get(Piece::Knight) & board.white).count_ones();
-    let num_black_knights = (*board.get(Piece::Knight) & !board.white).count_ones();
+    let num_white_queens = board.piece_count(Piece::WhiteQueen);
+    let num_black_queens = board.piece_count(Piece::BlackQueen);
+    // let num_white_rooks = board.piece_count(Piece::WhiteRook);
+    // let num_black_rooks = board.piece_count(Piece::BlackRook);
+    let num_white_bishops = board.piece_count(Piece::WhiteBishop);
+    let num_black_bishops = board.piece_count(Piece::BlackBishop);
+    let num_white_knights = board.piece_count(Piece::WhiteKnight);
+    let num_black_knights = board.piece_count(Piece::BlackKnight);
 
     enum GamePhase {
         Middlegame,
@@ -52,6 +130,7 @@ pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
         GamePhase::Middlegame
     };
 
+    let mut pawns: f32 = 0.0;
     let mut score: f32 = (num_white_knights as f32 - num_black_knights as f32) * 3.2
         + (num_white_bishops as f32 - num_black_bishops as f32) * 3.3
         + (num_white_queens as f32 - num_black_queens as f32) * 9.4;
@@ -62,9 +141,9 @@ pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
         } else {
             !board.white
         };
-        let num_rooks = (*board.get(Piece::Rook) & mask).count_ones();
-        let num_bishops = (*board.get(Piece::Bishop) & mask).count_ones();
-        let num_queens = (*board.get(Piece::Queen) & mask).count_ones();
+        let num_rooks = board.piece_count(Piece::Rook.with_color(color));
+        let num_bishops = board.piece_count(Piece::Bishop.with_color(color));
+        let num_queens = board.piece_count(Piece::Queen.with_color(color));
         let score_multiplier = if color == Color::White { 1.0 } else { -1.0 };
 
         match gamephase {
@@ -96,8 +175,9 @@ pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
         // Evaluate pawns based on the game phase
         let pawn_bitboard = *board.get(Piece::Pawn) & mask;
         let enemy_pawns_bitboard = *board.get(Piece::Pawn) & !mask;
-        let isolated_pawns = pawn_bitboard & !pawn_bitboard.surrounding_mask();
+        let isolated_pawns = isolated_pawns(pawn_bitboard);
         let connected_pawns = pawn_bitboard & pawn_bitboard.connected_mask(color.opposite());
+        let passed_pawns = passed_pawns(pawn_bitboard, enemy_pawns_bitboard, color);
 
         let mut pawn_score: f32 = 0.0;
         for pawn in pawn_bitboard.scan() {
@@ -112,8 +192,7 @@ pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
             // Check if isolated
             let is_isolated = isolated_pawns.get(pawn) && rank_colorless >= 2;
             let is_connected = connected_pawns.get(pawn);
-            let is_passed = rank_colorless >= 4
-                && (enemy_pawns_bitboard.0 & Bitboard::FILE[file as usize]) == 0;
+            let is_passed = rank_colorless >= 4 && passed_pawns.get(pawn);
 
             if is_passed {
                 if is_connected {
@@ -180,8 +259,192 @@ pub fn larry_kaufman_evaluation(board: &crate::board::Board) -> f32 {
             }
         }
 
+        // Every pawn stacked on the file of another one is a weakness on top of its own value
+        pawn_score -= DOUBLED_PAWN_PENALTY * doubled_pawns(pawn_bitboard).count_ones() as f32;
+
         // Add the pawn score to the total score
-        score += score_multiplier * pawn_score;
+        pawns += score_multiplier * pawn_score;
+    }
+
+    KaufmanComponents {
+        material: score,
+        pawns,
+        #[cfg(feature = "eval_mobility")]
+        mobility: mobility_score(board),
+        #[cfg(not(feature = "eval_mobility"))]
+        mobility: 0.0,
+        king_shelter: pawn_shield_score(board),
+        rook_files: rook_file_score(board),
+        king_safety: king_safety_score(board),
+    }
+}
+
+/// Pawns without any friendly pawn on the adjacent files.
+pub fn isolated_pawns(pawns: Bitboard) -> Bitboard {
+    let files = pawns.file_fill();
+    pawns & !(files.shift_east() | files.shift_west())
+}
+
+/// Pawns with a friendly pawn behind them on the same file, toward the first rank. Every pawn of a file
+/// but one is doubled, hence the count gives the number of extra pawns whatever the color.
+pub fn doubled_pawns(pawns: Bitboard) -> Bitboard {
+    pawns & pawns.occluded_fill(Bitboard::empty(), Direction::North)
+}
+
+/// Pawns of `color` that no enemy pawn can stop on their way to promotion: none of them stands ahead on the
+/// same file or on an adjacent one.
+pub fn passed_pawns(friendly: Bitboard, enemy: Bitboard, color: Color) -> Bitboard {
+    let backward = match color {
+        Color::White => Direction::South,
+        Color::Black => Direction::North,
+    };
+    let front_spans = enemy.occluded_fill(Bitboard::empty(), backward);
+    friendly & !(front_spans | front_spans.shift_east() | front_spans.shift_west())
+}
+
+/// Bonus for the activity of the sliding pieces: every square a bishop, rook or queen attacks (given the
+/// current occupancy) and that is not occupied by a piece of its own color is worth a small bonus. Positive
+/// values favor white.
+pub fn mobility_score(board: &crate::board::Board) -> f32 {
+    const MOBILITY_BONUS: f32 = 0.02;
+
+    let mut score = 0.0;
+    for color in [Color::White, Color::Black] {
+        let friendly = board.color_bitboard(color);
+        let rook_like = (*board.get(Piece::Rook) | *board.get(Piece::Queen)) & friendly;
+        let bishop_like = (*board.get(Piece::Bishop) | *board.get(Piece::Queen)) & friendly;
+
+        let mut squares = 0;
+        for piece in rook_like.scan_bitboard() {
+            squares += (generate_rook_movement(board.occupied, piece) & !friendly).count_ones();
+        }
+        for piece in bishop_like.scan_bitboard() {
+            squares += (generate_bishop_movement(board.occupied, piece) & !friendly).count_ones();
+        }
+        score += color.score_multiplier() * squares as f32 * MOBILITY_BONUS;
+    }
+
+    score
+}
+
+/// Scaling factor applied to the evaluation of drawish material configurations.
+///
+/// When the only pieces left besides kings and pawns are one bishop per side on squares of opposite
+/// colors, a material edge (even of a pawn or two) is rarely convertible, therefore the score is pulled
+/// toward 0 so the engine does not overvalue it.
+pub fn drawish_scale(board: &crate::board::Board) -> f32 {
+    const OPPOSITE_COLORED_BISHOPS_SCALE: f32 = 0.5;
+
+    let heavy_or_knights =
+        *board.get(Piece::Knight) | *board.get(Piece::Rook) | *board.get(Piece::Queen);
+    if !heavy_or_knights.is_empty() {
+        return 1.0;
+    }
+
+    let bishops = *board.get(Piece::Bishop);
+    let white_bishops = bishops & board.white;
+    let black_bishops = bishops & !board.white;
+    if white_bishops.count_ones() != 1 || black_bishops.count_ones() != 1 {
+        return 1.0;
+    }
+
+    let light_squares = Bitboard(Bitboard::LIGHT_SQUARES);
+    let white_on_light = !(white_bishops & light_squares).is_empty();
+    let black_on_light = !(black_bishops & light_squares).is_empty();
+    if white_on_light != black_on_light {
+        OPPOSITE_COLORED_BISHOPS_SCALE
+    } else {
+        1.0
+    }
+}
+
+/// Bonus for rooks standing on files without friendly pawns (semi-open) or without any pawn (open),
+/// from where they can pressure the enemy position. Positive values favor white.
+pub fn rook_file_score(board: &crate::board::Board) -> f32 {
+    const OPEN_FILE_BONUS: f32 = 0.25;
+    const SEMI_OPEN_FILE_BONUS: f32 = 0.10;
+
+    let pawns = *board.get(Piece::Pawn);
+    let open_files = Bitboard::open_files(pawns);
+
+    let mut score = 0.0;
+    for color in [Color::White, Color::Black] {
+        let mask = if color == Color::White {
+            board.white
+        } else {
+            !board.white
+        };
+        let rooks = *board.get(Piece::Rook) & mask;
+        let semi_open_files = Bitboard::open_files(pawns & mask) & !open_files;
+
+        let bonus = (rooks & open_files).count_ones() as f32 * OPEN_FILE_BONUS
+            + (rooks & semi_open_files).count_ones() as f32 * SEMI_OPEN_FILE_BONUS;
+        score += color.score_multiplier() * bonus;
+    }
+
+    score
+}
+
+/// Penalty for kings missing the pawns of their shield (the three squares in front of them) and for the
+/// files around them without any friendly pawn, along which the enemy heavy pieces get at the king. Only
+/// counted while the enemy has a queen, the king has to come out in the endgame anyway. Positive values favor
+/// white.
+pub fn pawn_shield_score(board: &crate::board::Board) -> f32 {
+    const MISSING_SHIELD_PAWN_PENALTY: f32 = 0.05;
+    const KING_FILE_PENALTY: f32 = 0.10;
+
+    let pawns = *board.get(Piece::Pawn);
+    let mut score = 0.0;
+    for color in [Color::White, Color::Black] {
+        let friendly = board.color_bitboard(color);
+        let king = *board.get(Piece::King) & friendly;
+        let enemy_queens = *board.get(Piece::Queen) & board.color_bitboard(color.opposite());
+        if king.is_empty() || enemy_queens.is_empty() {
+            continue;
+        }
+
+        let rank = (king.square() / 8) as usize;
+        let ahead = match color {
+            Color::White => (rank < 7).then_some(rank + 1),
+            Color::Black => rank.checked_sub(1),
+        };
+        let shield = ahead.map_or(Bitboard::empty(), |ahead| {
+            king.surrounding_mask() & Bitboard(Bitboard::RANK[ahead])
+        });
+        let missing = (shield & !(pawns & friendly)).count_ones();
+
+        // One square per file next to the king (its own included) without a friendly pawn
+        let king_files = (king | king.shift_east() | king.shift_west()).file_fill();
+        let open_files =
+            king_files & Bitboard::open_files(pawns & friendly) & Bitboard(Bitboard::RANK_1);
+
+        let penalty = missing as f32 * MISSING_SHIELD_PAWN_PENALTY
+            + open_files.count_ones() as f32 * KING_FILE_PENALTY;
+        score -= color.score_multiplier() * penalty;
+    }
+
+    score
+}
+
+/// King safety penalty (in centipawns) indexed by the weight of the attack on the king zone, see
+/// [`crate::board::Board::king_zone_attack`]. The penalty grows faster than linearly, since coordinated
+/// attackers are much more dangerous than isolated ones.
+const KING_SAFETY_TABLE: [u32; 24] = [
+    0, 0, 1, 2, 3, 5, 7, 9, 12, 15, 18, 22, 26, 30, 35, 39, 44, 50, 56, 62, 68, 75, 82, 89,
+];
+
+/// Penalty for kings whose surroundings are attacked by at least two enemy pieces. Positive values
+/// favor white.
+pub fn king_safety_score(board: &crate::board::Board) -> f32 {
+    let mut score = 0.0;
+    for color in [Color::White, Color::Black] {
+        let (attackers, attack_weight) = board.king_zone_attack(color);
+        if attackers < 2 {
+            continue;
+        }
+
+        let index = (attack_weight as usize).min(KING_SAFETY_TABLE.len() - 1);
+        score -= color.score_multiplier() * KING_SAFETY_TABLE[index] as f32 / 100.0;
     }
 
     score
@@ -191,11 +454,221 @@ pub fn evaluate(board: &crate::board::Board) -> f32 {
     // For now, we just use the simple evaluation function, but this is where we would implement a more complex evaluation
     // simple_evaluation(board)
     #[cfg(feature = "eval_larry_kaufman")]
-    {
-        larry_kaufman_evaluation(board)
-    }
+    let score = larry_kaufman_evaluation(board);
     #[cfg(not(feature = "eval_larry_kaufman"))]
-    {
-        simple_evaluation(board)
+    let score = simple_evaluation(board);
+
+    score * drawish_scale(board)
+}
+
+/// [`evaluate`] from the point of view of the side to move rather than white's, as used by a negamax search:
+/// positive when the side to move is ahead.
+pub fn evaluate_stm(board: &crate::board::Board) -> f32 {
+    evaluate(board) * board.next_to_move().score_multiplier()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bitboard::Square, board::Board};
+
+    fn pawns(squares: &[&str]) -> Bitboard {
+        squares.iter().fold(Bitboard::empty(), |pawns, square| {
+            pawns | Square::from_algebraic(square).unwrap().bitboard()
+        })
+    }
+
+    #[test]
+    fn test_isolated_pawns() {
+        // The c-pawns are both doubled and isolated, the e and f pawns support each other
+        let white = pawns(&["a2", "c2", "c3", "e4", "f5", "h2"]);
+        assert_eq!(isolated_pawns(white), pawns(&["a2", "c2", "c3", "h2"]));
+        assert_eq!(isolated_pawns(pawns(&["a2", "b7"])), Bitboard::empty());
+        assert_eq!(isolated_pawns(Bitboard::empty()), Bitboard::empty());
+    }
+
+    #[test]
+    fn test_doubled_pawns() {
+        // One pawn of each file is not counted, whatever the color: the count is the number of extra pawns
+        let white = pawns(&["a2", "c2", "c3", "d2", "d3", "d5", "h2"]);
+        assert_eq!(doubled_pawns(white), pawns(&["c3", "d3", "d5"]));
+        assert_eq!(doubled_pawns(white).count_ones(), 3);
+        assert_eq!(doubled_pawns(pawns(&["a2", "b3", "h7"])), Bitboard::empty());
+
+        // A doubled and isolated pawn is counted once in each category
+        let white = pawns(&["c2", "c3", "e4", "f5"]);
+        assert_eq!(isolated_pawns(white) & doubled_pawns(white), pawns(&["c3"]));
+    }
+
+    #[test]
+    fn test_passed_pawns() {
+        // The d7 pawn stops e5 (it can capture on e6) but not a5, the b4 pawn is already behind
+        let white = pawns(&["a5", "e5", "h2"]);
+        let black = pawns(&["b4", "d7", "h7"]);
+        assert_eq!(passed_pawns(white, black, Color::White), pawns(&["a5"]));
+
+        // Same for black, moving toward the first rank: e5 stops d7 as well
+        assert_eq!(passed_pawns(black, white, Color::Black), pawns(&["b4"]));
+
+        // Pawns side by side do not stop each other
+        assert_eq!(
+            passed_pawns(pawns(&["d5"]), pawns(&["e5"]), Color::White),
+            pawns(&["d5"])
+        );
+    }
+
+    #[test]
+    fn test_pawn_structure_in_evaluation() {
+        // Same material, the doubled and isolated c-pawns are worth less than the connected pair
+        let doubled = Board::from_fen("4k3/8/8/8/8/2P5/2P5/4K3 w - - 0 1").unwrap();
+        let connected = Board::from_fen("4k3/8/8/8/8/3P4/2P5/4K3 w - - 0 1").unwrap();
+        assert!(
+            larry_kaufman_components(&doubled).pawns < larry_kaufman_components(&connected).pawns
+        );
+    }
+
+    #[test]
+    fn test_adjacent_file_stops_passed_pawn() {
+        // The d7 pawn can capture the e5 pawn on e6, which is then no more passed than with a pawn in front of
+        // it on e7 (d7 and e7 are worth the same to black)
+        let adjacent = Board::from_fen("4k3/3p4/8/4P3/8/8/8/4K3 w - - 0 1").unwrap();
+        let in_front = Board::from_fen("4k3/4p3/8/4P3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            larry_kaufman_components(&adjacent).pawns,
+            larry_kaufman_components(&in_front).pawns
+        );
+
+        // Out of reach on the b-file, the same pawn gets the passed pawn bonus
+        let out_of_reach = Board::from_fen("4k3/1p6/8/4P3/8/8/8/4K3 w - - 0 1").unwrap();
+        let passed_white_pawns = |board: &Board| {
+            let white = *board.get(Piece::Pawn) & board.white;
+            passed_pawns(white, *board.get(Piece::Pawn) & !board.white, Color::White)
+        };
+        assert!(passed_white_pawns(&adjacent).is_empty());
+        assert!(!passed_white_pawns(&out_of_reach).is_empty());
+    }
+
+    #[test]
+    fn test_pawn_shield() {
+        // Castled king behind its f, g and h pawns, or with the three of them pushed two squares
+        let intact = Board::from_fen("3qk3/8/8/8/8/8/5PPP/3Q2K1 w - - 0 1").unwrap();
+        let advanced = Board::from_fen("3qk3/8/8/8/5PPP/8/8/3Q2K1 w - - 0 1").unwrap();
+        assert!(pawn_shield_score(&intact) > pawn_shield_score(&advanced));
+        assert!(
+            larry_kaufman_components(&intact).king_shelter
+                > larry_kaufman_components(&advanced).king_shelter
+        );
+        assert!(larry_kaufman_evaluation(&intact) > larry_kaufman_evaluation(&advanced));
+
+        // The files of the king are penalized once their pawn is gone, even if the shield is not fully broken
+        let open_g_file = Board::from_fen("3qk3/8/8/8/8/8/5P1P/3Q2K1 w - - 0 1").unwrap();
+        let pushed_g_pawn = Board::from_fen("3qk3/8/8/8/8/6P1/5P1P/3Q2K1 w - - 0 1").unwrap();
+        assert!(pawn_shield_score(&pushed_g_pawn) > pawn_shield_score(&open_g_file));
+
+        // Mirrored for black, and nothing is counted without queens
+        assert_eq!(
+            pawn_shield_score(&advanced.flip_colors()),
+            -pawn_shield_score(&advanced)
+        );
+        let endgame = Board::from_fen("4k3/8/8/8/5PPP/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(pawn_shield_score(&endgame), 0.0);
+    }
+
+    #[test]
+    fn test_evaluate_stm() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "6k1/pp3ppp/8/8/8/8/PP2PPPP/3R2K1 w - - 0 1",
+            "6k1/pp3ppp/8/8/8/8/PP2PPPP/3R2K1 b - - 0 1",
+            "3R2k1/5ppp/7Q/8/8/8/5PPP/6K1 b - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(
+                evaluate_stm(&board),
+                board.next_to_move().score_multiplier() * evaluate(&board),
+                "{}",
+                fen
+            );
+        }
+
+        // The side ahead is the side to move in one position and the opponent in the other
+        let white_to_move = Board::from_fen("6k1/pp3ppp/8/8/8/8/PP2PPPP/3R2K1 w - - 0 1").unwrap();
+        let black_to_move = Board::from_fen("6k1/pp3ppp/8/8/8/8/PP2PPPP/3R2K1 b - - 0 1").unwrap();
+        assert!(evaluate_stm(&white_to_move) > 0.0);
+        assert!(evaluate_stm(&black_to_move) < 0.0);
+    }
+
+    #[test]
+    fn test_opposite_colored_bishops_dampening() {
+        // White is two pawns up, but the light-squared bishop can't challenge the dark-squared one
+        let board = Board::from_fen("8/4k3/8/2b5/8/8/P1B1K1P1/8 w - - 0 1").unwrap();
+        assert_eq!(drawish_scale(&board), 0.5);
+        assert!(evaluate(&board) > 0.0);
+        assert!(evaluate(&board) < simple_evaluation(&board));
+
+        // Same-colored bishops are not dampened
+        let board = Board::from_fen("8/4k3/8/3b4/8/8/P1B1K1P1/8 w - - 0 1").unwrap();
+        assert_eq!(drawish_scale(&board), 1.0);
+
+        // Neither are positions with other pieces on the board
+        let board = Board::from_fen("8/4k3/8/2b5/8/8/P1B1K1P1/7R w - - 0 1").unwrap();
+        assert_eq!(drawish_scale(&board), 1.0);
+    }
+
+    #[test]
+    fn test_king_safety_attacking_queen() {
+        // Adding a queen next to the black king on top of the rook increases the attack weight substantially
+        let rook_only = Board::from_fen("3R2k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        let with_queen = Board::from_fen("3R2k1/5ppp/7Q/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        let (_, rook_weight) = rook_only.king_zone_attack(Color::Black);
+        let (_, queen_weight) = with_queen.king_zone_attack(Color::Black);
+        assert!(queen_weight >= rook_weight + 5);
+
+        assert_eq!(king_safety_score(&rook_only), 0.0);
+        assert!(king_safety_score(&with_queen) > 0.0);
+    }
+
+    #[test]
+    fn test_mobility_open_diagonal() {
+        // The d2 pawn blocks the c1 bishop, pushing it frees the c1-h6 diagonal
+        let closed = Board::from_fen("4k3/8/8/8/8/8/3P4/2B1K3 w - - 0 1").unwrap();
+        let open = Board::from_fen("4k3/8/8/8/8/3P4/8/2B1K3 w - - 0 1").unwrap();
+        assert!(mobility_score(&open) > mobility_score(&closed));
+        assert!(mobility_score(&closed) > 0.0);
+
+        // Mirrored for black
+        assert!(mobility_score(&open.flip_colors()) < mobility_score(&closed.flip_colors()));
+        assert_eq!(mobility_score(&open.flip_colors()), -mobility_score(&open));
+    }
+
+    #[test]
+    fn test_rook_on_open_file() {
+        // Identical positions except for the rook, on the open d-file or behind its own e-pawn
+        let open = Board::from_fen("6k1/pp3ppp/8/8/8/8/PP2PPPP/3R2K1 w - - 0 1").unwrap();
+        let closed = Board::from_fen("6k1/pp3ppp/8/8/8/8/PP2PPPP/4R1K1 w - - 0 1").unwrap();
+        assert!(rook_file_score(&open) > 0.0);
+        assert_eq!(rook_file_score(&closed), 0.0);
+        assert!(larry_kaufman_evaluation(&open) > larry_kaufman_evaluation(&closed));
+
+        // A semi-open file (only enemy pawns) is rewarded less than an open one
+        let semi_open = Board::from_fen("6k1/pp1p1ppp/8/8/8/8/PP2PPPP/3R2K1 w - - 0 1").unwrap();
+        assert!(rook_file_score(&semi_open) > 0.0);
+        assert!(rook_file_score(&semi_open) < rook_file_score(&open));
+    }
+
+    #[test]
+    fn test_kaufman_components() {
+        let board = Board::default_position();
+        let components = larry_kaufman_components(&board);
+        assert!(components.material.abs() < 1e-5); // Symmetric up to rounding
+        assert!(components.pawns.abs() < 1e-5);
+        assert_eq!(components.total(), larry_kaufman_evaluation(&board));
+
+        // White is a knight down for a far advanced passed pawn
+        let board = Board::from_fen("4k3/1P6/8/8/8/8/8/1n2K3 w - - 0 1").unwrap();
+        let components = larry_kaufman_components(&board);
+        assert!((components.material + 3.2).abs() < 1e-5);
+        assert!((components.pawns - 3.5).abs() < 1e-5);
+        assert_eq!(components.total(), larry_kaufman_evaluation(&board));
     }
 }
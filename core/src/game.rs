@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    board::Board,
+    moves::{Move, UndoInfo, generate_moves},
+};
+
+/// A game, i.e. a starting position and the sequence of moves played from it, alongside the tag pairs
+/// describing it (event, players, result... as found in PGN files).
+///
+/// Moves are played with [`Game::push`] and taken back with [`Game::pop`], which keep the current position and
+/// the zobrist key reached after every move, so that repetitions over the whole game can be detected.
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub tags: BTreeMap<String, String>,
+    start: Board,
+    board: Board,
+    moves: Vec<(Move, UndoInfo)>, // Moves played, with what is needed to take them back
+    keys: Vec<u64>, // Zobrist key of the starting position, then of the position after each move
+}
+
+impl Game {
+    /// A game without any move nor tag, starting from the given position.
+    pub fn new(start: Board) -> Self {
+        Game {
+            tags: BTreeMap::new(),
+            start,
+            board: start,
+            moves: Vec::new(),
+            keys: vec![start.zobrist],
+        }
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> Board {
+        self.start
+    }
+
+    /// The moves played since the starting position.
+    pub fn moves(&self) -> Vec<Move> {
+        self.moves.iter().map(|(mv, _)| *mv).collect()
+    }
+
+    /// Number of moves (plies) played since the starting position.
+    pub fn ply_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// The current position, i.e. the starting position once all of the moves have been played.
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    /// The legal moves in the current position.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&self.board, &mut moves, &mut currently_in_check);
+        moves
+    }
+
+    /// Play a move on the current position, which is expected to be legal there.
+    pub fn push(&mut self, mv: Move) {
+        let undo = mv.make(&mut self.board);
+        self.moves.push((mv, undo));
+        self.keys.push(self.board.zobrist);
+    }
+
+    /// Take back the last move played, `None` if the game is back to its starting position.
+    pub fn pop(&mut self) -> Option<Move> {
+        let (mv, undo) = self.moves.pop()?;
+        mv.unmake(&mut self.board, undo);
+        self.keys.pop();
+        Some(mv)
+    }
+
+    /// Number of times the current position occurred in the game, itself included.
+    pub fn repetitions(&self) -> usize {
+        let current = self.board.zobrist;
+        self.keys.iter().filter(|&&key| key == current).count()
+    }
+
+    /// Whether the current position occurred (at least) three times in the game, which allows claiming a
+    /// draw.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Board::default_position())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut game = Game::new(Board::from_fen("4k1n1/8/8/8/8/8/8/4K1N1 w - - 0 1").unwrap());
+        assert_eq!(game.repetitions(), 1);
+
+        // Each round trip of the knights comes back to the starting position
+        for round in 0..2 {
+            for (ply, uci) in ["g1f3", "g8f6", "f3g1", "f6g8"].into_iter().enumerate() {
+                assert!(
+                    !game.is_threefold_repetition(),
+                    "round {}, ply {}",
+                    round,
+                    ply
+                );
+                game.push(Move::from_uci(uci, &game.board()).unwrap());
+            }
+            assert_eq!(game.repetitions(), round + 2);
+        }
+        assert!(game.is_threefold_repetition());
+        assert_eq!(game.ply_count(), 8);
+        assert_eq!(game.board().zobrist, game.start().zobrist);
+
+        // Moving away ends the repetition, taking the move back restores it
+        game.push(Move::from_uci("e1e2", &game.board()).unwrap());
+        assert!(!game.is_threefold_repetition());
+        game.pop();
+        assert!(game.is_threefold_repetition());
+    }
+
+    #[test]
+    fn test_push_pop_restores_positions() {
+        use rand::{RngExt, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x1783);
+        for fen in [
+            Board::DEFAULT_POSITION_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/1P1p4/8/4P3/8/8/8/R3K2R b KQkq - 0 1",
+        ] {
+            let mut game = Game::new(Board::from_fen(fen).unwrap());
+            assert_eq!(game.pop(), None);
+
+            // Random legal moves, remembering every position reached
+            let mut played = Vec::new();
+            for _ in 0..40 {
+                let moves = game.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                let mv = moves[rng.random_range(0..moves.len())];
+                played.push((mv, game.board()));
+                game.push(mv);
+            }
+            assert_eq!(game.ply_count(), played.len());
+            assert_eq!(
+                game.moves(),
+                played.iter().map(|(mv, _)| *mv).collect::<Vec<_>>()
+            );
+
+            // Each move taken back restores the position it was played in
+            while let Some((mv, before)) = played.pop() {
+                assert_eq!(game.pop(), Some(mv));
+                assert_eq!(
+                    game.board(),
+                    before,
+                    "after taking back {} in \"{}\"",
+                    mv,
+                    fen
+                );
+            }
+            assert_eq!(game.pop(), None);
+            assert_eq!(
+                game.board().fen().to_string(),
+                game.start().fen().to_string()
+            );
+            assert_eq!(game.repetitions(), 1);
+        }
+    }
+}
@@ -1,9 +1,15 @@
 pub mod ai;
 pub mod bitboard;
 pub mod board;
+pub mod book;
+pub mod epd;
 pub mod eval;
+pub mod game;
 pub mod magic;
 pub mod moves;
+pub mod outcome;
+pub mod pgn;
 #[cfg(feature = "study")]
 pub mod study;
 pub mod tree;
+pub mod zobrist;
@@ -1,3 +1,9 @@
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    sync::OnceLock,
+};
+
 use crate::bitboard::Bitboard;
 #[cfg(not(debug_assertions))]
 use rand::{RngExt, SeedableRng};
@@ -20,7 +26,26 @@ pub struct Magic {
     attack_table: Vec<u64>,
 }
 
+static MAGIC: OnceLock<Magic> = OnceLock::new();
+
+/// Header of the serialized tables, followed by the format version.
+const FILE_SIGNATURE: &[u8; 4] = b"CHMG";
+const FILE_VERSION: u32 = 1;
+
+#[cfg(not(debug_assertions))]
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
 impl Magic {
+    /// The shared tables, generated on first use. Debug builds skip the generation and fall back to the
+    /// iterative raycasts.
+    pub fn global() -> &'static Magic {
+        MAGIC.get_or_init(Magic::generate)
+    }
+
     fn bishop_legacy_raycast(square: u8, occ: Bitboard) -> u64 {
         Bitboard(1 << square).bishop_raycast(occ).0
     }
@@ -206,6 +231,106 @@ impl Magic {
         Self {}
     }
 
+    /// Write the tables to the given file, so that they can be loaded back with [`Magic::load`] instead of
+    /// being generated again. The format is a small header followed by the bishop and rook entries and the
+    /// attack table, all little-endian. Debug builds have no table, only the header is written.
+    pub fn serialize(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+        writer.write_all(FILE_SIGNATURE)?;
+        writer.write_all(&FILE_VERSION.to_le_bytes())?;
+
+        #[cfg(not(debug_assertions))]
+        {
+            for entry in self.bishop.iter().chain(self.rook.iter()) {
+                writer.write_all(&entry.mask.to_le_bytes())?;
+                writer.write_all(&entry.magic.to_le_bytes())?;
+                writer.write_all(&(entry.offset as u64).to_le_bytes())?;
+                writer.write_all(&[entry.bits])?;
+            }
+
+            writer.write_all(&(self.attack_table.len() as u64).to_le_bytes())?;
+            for attacks in &self.attack_table {
+                writer.write_all(&attacks.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Read tables written by [`Magic::serialize`]. Fails with [`io::ErrorKind::InvalidData`] if the file is
+    /// not a table of the current format.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Magic> {
+        let mut reader = io::BufReader::new(std::fs::File::open(path)?);
+
+        let mut signature = [0; 4];
+        reader.read_exact(&mut signature)?;
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        if &signature != FILE_SIGNATURE || u32::from_le_bytes(version) != FILE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a magic table file",
+            ));
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            Ok(Magic {})
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            let mut entries = [SMagic::default(); 128];
+            for entry in entries.iter_mut() {
+                entry.mask = read_u64(&mut reader)?;
+                entry.magic = read_u64(&mut reader)?;
+                entry.offset = read_u64(&mut reader)? as usize;
+                let mut bits = [0; 1];
+                reader.read_exact(&mut bits)?;
+                entry.bits = bits[0];
+            }
+
+            let len = read_u64(&mut reader)? as usize;
+            let mut attack_table = Vec::with_capacity(len.min(1 << 20));
+            for _ in 0..len {
+                attack_table.push(read_u64(&mut reader)?);
+            }
+
+            // Lookups index the attack table without checks, make sure they stay in bounds
+            if entries.iter().any(|entry| {
+                entry.bits == 0 || entry.bits > 12 || entry.offset + (1 << entry.bits) > len
+            }) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Magic entry out of the attack table",
+                ));
+            }
+
+            let mut bishop = [SMagic::default(); 64];
+            let mut rook = [SMagic::default(); 64];
+            bishop.copy_from_slice(&entries[..64]);
+            rook.copy_from_slice(&entries[64..]);
+            Ok(Magic {
+                bishop,
+                rook,
+                attack_table,
+            })
+        }
+    }
+
+    /// Load the tables from the given file, or generate them and save them there if the file does not hold
+    /// valid tables. Only failing to save the generated tables is reported.
+    pub fn generate_or_load(path: impl AsRef<Path>) -> io::Result<Magic> {
+        match Magic::load(&path) {
+            Ok(magic) => Ok(magic),
+            Err(_) => {
+                let magic = Magic::generate();
+                magic.serialize(&path)?;
+                Ok(magic)
+            }
+        }
+    }
+
     #[cfg(debug_assertions)]
     pub fn bishop_raycast(&self, square: u8, occupancy: Bitboard) -> Bitboard {
         Bitboard(Self::bishop_legacy_raycast(square, occupancy))
@@ -267,6 +392,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_serialize_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("chessoteric_magic_{}.bin", std::process::id()));
+        let magic = Magic::generate();
+        magic.serialize(&path).unwrap();
+        let loaded = Magic::load(&path).unwrap();
+
+        let mut seeded_rng = rand::rngs::StdRng::seed_from_u64(0x42);
+        for square in 0..64 {
+            for _ in 0..100 {
+                let occupancy = Bitboard(seeded_rng.random::<u64>() & seeded_rng.random::<u64>());
+                assert_eq!(
+                    magic.rook_raycast(square, occupancy),
+                    loaded.rook_raycast(square, occupancy)
+                );
+                assert_eq!(
+                    magic.bishop_raycast(square, occupancy),
+                    loaded.bishop_raycast(square, occupancy)
+                );
+            }
+        }
+
+        // Loading an existing file does not generate (nor rewrite) anything
+        assert!(Magic::generate_or_load(&path).is_ok());
+
+        std::fs::write(&path, b"not a table").unwrap();
+        assert_eq!(
+            Magic::load(&path).err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn test_magic_bishop() {
         let magic = Magic::generate();
@@ -1,8 +1,10 @@
 use crate::{
-    bitboard::{Bitboard, Direction, algebraic_to_square, square_to_algebraic},
-    board::{Board, BoardFlags, Color, Piece},
+    bitboard::{Bitboard, Direction, Square, algebraic_to_square, square_to_algebraic},
+    board::{Board, BoardFlags, CastleSide, Color, Piece, SquareCentricBoard},
+    zobrist::ZOBRIST,
 };
 use bitflags::bitflags;
+use strum::IntoEnumIterator;
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,7 +23,30 @@ pub struct Move {
     pub flags: MoveFlags,
 }
 
+/// State that can't be recovered from a move alone, returned by [`Move::make`] to revert it with
+/// [`Move::unmake`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+    /// The (colorless) piece standing on the destination square before the move, en passant captures
+    /// are deduced from the move flags instead.
+    pub captured: Option<Piece>,
+    pub flags: BoardFlags,
+    pub en_passant_square: u8,
+    pub halfmove_clock: u16,
+    pub zobrist: u64,
+}
+
 impl Move {
+    /// The square the piece leaves, as a [`Square`].
+    pub fn from_square(&self) -> Square {
+        Square::from_index(self.from).expect("Move origin out of range")
+    }
+
+    /// The square the piece lands on (the king's destination for a castle), as a [`Square`].
+    pub fn to_square(&self) -> Square {
+        Square::from_index(self.to).expect("Move destination out of range")
+    }
+
     fn display_castle(&self) -> Option<&'static str> {
         if self.flags.contains(MoveFlags::CASTLE) {
             match self.to {
@@ -34,6 +59,15 @@ impl Move {
         }
     }
 
+    /// The side of the castling, `None` if the move is not a castling move.
+    pub fn castle_side(&self) -> Option<CastleSide> {
+        if self.piece == Piece::King && self.flags.contains(MoveFlags::CASTLE) {
+            CastleSide::from_king_destination(self.to)
+        } else {
+            None
+        }
+    }
+
     pub fn algebraic_notation<'a>(
         &'a self,
         board: &'a Board,
@@ -80,12 +114,7 @@ impl Move {
                     needs_source_disambiguation && !file_disambiguation_sufficient;
 
                 // Determine if this is a check or checkmate move, to include the + or # symbol in the move notation
-                let mut board_after_move = self.board.clone();
-                self.r#move.apply(&mut board_after_move);
-                let mut mvs = Vec::new();
-                let mut currently_in_check = false;
-                generate_moves(&board_after_move, &mut mvs, &mut currently_in_check);
-                let is_checkmate = mvs.is_empty() && currently_in_check;
+                let (gives_check, is_checkmate) = self.r#move.check_status(self.board);
 
                 // Finally, construct the move string
                 let piece_str = if needs_piece_symbol {
@@ -122,7 +151,7 @@ impl Move {
                 // If this is a check move, we need to include the '+' symbol in the move
                 let check_str = if is_checkmate {
                     "#"
-                } else if currently_in_check {
+                } else if gives_check {
                     "+"
                 } else {
                     ""
@@ -150,6 +179,48 @@ impl Move {
         }
     }
 
+    /// Standard algebraic notation of the move, the legal moves of the board used for disambiguation being
+    /// generated internally. Prefer [`Move::algebraic_notation`] when formatting several moves of the same
+    /// position.
+    pub fn san(&self, board: &Board) -> String {
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(board, &mut moves, &mut currently_in_check);
+        self.algebraic_notation(board, &moves).to_string()
+    }
+
+    /// Apply the move on a copy of the board, returns whether the opponent is then in check and whether
+    /// it is checkmate.
+    fn check_status(&self, board: &Board) -> (bool, bool) {
+        let mut board_after_move = *board;
+        self.apply(&mut board_after_move);
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&board_after_move, &mut moves, &mut currently_in_check);
+        (currently_in_check, currently_in_check && moves.is_empty())
+    }
+
+    /// Whether playing this move on the given board puts the opponent in check (including checkmate).
+    pub fn gives_check(&self, board: &Board) -> bool {
+        self.check_status(board).0
+    }
+
+    /// Whether playing this move on the given board checkmates the opponent.
+    pub fn gives_checkmate(&self, board: &Board) -> bool {
+        self.check_status(board).1
+    }
+
+    /// UCI notation of the move followed by `+` if it gives check or `#` if it gives checkmate, e.g.
+    /// `d8h4#`. Meant for logs and `info` lines, the result is not valid UCI.
+    pub fn uci_annotated(&self, board: &Board) -> String {
+        let suffix = match self.check_status(board) {
+            (_, true) => "#",
+            (true, false) => "+",
+            (false, false) => "",
+        };
+        format!("{}{}", self.to_uci(board), suffix)
+    }
+
     pub fn uci(&self) -> impl std::fmt::Display + '_ {
         struct UciMove<'a>(&'a Move);
 
@@ -177,13 +248,38 @@ impl Move {
         UciMove(self)
     }
 
+    /// UCI notation of the move played on the given board, which [`Move::from_uci`] reads back. Castling is
+    /// written as the king capturing its own rook (e.g. `c1b1`) unless it is the usual `e1g1`, `e1c1`, `e8g8`
+    /// or `e8c8`, as the destination of the king alone may be a plain king move (or no move at all) in Chess960.
+    pub fn to_uci(&self, board: &Board) -> String {
+        let standard = matches!((self.from, self.to), (4, 6) | (4, 2) | (60, 62) | (60, 58));
+        if !self.flags.contains(MoveFlags::CASTLE) || standard {
+            return self.uci().to_string();
+        }
+
+        let side = if self.to % 8 == 6 {
+            CastleSide::KingSide
+        } else {
+            CastleSide::QueenSide
+        };
+        let rook_square = board.castling_rook_square(board.next_to_move(), side);
+        format!(
+            "{}{}",
+            square_to_algebraic(self.from),
+            square_to_algebraic(rook_square)
+        )
+    }
+
+    /// Parse a move in UCI notation (e.g. `e2e4`, `e7e8q`), without checking that it is legal. Castling is
+    /// written either with the destination of the king (`e1g1`) or as the king capturing its own rook (`e1h1`),
+    /// the latter being the only unambiguous way in Chess960.
     pub fn from_uci(value: &str, board: &Board) -> Option<Self> {
         if value.len() < 4 {
             return None;
         }
 
         let from = algebraic_to_square(&value[0..2])?;
-        let to = algebraic_to_square(&value[2..4])?;
+        let mut to = algebraic_to_square(&value[2..4])?;
 
         let promotion = if value.len() > 4 {
             match value.chars().nth(4)? {
@@ -197,17 +293,27 @@ impl Move {
             None
         };
 
-        let mut current_piece = None;
-        for piece in Piece::colorless_iter() {
-            if board.get(piece).get(from) {
-                current_piece = Some(piece);
-                break;
-            }
+        let moving_piece = board.piece_at(from)?;
+        let current_piece = moving_piece.colorless();
+
+        // A king "capturing" a rook of its own color castles with it, which is how castling is written in Chess960
+        let color = moving_piece.color();
+        let castles_with_rook = current_piece == Piece::King
+            && board.get(Piece::Rook).get(to)
+            && board.color_bitboard(color).get(to);
+        if castles_with_rook {
+            let side = if to > from {
+                CastleSide::KingSide
+            } else {
+                CastleSide::QueenSide
+            };
+            to = side.king_destination(color);
         }
-        let current_piece = current_piece?;
 
         // If pawn and diagonal move without destination piece, it is an en passant capture
-        let flags = if current_piece == Piece::Pawn
+        let flags = if castles_with_rook {
+            MoveFlags::CASTLE
+        } else if current_piece == Piece::Pawn
             && (from as i8 - to as i8).abs() % 8 != 0
             && !board.occupied.get(to)
         {
@@ -220,39 +326,258 @@ impl Move {
             MoveFlags::empty()
         };
 
-        Some(Move {
+        let mv = Move {
             from,
             to,
             piece: current_piece,
             promotion,
             flags,
-        })
+        };
+
+        // A pawn reaching the last rank must promote, and only such a move can carry a promotion piece
+        if mv.requires_promotion(board) != promotion.is_some() {
+            return None;
+        }
+
+        Some(mv)
     }
 
-    pub fn apply(&self, board: &mut Board) {
-        // Remove all pieces of all bitboards on the destination square, to handle captures and promotions
-        for bitboard in board.bitboards.iter_mut() {
-            bitboard.unset(self.to);
+    /// Parse a move in standard algebraic notation (e.g. `Nbd7`, `exd6`, `e8=Q+`, `O-O`) and resolve it
+    /// against the legal moves of the board. Check and annotation suffixes are ignored. Returns `None` if the
+    /// notation is invalid, or if it matches no legal move or more than one.
+    pub fn from_san(value: &str, board: &Board) -> Option<Self> {
+        let san = value.trim_end_matches(['+', '#', '!', '?']);
+        if !san.is_ascii() {
+            return None;
+        }
+
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(board, &mut moves, &mut currently_in_check);
+
+        let castle_side = match san {
+            "O-O" | "0-0" => Some(CastleSide::KingSide),
+            "O-O-O" | "0-0-0" => Some(CastleSide::QueenSide),
+            _ => None,
+        };
+        if let Some(side) = castle_side {
+            let to = side.king_destination(board.next_to_move());
+            return moves
+                .into_iter()
+                .find(|mv| mv.flags.contains(MoveFlags::CASTLE) && mv.to == to);
+        }
+
+        // Promotion piece, written either as `e8=Q` or `e8Q`
+        let (san, promotion) = match san.as_bytes() {
+            [.., b'=', piece] | [.., b'1'..=b'8', piece] if b"NBRQ".contains(piece) => (
+                san[..san.len() - 1].trim_end_matches('='),
+                Some(Piece::from_fen_char(*piece as char)?),
+            ),
+            _ => (san, None),
+        };
+
+        let (piece, san) = match san.as_bytes().first()? {
+            piece @ (b'N' | b'B' | b'R' | b'Q' | b'K') => (
+                (*piece as char).to_string().parse::<Piece>().ok()?,
+                &san[1..],
+            ),
+            _ => (Piece::Pawn, san),
+        };
+
+        // Destination square last, preceded by the optional disambiguation and capture marker
+        if san.len() < 2 {
+            return None;
+        }
+        let to = algebraic_to_square(&san[san.len() - 2..])?;
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in san[..san.len() - 2].trim_end_matches('x').bytes() {
+            match c {
+                b'a'..=b'h' => from_file = Some(c - b'a'),
+                b'1'..=b'8' => from_rank = Some(c - b'1'),
+                _ => return None,
+            }
         }
 
-        let bitboard = board.get_mut(self.piece.colorless());
-        if let Some(promotion_piece) = self.promotion {
-            bitboard.unset(self.from); // Remove the piece from the origin square
-            board.get_mut(promotion_piece.colorless()).set(self.to); // Place the promoted piece on the destination square
+        let mut candidates = moves.into_iter().filter(|mv| {
+            mv.piece == piece
+                && mv.to == to
+                && mv.promotion == promotion
+                && !mv.flags.contains(MoveFlags::CASTLE)
+                && from_file.is_none_or(|file| mv.from % 8 == file)
+                && from_rank.is_none_or(|rank| mv.from / 8 == rank)
+        });
+        let mv = candidates.next()?;
+        candidates.next().is_none().then_some(mv)
+    }
+
+    /// Returns true if this move brings a pawn of the side to move onto its last rank, in which case
+    /// a promotion piece is mandatory.
+    pub fn requires_promotion(&self, board: &Board) -> bool {
+        self.piece == Piece::Pawn
+            && match board.next_to_move() {
+                Color::White => self.to >= 56,
+                Color::Black => self.to <= 7,
+            }
+    }
+
+    /// Most valuable victim / least valuable attacker ordering key: captures are ranked by the value of the
+    /// captured piece first, then by the (inverse) value of the capturing piece. Quiet moves score 0, below
+    /// every capture.
+    pub fn mvv_lva_score(&self, board: &Board) -> i32 {
+        let victim = if self.flags.contains(MoveFlags::EN_PASSANT) {
+            Some(Piece::Pawn)
         } else {
-            *bitboard ^= Bitboard((1 << self.from) | (1 << self.to)); // Move the piece from the origin square to the destination square
+            board.piece_at(self.to).map(Piece::colorless)
+        };
+
+        match victim {
+            Some(victim) => (victim as i32 + 1) * 8 - self.piece.colorless() as i32,
+            None => 0,
         }
+    }
 
-        // Handle occupied
-        board.occupied.unset(self.from);
-        board.occupied.set(self.to);
+    /// Static exchange evaluation of the move (in centipawns, see [`Piece::value`]): the material balance
+    /// for the side to move once both sides have made all profitable recaptures on the destination square,
+    /// always recapturing with their least valuable attacker. Pins are ignored.
+    pub fn see(&self, board: &Board) -> i32 {
+        let mut occupied = board.occupied & !Bitboard(1 << self.from);
+        let mut gain = [0i32; 32];
+        gain[0] = if self.flags.contains(MoveFlags::EN_PASSANT) {
+            let captured_pawn_square = match board.next_to_move() {
+                Color::White => self.to - 8,
+                Color::Black => self.to + 8,
+            };
+            occupied.unset(captured_pawn_square);
+            Piece::Pawn.value()
+        } else {
+            board.piece_at(self.to).map_or(0, Piece::value)
+        };
+
+        // Value of the piece standing on the destination square, which is the next one to be captured
+        let mut target_value = match self.promotion {
+            Some(promotion_piece) => {
+                gain[0] += promotion_piece.value() - Piece::Pawn.value();
+                promotion_piece.value()
+            }
+            None => self.piece.value(),
+        };
+
+        let mut side = board.next_to_move().opposite();
+        let mut depth = 0;
+        loop {
+            let attackers = attackers_to(board, self.to, occupied) & board.color_bitboard(side);
+            let Some((piece, square)) = Piece::colorless_iter().find_map(|piece| {
+                let bitboard = attackers & *board.get(piece);
+                (!bitboard.is_empty()).then(|| (piece, bitboard.square()))
+            }) else {
+                break;
+            };
 
-        board.white.unset(self.from);
+            depth += 1;
+            gain[depth] = target_value - gain[depth - 1];
+            if gain[depth].max(-gain[depth - 1]) < 0 || depth == gain.len() - 1 {
+                break; // Neither side can improve on what it already has by going on
+            }
+
+            target_value = piece.value();
+            occupied.unset(square);
+            side = side.opposite();
+        }
 
-        if board.flags.contains(BoardFlags::WHITE_TO_MOVE) {
-            board.white.set(self.to);
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+
+    /// Apply the move to the board, the board is modified in place.
+    pub fn apply(&self, board: &mut Board) {
+        self.make(board);
+    }
+
+    /// Apply the move to the board and return the information needed to revert it with [`Move::unmake`].
+    pub fn make(&self, board: &mut Board) -> UndoInfo {
+        let color = board.next_to_move();
+        let previous_flags = board.flags;
+        let previous_en_passant_square = board.en_passant_square;
+        let castle = self.castle_side();
+        let undo = UndoInfo {
+            captured: board
+                .piece_at(self.to)
+                .map(Piece::colorless)
+                .filter(|_| castle.is_none()), // The king may land on its own rook in Chess960
+            flags: board.flags,
+            en_passant_square: board.en_passant_square,
+            halfmove_clock: board.halfmove_clock,
+            zobrist: board.zobrist,
+        };
+
+        // The halfmove clock is reset by any capture (en passant included) or pawn move
+        if self.piece == Piece::Pawn
+            || undo.captured.is_some()
+            || self.flags.contains(MoveFlags::EN_PASSANT)
+        {
+            board.halfmove_clock = 0;
         } else {
-            board.white.unset(self.to);
+            board.halfmove_clock = board.halfmove_clock.saturating_add(1);
+        }
+
+        if let Some(side) = castle {
+            // The king and the rook may land on each other's initial square in Chess960, hence both pieces are
+            // lifted before being put down
+            let (rook_from, rook_to) = (
+                board.castling_rook_square(color, side),
+                side.rook_destination(color),
+            );
+            let lifted = Bitboard((1 << self.from) | (1 << rook_from));
+            let landed = Bitboard((1 << self.to) | (1 << rook_to));
+            board.get_mut(Piece::King).unset(self.from);
+            board.get_mut(Piece::Rook).unset(rook_from);
+            board.get_mut(Piece::King).set(self.to);
+            board.get_mut(Piece::Rook).set(rook_to);
+            board.occupied = (board.occupied & !lifted) | landed;
+            if color == Color::White {
+                board.white = (board.white & !lifted) | landed;
+            }
+            board.zobrist ^= ZOBRIST.piece(Piece::King.with_color(color), self.from)
+                ^ ZOBRIST.piece(Piece::King.with_color(color), self.to)
+                ^ ZOBRIST.piece(Piece::Rook.with_color(color), rook_from)
+                ^ ZOBRIST.piece(Piece::Rook.with_color(color), rook_to);
+        } else {
+            // Remove all pieces of all bitboards on the destination square, to handle captures and promotions
+            for (piece, bitboard) in Piece::colorless_iter().zip(board.bitboards.iter_mut()) {
+                if bitboard.get(self.to) {
+                    board.zobrist ^= ZOBRIST.piece(piece.with_color(color.opposite()), self.to);
+                }
+                bitboard.unset(self.to);
+            }
+            board.zobrist ^= ZOBRIST.piece(self.piece.with_color(color), self.from)
+                ^ ZOBRIST.piece(
+                    self.promotion.unwrap_or(self.piece).with_color(color),
+                    self.to,
+                );
+
+            let bitboard = board.get_mut(self.piece.colorless());
+            if let Some(promotion_piece) = self.promotion {
+                bitboard.unset(self.from); // Remove the piece from the origin square
+                board.get_mut(promotion_piece.colorless()).set(self.to); // Place the promoted piece on the destination square
+            } else {
+                *bitboard ^= Bitboard((1 << self.from) | (1 << self.to)); // Move the piece from the origin square to the destination square
+            }
+
+            // Handle occupied
+            board.occupied.unset(self.from);
+            board.occupied.set(self.to);
+
+            board.white.unset(self.from);
+
+            if board.flags.contains(BoardFlags::WHITE_TO_MOVE) {
+                board.white.set(self.to);
+            } else {
+                board.white.unset(self.to);
+            }
         }
 
         // Check if current move generates a en passant square, if so, set the en passant square in the board flags
@@ -263,7 +588,7 @@ impl Move {
             };
             board.en_passant_square = en_passant_square;
         } else {
-            board.en_passant_square = 64; // Invalid square, to indicate that there is no en passant square
+            board.en_passant_square = Board::NO_EN_PASSANT_SQUARE;
         }
 
         // If current move is an en passant capture, we need to remove the captured pawn
@@ -275,6 +600,10 @@ impl Move {
             board.get_mut(Piece::Pawn).unset(captured_pawn_square);
             board.occupied.unset(captured_pawn_square);
             board.white.unset(captured_pawn_square);
+            board.zobrist ^= ZOBRIST.piece(
+                Piece::Pawn.with_color(color.opposite()),
+                captured_pawn_square,
+            );
         }
 
         // Handle castling rights, if the move is a king move, remove all castling right of the current side
@@ -289,76 +618,116 @@ impl Move {
             }
         }
 
-        // If the move is a rook move, we need to remove the castling right corresponding to the rook if it is on its original square
-        match self.to {
-            0 => board.flags.remove(BoardFlags::WHITE_QUEEN_SIDE_CASTLE),
-            7 => board.flags.remove(BoardFlags::WHITE_KING_SIDE_CASTLE),
-            56 => board.flags.remove(BoardFlags::BLACK_QUEEN_SIDE_CASTLE),
-            63 => board.flags.remove(BoardFlags::BLACK_KING_SIDE_CASTLE),
-            _ => { /* No castling rights to remove */ }
+        // A rook leaving its initial square (or captured on it) loses the castling right it was backing
+        for rook_color in [Color::White, Color::Black] {
+            for side in CastleSide::iter() {
+                let rook_square = board.castling_rook_square(rook_color, side);
+                if self.to == rook_square || (rook_color == color && self.from == rook_square) {
+                    board
+                        .flags
+                        .remove(BoardFlags::castling_right(rook_color, side));
+                }
+            }
         }
 
-        match board.next_to_move() {
-            Color::White => {
-                if self.from == 0 {
-                    board.flags.remove(BoardFlags::WHITE_QUEEN_SIDE_CASTLE);
-                } else if self.from == 7 {
-                    board.flags.remove(BoardFlags::WHITE_KING_SIDE_CASTLE);
-                }
+        board.flags.toggle(BoardFlags::WHITE_TO_MOVE);
+
+        // Finally hash out the previous state flags and hash in the new ones
+        board.zobrist ^= ZOBRIST.castling(previous_flags)
+            ^ ZOBRIST.castling(board.flags)
+            ^ ZOBRIST.en_passant(previous_en_passant_square)
+            ^ ZOBRIST.en_passant(board.en_passant_square)
+            ^ ZOBRIST.side_to_move(previous_flags)
+            ^ ZOBRIST.side_to_move(board.flags);
+
+        undo
+    }
+
+    /// Revert the move on the board it was just made on with [`Move::make`], restoring the exact previous state.
+    pub fn unmake(&self, board: &mut Board, undo: UndoInfo) {
+        let color = board.next_to_move().opposite(); // The side that played the move
+        let color_is_white = color == Color::White;
+
+        if let Some(side) = self.castle_side() {
+            // Both pieces are lifted before being put back, as they may have swapped squares in Chess960
+            let (rook_from, rook_to) = (
+                board.castling_rook_square(color, side),
+                side.rook_destination(color),
+            );
+            let lifted = Bitboard((1 << self.to) | (1 << rook_to));
+            let landed = Bitboard((1 << self.from) | (1 << rook_from));
+            board.get_mut(Piece::King).unset(self.to);
+            board.get_mut(Piece::Rook).unset(rook_to);
+            board.get_mut(Piece::King).set(self.from);
+            board.get_mut(Piece::Rook).set(rook_from);
+            board.occupied = (board.occupied & !lifted) | landed;
+            if color_is_white {
+                board.white = (board.white & !lifted) | landed;
             }
-            Color::Black => {
-                if self.from == 56 {
-                    board.flags.remove(BoardFlags::BLACK_QUEEN_SIDE_CASTLE);
-                } else if self.from == 63 {
-                    board.flags.remove(BoardFlags::BLACK_KING_SIDE_CASTLE);
-                }
+        } else {
+            // Move the piece back to its origin square (demoting it if needed)
+            board
+                .get_mut(self.promotion.unwrap_or(self.piece).colorless())
+                .unset(self.to);
+            board.get_mut(self.piece.colorless()).set(self.from);
+            board.occupied.set(self.from);
+            board.occupied.unset(self.to);
+            board.white.unset(self.to);
+            if color_is_white {
+                board.white.set(self.from);
             }
-        }
 
-        // If the move is a castle, we need to move the rook as well
-        if self.piece == Piece::King && self.flags.contains(MoveFlags::CASTLE) {
-            match self.to {
-                6 => {
-                    // White king side castle
-                    board.get_mut(Piece::Rook).unset(7);
-                    board.get_mut(Piece::Rook).set(5);
-                    board.occupied.unset(7);
-                    board.occupied.set(5);
-                    board.white.unset(7);
-                    board.white.set(5);
-                    board.flags.remove(BoardFlags::WHITE_CASTLE);
-                }
-                2 => {
-                    // White queen side castle
-                    board.get_mut(Piece::Rook).unset(0);
-                    board.get_mut(Piece::Rook).set(3);
-                    board.occupied.unset(0);
-                    board.occupied.set(3);
-                    board.white.unset(0);
-                    board.white.set(3);
-                    board.flags.remove(BoardFlags::WHITE_CASTLE);
+            // Put back the captured piece, if any
+            let captured_square = if self.flags.contains(MoveFlags::EN_PASSANT) {
+                match color {
+                    Color::White => Some((Piece::Pawn, self.to - 8)),
+                    Color::Black => Some((Piece::Pawn, self.to + 8)),
                 }
-                62 => {
-                    // Black king side castle
-                    board.get_mut(Piece::Rook).unset(63);
-                    board.get_mut(Piece::Rook).set(61);
-                    board.occupied.unset(63);
-                    board.occupied.set(61);
-                    board.flags.remove(BoardFlags::BLACK_CASTLE);
-                }
-                58 => {
-                    // Black queen side castle
-                    board.get_mut(Piece::Rook).unset(56);
-                    board.get_mut(Piece::Rook).set(59);
-                    board.occupied.unset(56);
-                    board.occupied.set(59);
-                    board.flags.remove(BoardFlags::BLACK_CASTLE);
+            } else {
+                undo.captured.map(|piece| (piece, self.to))
+            };
+            if let Some((piece, square)) = captured_square {
+                board.get_mut(piece).set(square);
+                board.occupied.set(square);
+                if !color_is_white {
+                    board.white.set(square);
                 }
-                _ => {}
             }
         }
 
-        board.flags.toggle(BoardFlags::WHITE_TO_MOVE);
+        board.flags = undo.flags;
+        board.en_passant_square = undo.en_passant_square;
+        board.halfmove_clock = undo.halfmove_clock;
+        board.zobrist = undo.zobrist;
+    }
+
+    /// Apply the move to both the board and its square-centric view, keeping them in sync without
+    /// a full conversion. The square-centric board must match the board before the move.
+    pub fn apply_to_both(&self, board: &mut Board, square_centric: &mut SquareCentricBoard) {
+        let color = board.next_to_move();
+        self.apply(board);
+
+        let piece = self.promotion.unwrap_or(self.piece).with_color(color);
+        square_centric.squares[self.from as usize] = None;
+        if let Some(side) = self.castle_side() {
+            // Lift the rook before the king lands, which may be on the initial square of the rook
+            square_centric.squares[board.castling_rook_square(color, side) as usize] = None;
+            square_centric.squares[side.rook_destination(color) as usize] =
+                Some(Piece::Rook.with_color(color));
+        }
+        square_centric.squares[self.to as usize] = Some(piece);
+
+        if self.flags.contains(MoveFlags::EN_PASSANT) {
+            let captured_pawn_square = match color {
+                Color::White => self.to - 8,
+                Color::Black => self.to + 8,
+            };
+            square_centric.squares[captured_pawn_square as usize] = None;
+        }
+
+        square_centric.flags = board.flags;
+        square_centric.en_passant_square = board.en_passant_square;
+        square_centric.halfmove_clock = board.halfmove_clock;
     }
 }
 
@@ -398,7 +767,8 @@ pub fn generate_queen_movement(occlusion: Bitboard, origin: Bitboard) -> Bitboar
     generate_rook_movement(occlusion, origin) | generate_bishop_movement(occlusion, origin)
 }
 
-pub fn generate_knight_movement(origin: Bitboard) -> Bitboard {
+/// Set-wise knight attacks of every knight in `origin`, see [`KNIGHT_ATTACKS`] for a single square.
+pub const fn generate_knight_movement(origin: Bitboard) -> Bitboard {
     let l1 = (origin.0 >> 1) & 0x7f7f7f7f7f7f7f7f;
     let l2 = (origin.0 >> 2) & 0x3f3f3f3f3f3f3f3f;
     let r1 = (origin.0 << 1) & 0xfefefefefefefefe;
@@ -409,10 +779,33 @@ pub fn generate_knight_movement(origin: Bitboard) -> Bitboard {
     Bitboard(attacks)
 }
 
-pub fn generate_king_movement(origin: Bitboard) -> Bitboard {
+/// Set-wise king attacks of every king in `origin`, see [`KING_ATTACKS`] for a single square.
+pub const fn generate_king_movement(origin: Bitboard) -> Bitboard {
     origin.surrounding_mask()
 }
 
+/// Knight attacks from a single square, indexed by square.
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = {
+    let mut table = [Bitboard(0); 64];
+    let mut square = 0;
+    while square < 64 {
+        table[square] = generate_knight_movement(Bitboard(1 << square));
+        square += 1;
+    }
+    table
+};
+
+/// King attacks from a single square, indexed by square.
+pub const KING_ATTACKS: [Bitboard; 64] = {
+    let mut table = [Bitboard(0); 64];
+    let mut square = 0;
+    while square < 64 {
+        table[square] = generate_king_movement(Bitboard(1 << square));
+        square += 1;
+    }
+    table
+};
+
 fn generate_pawn_attacks(origin: Bitboard, color: Color) -> Bitboard {
     match color {
         Color::White => {
@@ -428,9 +821,50 @@ fn generate_pawn_attacks(origin: Bitboard, color: Color) -> Bitboard {
     }
 }
 
-pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check: &mut bool) {
-    moves.clear();
+/// All of the pieces (of both colors) attacking the given square, considering only the pieces in `occupied`
+/// both as attackers and as blockers, which allows revealing x-ray attackers during exchanges.
+pub(crate) fn attackers_to(board: &Board, square: u8, occupied: Bitboard) -> Bitboard {
+    let origin = Bitboard(1 << square);
+    let rook_like = *board.get(Piece::Rook) | *board.get(Piece::Queen);
+    let bishop_like = *board.get(Piece::Bishop) | *board.get(Piece::Queen);
+    let pawns = *board.get(Piece::Pawn);
+
+    let attackers = (Bitboard::rook_attacks(square, occupied) & rook_like)
+        | (Bitboard::bishop_attacks(square, occupied) & bishop_like)
+        | (KNIGHT_ATTACKS[square as usize] & *board.get(Piece::Knight))
+        | (KING_ATTACKS[square as usize] & *board.get(Piece::King))
+        | (generate_pawn_attacks(origin, Color::Black) & pawns & board.white)
+        | (generate_pawn_attacks(origin, Color::White) & pawns & !board.white);
+    attackers & occupied
+}
+
+/// All of the squares attacked by the pieces of the given color, using the current occupancy of the board
+/// as occlusion (the pieces of both sides block sliding attacks).
+pub fn attacked_squares(board: &Board, by: Color) -> Bitboard {
+    let attackers = match by {
+        Color::White => board.white,
+        Color::Black => board.occupied ^ board.white,
+    };
+    let rook_like = (*board.get(Piece::Rook) | *board.get(Piece::Queen)) & attackers;
+    let bishop_like = (*board.get(Piece::Bishop) | *board.get(Piece::Queen)) & attackers;
+
+    generate_rook_movement(board.occupied, rook_like)
+        | generate_bishop_movement(board.occupied, bishop_like)
+        | generate_knight_movement(*board.get(Piece::Knight) & attackers)
+        | generate_pawn_attacks(*board.get(Piece::Pawn) & attackers, by)
+        | generate_king_movement(*board.get(Piece::King) & attackers)
+}
+
+/// Check and pin information for the side to move, shared by the move generators.
+struct CheckInfo {
+    king_square: u8,
+    destination_filter_outside_king: Bitboard, // Squares non-king moves may land on (blocking or capturing a checker)
+    pinned_bitboard: Bitboard,
+    all_enemy_attacks: Bitboard, // Computed without the ally king, so that it cannot step back along a checking ray
+    currently_in_check: bool,
+}
 
+fn check_info(board: &Board) -> CheckInfo {
     let rook_like = *board.get(Piece::Rook) | *board.get(Piece::Queen);
     let bishop_like = *board.get(Piece::Bishop) | *board.get(Piece::Queen);
     let knight_like = *board.get(Piece::Knight);
@@ -439,7 +873,7 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
     let bishop_like_enemy = bishop_like & board.enemy_bitboard();
     let knight_enemy = knight_like & board.enemy_bitboard();
     let pawn_like_enemy = pawn_like & board.enemy_bitboard();
-    *currently_in_check = false;
+    let mut currently_in_check = false;
 
     // Retrieve the ally king position and bitboard
     let ally_king_bitboard = *board.get(Piece::King) & board.friendly_bitboard();
@@ -479,29 +913,17 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
         let king_rook_checkers = king_rook_ray & rook_like_enemy;
         let king_bishop_checkers = king_bishop_ray & bishop_like_enemy;
 
-        // Bitscan other the king_rook_checkers and update the allowed_destinations
-        // as needed
-        for checker_square in king_rook_checkers.scan_bitboard() {
-            debug_assert!(
-                checker_square.count_ones() == 1,
-                "Checker square should have exactly one bit set"
-            );
-            destination_filter_outside_king &=
-                (generate_rook_movement(board.occupied, checker_square) & king_rook_ray)
-                    | checker_square;
-            *currently_in_check = true;
-        }
-
-        // Bitscan over the king_bishop_checkers and update the allowed_destinations as needed
-        for checker_square in king_bishop_checkers.scan_bitboard() {
-            debug_assert!(
-                checker_square.count_ones() == 1,
-                "Checker square should have exactly one bit set"
-            );
+        // Non-king moves must capture the checker or land between it and the king (knights and pawns can't be
+        // blocked, there is nothing between them and the king)
+        let king_knight_checkers = KNIGHT_ATTACKS[king_square as usize] & knight_enemy;
+        let king_pawn_checkers =
+            generate_pawn_attacks(ally_king_bitboard, board.next_to_move()) & pawn_like_enemy;
+        let checkers =
+            king_rook_checkers | king_bishop_checkers | king_knight_checkers | king_pawn_checkers;
+        for checker_square in checkers.scan() {
             destination_filter_outside_king &=
-                (generate_bishop_movement(board.occupied, checker_square) & king_bishop_ray)
-                    | checker_square;
-            *currently_in_check = true;
+                Bitboard::between(king_square, checker_square) | Bitboard(1 << checker_square);
+            currently_in_check = true;
         }
 
         // Compute x-ray attacks for pinned pieces, to do so, we perform a ray attack, where the occlusion as been modified
@@ -523,99 +945,28 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
         let pinner_rook_squares = king_rook_xray & rook_like_enemy & !king_rook_ray;
         let pinner_bishop_squares = king_bishop_xray & bishop_like_enemy & !king_bishop_ray;
 
-        for pinner in pinner_rook_squares.scan_bitboard() {
-            // Raycast from this piece to the king
-            let pinner_ray = generate_rook_movement(board.occupied, pinner);
-            pinned_bitboard |= pinner_ray & king_rook_ray; // & king_rook_friendly_blocker;
-        }
-
-        for pinner in pinner_bishop_squares.scan_bitboard() {
-            // Raycast from this piece to the king
-            let pinner_ray = generate_bishop_movement(board.occupied, pinner);
-            pinned_bitboard |= pinner_ray & king_bishop_ray; // & king_bishop_friendly_blocker;
-        }
-
-        // Consider knight checks as well, which are simpler since they can't be blocked
-        let king_knight_checkers = generate_knight_movement(ally_king_bitboard) & knight_enemy;
-        let king_pawn_checkers =
-            generate_pawn_attacks(ally_king_bitboard, board.next_to_move()) & pawn_like_enemy;
-        for checker_square in (king_knight_checkers | king_pawn_checkers).scan_bitboard() {
-            debug_assert!(
-                checker_square.count_ones() == 1,
-                "Checker square should have exactly one bit set"
-            );
-            destination_filter_outside_king &= checker_square; // Only the square the knight is on can be moved to, to capture the knight
-            *currently_in_check = true;
+        for pinner in (pinner_rook_squares | pinner_bishop_squares).scan() {
+            pinned_bitboard |= Bitboard::between(king_square, pinner) & board.friendly_bitboard();
         }
     }
 
-    // Generate pawn moves (non-captures first, since they are simpler and also can be double moves)
-    let friendly_pawns = *board.get(Piece::Pawn) & board.friendly_bitboard();
-    let pawn_non_capture_single_moves = match board.next_to_move() {
-        Color::White => (friendly_pawns.0 << 8) & !board.occupied.0,
-        Color::Black => (friendly_pawns.0 >> 8) & !board.occupied.0,
-    };
-    let pawn_non_capture_double_moves = match board.next_to_move() {
-        Color::White => {
-            ((pawn_non_capture_single_moves & Bitboard::RANK_3) << 8) & !board.occupied.0
-        }
-        Color::Black => {
-            ((pawn_non_capture_single_moves & Bitboard::RANK_6) >> 8) & !board.occupied.0
-        }
-    };
-
-    for pawn_single_move in
-        Bitboard(pawn_non_capture_single_moves & destination_filter_outside_king.0).scan()
-    {
-        let can_promote = match board.next_to_move() {
-            Color::White => pawn_single_move >= 56,
-            Color::Black => pawn_single_move <= 7,
-        };
-
-        let from_square = match board.next_to_move() {
-            Color::White => pawn_single_move - 8,
-            Color::Black => pawn_single_move + 8,
-        };
-
-        if can_promote {
-            for promotion_piece in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
-                moves.push(Move {
-                    from: from_square as u8,
-                    to: pawn_single_move as u8,
-                    piece: Piece::Pawn,
-                    promotion: Some(promotion_piece),
-                    flags: MoveFlags::empty(),
-                });
-            }
-        } else {
-            moves.push(Move {
-                from: from_square as u8,
-                to: pawn_single_move as u8,
-                piece: Piece::Pawn,
-                promotion: None,
-                flags: MoveFlags::empty(),
-            });
-        }
-    }
-
-    for pawn_double_move in
-        Bitboard(pawn_non_capture_double_moves & destination_filter_outside_king.0).scan()
-    {
-        let from_square = match board.next_to_move() {
-            Color::White => pawn_double_move - 16,
-            Color::Black => pawn_double_move + 16,
-        };
-
-        moves.push(Move {
-            from: from_square as u8,
-            to: pawn_double_move as u8,
-            piece: Piece::Pawn,
-            promotion: None,
-            flags: MoveFlags::empty(),
-        });
+    CheckInfo {
+        king_square,
+        destination_filter_outside_king,
+        pinned_bitboard,
+        all_enemy_attacks,
+        currently_in_check,
     }
+}
 
-    // Generate pawn captures
+/// Push the pawn captures (with promotions) and the en passant captures of the side to move whose destination
+/// is allowed by the filter. Pins are not taken into account.
+fn push_pawn_captures(
+    board: &Board,
+    moves: &mut Vec<Move>,
+    friendly_pawns: Bitboard,
+    destination_filter_outside_king: Bitboard,
+) {
     let pawn_attacks = generate_pawn_attacks(friendly_pawns, board.next_to_move());
     let pawn_capture_moves =
         pawn_attacks & board.enemy_bitboard() & destination_filter_outside_king;
@@ -664,18 +1015,26 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
     }
 
     // Generate en passant moves
-    if (pawn_attacks & destination_filter_outside_king)
-        & Bitboard(u64::checked_shl(1, board.en_passant_square as u32).unwrap_or(0))
+    if (pawn_attacks & destination_filter_outside_king) & board.en_passant_bitboard()
         != Bitboard::empty()
     {
+        // The masks keep a pawn on the other edge of the board from capturing across it
         let dir = match board.next_to_move() {
-            Color::White => [Direction::SouthEast, Direction::SouthWest],
-            Color::Black => [Direction::NorthEast, Direction::NorthWest],
+            Color::White => [
+                (Direction::SouthEast, !Bitboard::FILE_A),
+                (Direction::SouthWest, !Bitboard::FILE_H),
+            ],
+            Color::Black => [
+                (Direction::NorthEast, !Bitboard::FILE_A),
+                (Direction::NorthWest, !Bitboard::FILE_H),
+            ],
         };
 
-        for direction in dir.iter() {
+        for (direction, mask) in dir.iter() {
             let from_square = direction.shift(board.en_passant_square).unwrap();
-            if Bitboard(1 << from_square) & friendly_pawns != Bitboard::empty() {
+            if (Bitboard(1 << from_square) & Bitboard(*mask)) & friendly_pawns != Bitboard::empty()
+                && !en_passant_exposes_king(board, from_square)
+            {
                 moves.push(Move {
                     from: from_square as u8,
                     to: board.en_passant_square as u8,
@@ -686,12 +1045,131 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
             }
         }
     }
+}
+
+/// Whether the en passant capture made by the pawn on `from_square` leaves the king of the side to move in
+/// check. Both pawns leave the same rank at once, which the pins don't account for: with the king and an enemy
+/// rook on that rank (e.g. "8/8/8/K1Pp3r/8/8/8/k7 w - d6 0 1"), the capture is illegal even though none of the
+/// pawns is pinned on its own. The captured pawn may also have been the only blocker on a diagonal.
+fn en_passant_exposes_king(board: &Board, from_square: u8) -> bool {
+    let king = *board.get(Piece::King) & board.friendly_bitboard();
+    let Some(king_square) = king.scan().next() else {
+        return false;
+    };
+    let captured_square = match board.next_to_move() {
+        Color::White => board.en_passant_square - 8,
+        Color::Black => board.en_passant_square + 8,
+    };
+
+    // Occupancy once both pawns left their squares and the capturing pawn landed on the en passant square
+    let occupied = (board.occupied & !Bitboard(1 << from_square) & !Bitboard(1 << captured_square))
+        | board.en_passant_bitboard();
+    let enemy_queens = *board.get(Piece::Queen) & board.enemy_bitboard();
+    let rook_like_enemy = (*board.get(Piece::Rook) & board.enemy_bitboard()) | enemy_queens;
+    let bishop_like_enemy = (*board.get(Piece::Bishop) & board.enemy_bitboard()) | enemy_queens;
+
+    Bitboard::rook_attacks(king_square, occupied) & rook_like_enemy != Bitboard::empty()
+        || Bitboard::bishop_attacks(king_square, occupied) & bishop_like_enemy != Bitboard::empty()
+}
+
+/// Remove the moves made by pinned pieces that don't stay on the ray between the king and the pinner.
+fn retain_pin_legal(moves: &mut Vec<Move>, king_square: u8, pinned_bitboard: Bitboard) {
+    // A pinned piece stays on the line going through the king and itself, which is the line of the pin
+    moves.retain(|m| !pinned_bitboard.get(m.from) || Bitboard::line(king_square, m.from).get(m.to));
+}
+
+pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check: &mut bool) {
+    moves.clear();
+
+    let CheckInfo {
+        king_square,
+        destination_filter_outside_king,
+        pinned_bitboard,
+        all_enemy_attacks,
+        currently_in_check: in_check,
+    } = check_info(board);
+    *currently_in_check = in_check;
+
+    // Generate pawn moves (non-captures first, since they are simpler and also can be double moves)
+    let friendly_pawns = *board.get(Piece::Pawn) & board.friendly_bitboard();
+    let pawn_non_capture_single_moves = match board.next_to_move() {
+        Color::White => (friendly_pawns.0 << 8) & !board.occupied.0,
+        Color::Black => (friendly_pawns.0 >> 8) & !board.occupied.0,
+    };
+    let double_push_rank = Bitboard::double_push_rank(board.next_to_move()).0;
+    let pawn_non_capture_double_moves = match board.next_to_move() {
+        Color::White => {
+            ((pawn_non_capture_single_moves & double_push_rank) << 8) & !board.occupied.0
+        }
+        Color::Black => {
+            ((pawn_non_capture_single_moves & double_push_rank) >> 8) & !board.occupied.0
+        }
+    };
+
+    for pawn_single_move in
+        Bitboard(pawn_non_capture_single_moves & destination_filter_outside_king.0).scan()
+    {
+        let can_promote = match board.next_to_move() {
+            Color::White => pawn_single_move >= 56,
+            Color::Black => pawn_single_move <= 7,
+        };
+
+        let from_square = match board.next_to_move() {
+            Color::White => pawn_single_move - 8,
+            Color::Black => pawn_single_move + 8,
+        };
+
+        if can_promote {
+            for promotion_piece in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+                moves.push(Move {
+                    from: from_square,
+                    to: pawn_single_move,
+                    piece: Piece::Pawn,
+                    promotion: Some(promotion_piece),
+                    flags: MoveFlags::empty(),
+                });
+            }
+        } else {
+            moves.push(Move {
+                from: from_square,
+                to: pawn_single_move,
+                piece: Piece::Pawn,
+                promotion: None,
+                flags: MoveFlags::empty(),
+            });
+        }
+    }
+
+    for pawn_double_move in
+        Bitboard(pawn_non_capture_double_moves & destination_filter_outside_king.0).scan()
+    {
+        let from_square = match board.next_to_move() {
+            Color::White => pawn_double_move - 16,
+            Color::Black => pawn_double_move + 16,
+        };
+
+        moves.push(Move {
+            from: from_square,
+            to: pawn_double_move,
+            piece: Piece::Pawn,
+            promotion: None,
+            flags: MoveFlags::empty(),
+        });
+    }
+
+    // Generate pawn captures, including en passant
+    push_pawn_captures(
+        board,
+        moves,
+        friendly_pawns,
+        destination_filter_outside_king,
+    );
 
     // Generate rook moves
     let rook_friendly = *board.get(Piece::Rook) & board.friendly_bitboard();
     for rook_square in rook_friendly.scan() {
-        let rook_moves = generate_rook_movement(board.occupied, Bitboard(1 << rook_square))
-            & destination_filter_outside_king;
+        let rook_moves =
+            Bitboard::rook_attacks(rook_square, board.occupied) & destination_filter_outside_king;
         for rook_move in rook_moves.scan() {
             moves.push(Move {
                 from: rook_square as u8,
@@ -705,7 +1183,7 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
 
     let bishop_friendly = *board.get(Piece::Bishop) & board.friendly_bitboard();
     for bishop_square in bishop_friendly.scan() {
-        let bishop_moves = generate_bishop_movement(board.occupied, Bitboard(1 << bishop_square))
+        let bishop_moves = Bitboard::bishop_attacks(bishop_square, board.occupied)
             & destination_filter_outside_king;
         for bishop_move in bishop_moves.scan() {
             moves.push(Move {
@@ -721,7 +1199,8 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
     // Generate queen moves
     let queen_friendly = *board.get(Piece::Queen) & board.friendly_bitboard();
     for queen_square in queen_friendly.scan() {
-        let queen_moves = generate_queen_movement(board.occupied, Bitboard(1 << queen_square))
+        let queen_moves = (Bitboard::rook_attacks(queen_square, board.occupied)
+            | Bitboard::bishop_attacks(queen_square, board.occupied))
             & destination_filter_outside_king;
         for queen_move in queen_moves.scan() {
             moves.push(Move {
@@ -738,7 +1217,7 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
     let knight_friendly = *board.get(Piece::Knight) & board.friendly_bitboard();
     let knight_moves = generate_knight_movement(knight_friendly) & destination_filter_outside_king;
     for knight_move in knight_moves.scan() {
-        let from_squares = generate_knight_movement(Bitboard(1 << knight_move)) & knight_friendly;
+        let from_squares = KNIGHT_ATTACKS[knight_move as usize] & knight_friendly;
         for from_square in from_squares.scan() {
             moves.push(Move {
                 from: from_square as u8,
@@ -751,9 +1230,8 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
     }
 
     // Generate king moves
-    let king_moves = generate_king_movement(ally_king_bitboard)
-        & !board.friendly_bitboard()
-        & !all_enemy_attacks;
+    let king_moves =
+        KING_ATTACKS[king_square as usize] & !board.friendly_bitboard() & !all_enemy_attacks;
     for king_move in king_moves.scan() {
         moves.push(Move {
             from: king_square as u8,
@@ -764,97 +1242,761 @@ pub fn generate_moves(board: &Board, moves: &mut Vec<Move>, currently_in_check:
         });
     }
 
-    // Generate castling moves, we need to check that the squares between the king and the rook are empty, and
+    // Generate castling moves, we need to check that the squares the king and the rook go through are empty, and
     // that the king is not in threat during transit
-    let threat_or_non_empty = board.occupied | all_enemy_attacks;
     if !*currently_in_check {
-        match board.next_to_move() {
-            Color::White => {
-                if board.flags.contains(BoardFlags::WHITE_KING_SIDE_CASTLE)
-                    && (threat_or_non_empty.0 & 0x60) == 0
-                {
-                    moves.push(Move {
-                        from: king_square as u8,
-                        to: 6,
-                        piece: Piece::King,
-                        promotion: None,
-                        flags: MoveFlags::CASTLE,
-                    });
-                }
-                if board.flags.contains(BoardFlags::WHITE_QUEEN_SIDE_CASTLE)
-                    && (threat_or_non_empty.0 & 0x0c) == 0
-                    && (board.occupied.0 & 0x0e) == 0
-                {
-                    moves.push(Move {
-                        from: king_square as u8,
-                        to: 2,
-                        piece: Piece::King,
-                        promotion: None,
-                        flags: MoveFlags::CASTLE,
-                    });
+        let color = board.next_to_move();
+        for side in CastleSide::iter() {
+            if board.has_castling_right(color, side)
+                && (all_enemy_attacks & board.castling_transit_mask(color, side)).is_empty()
+                && (board.occupied & board.castling_between_mask(color, side)).is_empty()
+                && !board.castling_uncovers_king(color, side)
+            {
+                moves.push(Move {
+                    from: king_square,
+                    to: side.king_destination(color),
+                    piece: Piece::King,
+                    promotion: None,
+                    flags: MoveFlags::CASTLE,
+                });
+            }
+        }
+    }
+
+    // Remove all moves that are made by pinned pieces and that don't move along the ray of the pin
+    retain_pin_legal(moves, king_square, pinned_bitboard);
+}
+
+/// Same as [`generate_moves`], but only the legal capturing moves are generated (captures with or without
+/// promotion and en passant), for quiescence search and move ordering.
+pub fn generate_captures(board: &Board, moves: &mut Vec<Move>, currently_in_check: &mut bool) {
+    moves.clear();
+
+    let info = check_info(board);
+    *currently_in_check = info.currently_in_check;
+
+    let friendly_pawns = *board.get(Piece::Pawn) & board.friendly_bitboard();
+    push_pawn_captures(
+        board,
+        moves,
+        friendly_pawns,
+        info.destination_filter_outside_king,
+    );
+
+    // Rooks, bishops, queens and knights may capture any enemy piece allowed by the check filter
+    let targets = info.destination_filter_outside_king & board.enemy_bitboard();
+    for piece in [Piece::Rook, Piece::Bishop, Piece::Queen, Piece::Knight] {
+        let friendly = *board.get(piece) & board.friendly_bitboard();
+        for from_square in friendly.scan() {
+            let attacks = match piece {
+                Piece::Rook => Bitboard::rook_attacks(from_square, board.occupied),
+                Piece::Bishop => Bitboard::bishop_attacks(from_square, board.occupied),
+                Piece::Queen => {
+                    Bitboard::rook_attacks(from_square, board.occupied)
+                        | Bitboard::bishop_attacks(from_square, board.occupied)
                 }
+                _ => KNIGHT_ATTACKS[from_square as usize],
+            };
+            for to_square in (attacks & targets).scan() {
+                moves.push(Move {
+                    from: from_square,
+                    to: to_square,
+                    piece,
+                    promotion: None,
+                    flags: MoveFlags::empty(),
+                });
             }
-            Color::Black => {
-                if board.flags.contains(BoardFlags::BLACK_KING_SIDE_CASTLE)
-                    && (threat_or_non_empty.0 & 0x6000000000000000) == 0
-                {
-                    moves.push(Move {
-                        from: king_square as u8,
-                        to: 62,
-                        piece: Piece::King,
-                        promotion: None,
-                        flags: MoveFlags::CASTLE,
-                    });
+        }
+    }
+
+    let king_captures =
+        KING_ATTACKS[info.king_square as usize] & board.enemy_bitboard() & !info.all_enemy_attacks;
+    for to_square in king_captures.scan() {
+        moves.push(Move {
+            from: info.king_square,
+            to: to_square,
+            piece: Piece::King,
+            promotion: None,
+            flags: MoveFlags::empty(),
+        });
+    }
+
+    retain_pin_legal(moves, info.king_square, info.pinned_bitboard);
+}
+
+/// Count the leaf nodes of the legal move tree of the given depth, used to validate move generation
+/// against known node counts.
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    let mut board = *board;
+    let mut buffers = vec![Vec::new(); depth as usize];
+    perft_recursive(&mut board, &mut buffers)
+}
+
+/// Same as [`perft`], but returns the node count below each legal move of the root position.
+pub fn perft_divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    let mut board = *board;
+    let mut moves = Vec::new();
+    let mut currently_in_check = false;
+    generate_moves(&board, &mut moves, &mut currently_in_check);
+
+    let mut buffers = vec![Vec::new(); depth.saturating_sub(1) as usize];
+    moves
+        .into_iter()
+        .map(|mv| {
+            let undo = mv.make(&mut board);
+            let count = perft_recursive(&mut board, &mut buffers);
+            mv.unmake(&mut board, undo);
+            (mv, count)
+        })
+        .collect()
+}
+
+/// One move buffer per remaining depth, so that no allocation happens once the buffers have grown.
+fn perft_recursive(board: &mut Board, buffers: &mut [Vec<Move>]) -> u64 {
+    let Some((moves, rest)) = buffers.split_first_mut() else {
+        return 1;
+    };
+
+    moves.clear();
+    let mut currently_in_check = false;
+    generate_moves(board, moves, &mut currently_in_check);
+    if rest.is_empty() {
+        return moves.len() as u64;
+    }
+
+    let mut count = 0;
+    for mv in moves.iter() {
+        let undo = mv.make(board);
+        count += perft_recursive(board, rest);
+        mv.unmake(board, undo);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_to_both_keeps_square_centric_in_sync() {
+        // Covers castling on both sides, an en passant capture and a promotion with capture
+        let mut board = Board::from_fen("r3k2r/1P1p4/8/4P3/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        let mut square_centric = board.square_centric();
+
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        for uci in ["d7d5", "e5d6", "e8g8", "b7a8q", "g8g7", "e1c1"] {
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            let mv = Move::from_uci(uci, &board).unwrap();
+            assert!(moves.contains(&mv), "{} should be legal", uci);
+
+            mv.apply_to_both(&mut board, &mut square_centric);
+            assert_eq!(
+                square_centric,
+                board.square_centric(),
+                "Square-centric board out of sync after {}",
+                uci
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_uci_requires_promotion_piece() {
+        let board = Board::from_fen("8/4P3/8/8/8/2k5/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(Move::from_uci("e7e8", &board), None);
+
+        let mv = Move::from_uci("e7e8q", &board).unwrap();
+        assert!(mv.requires_promotion(&board));
+        assert_eq!(mv.promotion, Some(Piece::Queen));
+
+        // A promotion suffix on a move that doesn't reach the last rank is rejected as well
+        assert!(
+            !Move::from_uci("e1e2", &board)
+                .unwrap()
+                .requires_promotion(&board)
+        );
+        assert_eq!(Move::from_uci("e1e2q", &board), None);
+    }
+
+    #[test]
+    fn test_from_uci_king_takes_rook_castling() {
+        // Both encodings of a castle, the king's destination and the king "capturing" its own rook, give the same move
+        for (fen, standard, king_takes_rook) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "e1h1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1", "e1a1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8g8", "e8h8"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "e8a8"),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let expected = Move::from_uci(standard, &board).unwrap();
+            let mv = Move::from_uci(king_takes_rook, &board).unwrap();
+            assert_eq!(mv, expected, "{} in {}", king_takes_rook, fen);
+            assert!(mv.flags.contains(MoveFlags::CASTLE));
+            let mut moves = Vec::new();
+            generate_moves(&board, &mut moves, &mut false);
+            assert!(moves.contains(&mv), "{} in {}", king_takes_rook, fen);
+        }
+
+        // Moving the king next to an enemy rook is a capture, not a castle
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4Kr2 w - - 0 1").unwrap();
+        let mv = Move::from_uci("e1f1", &board).unwrap();
+        assert!(!mv.flags.contains(MoveFlags::CASTLE));
+    }
+
+    #[test]
+    fn test_uci_annotated_suffix() {
+        // Fool's mate: Qh4 is checkmate, while any other move is plain
+        let board =
+            Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2")
+                .unwrap();
+        let mate = Move::from_uci("d8h4", &board).unwrap();
+        assert!(mate.gives_check(&board));
+        assert!(mate.gives_checkmate(&board));
+        assert_eq!(mate.uci_annotated(&board), "d8h4#");
+
+        let quiet = Move::from_uci("b8c6", &board).unwrap();
+        assert!(!quiet.gives_check(&board));
+        assert_eq!(quiet.uci_annotated(&board), "b8c6");
+
+        // Ra8+ is a check the king can escape
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let check = Move::from_uci("a1a8", &board).unwrap();
+        assert!(check.gives_check(&board));
+        assert!(!check.gives_checkmate(&board));
+        assert_eq!(check.uci_annotated(&board), "a1a8+");
+    }
+
+    #[test]
+    fn test_halfmove_clock() {
+        let mut board = Board::from_fen("4k3/3p4/8/4P3/8/8/8/R3K3 b - - 12 40").unwrap();
+        assert_eq!(board.halfmove_clock, 12);
+
+        // Quiet piece moves increment, the double pawn push and the en passant capture reset it
+        for (uci, clock) in [
+            ("e8f7", 13),
+            ("a1a2", 14),
+            ("d7d5", 0),
+            ("e5d6", 0),
+            ("f7e6", 1),
+            ("a2a3", 2),
+        ] {
+            Move::from_uci(uci, &board).unwrap().apply(&mut board);
+            assert_eq!(board.halfmove_clock, clock, "after {}", uci);
+        }
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert!(!board.is_fifty_move_draw());
+        let mut after = board;
+        Move::from_uci("a1a2", &board).unwrap().apply(&mut after);
+        assert!(after.is_fifty_move_draw());
+    }
+
+    #[test]
+    fn test_make_unmake_restores_board() {
+        use rand::{RngExt, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x42);
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+
+        // Play random games, making and unmaking every legal move before picking one at random
+        for fen in [
+            Board::DEFAULT_POSITION_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/1P1p4/8/4P3/8/8/8/R3K2R b KQkq - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", // Chess960
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            for _ in 0..60 {
+                generate_moves(&board, &mut moves, &mut currently_in_check);
+                if moves.is_empty() {
+                    break;
                 }
-                if board.flags.contains(BoardFlags::BLACK_QUEEN_SIDE_CASTLE)
-                    && (threat_or_non_empty.0 & 0x0c00000000000000) == 0
-                    && (board.occupied.0 & 0x0e00000000000000) == 0
-                {
-                    moves.push(Move {
-                        from: king_square as u8,
-                        to: 58,
-                        piece: Piece::King,
-                        promotion: None,
-                        flags: MoveFlags::CASTLE,
-                    });
+
+                for mv in &moves {
+                    let before = board;
+                    let undo = mv.make(&mut board);
+                    mv.unmake(&mut board, undo);
+                    assert_eq!(
+                        board,
+                        before,
+                        "make/unmake of {} from \"{}\"",
+                        mv,
+                        before.fen()
+                    );
                 }
+
+                let mv = moves[rng.random_range(0..moves.len())];
+                mv.apply(&mut board);
             }
         }
     }
 
-    // Convert king position to 8x8
-    let king_position_8x8 = (king_square % 8, king_square / 8);
+    #[cfg(feature = "study")]
+    #[test]
+    fn test_magic_attacks_match_raycasts_on_standard_study() {
+        // The move generator relies on the magic lookups, which must agree with the iterative raycasts
+        // it used before for every occupancy met in practice (run with --release to exercise the tables)
+        for study in crate::study::get_standard_study() {
+            let board = Board::from_fen(&study.start.fen).unwrap();
+            for square in 0..64 {
+                let origin = Bitboard(1 << square);
+                assert_eq!(
+                    Bitboard::rook_attacks(square, board.occupied),
+                    generate_rook_movement(board.occupied, origin),
+                    "Rook attacks from {} in \"{}\"",
+                    square,
+                    study.start.fen
+                );
+                assert_eq!(
+                    Bitboard::bishop_attacks(square, board.occupied),
+                    generate_bishop_movement(board.occupied, origin),
+                    "Bishop attacks from {} in \"{}\"",
+                    square,
+                    study.start.fen
+                );
+            }
+        }
+    }
 
-    // Remove all moves that are made by pinned pieces and that don't move along the ray of the pin
-    moves.retain(|m| {
-        if pinned_bitboard.get(m.from) {
-            // Pinned can only move along the ray direction between the king and the pinner, to compute this direction
-            let from_square_8x8 = (m.from % 8, m.from / 8);
-            let to_square_8x8 = (m.to % 8, m.to / 8);
-
-            // Only valid if the king_position_8x8, from_square_8x8 and to_square_8x8 are all aligned
-            let from_dir_unit = (
-                (from_square_8x8.0 as i8 - king_position_8x8.0 as i8).signum(),
-                (from_square_8x8.1 as i8 - king_position_8x8.1 as i8).signum(),
-            );
+    #[test]
+    fn test_attack_tables_match_shifts() {
+        for square in 0..64 {
+            let origin = Bitboard(1 << square);
+            assert_eq!(KNIGHT_ATTACKS[square], generate_knight_movement(origin));
+            assert_eq!(KING_ATTACKS[square], generate_king_movement(origin));
+        }
+        assert_eq!(KNIGHT_ATTACKS[0].count_ones(), 2); // a1: b3, c2
+        assert_eq!(KING_ATTACKS[63].count_ones(), 3); // h8: g8, g7, h7
+    }
 
-            let to_dir = (
-                (to_square_8x8.0 as i8 - king_position_8x8.0 as i8),
-                (to_square_8x8.1 as i8 - king_position_8x8.1 as i8),
+    #[test]
+    fn test_perft_start_position() {
+        let board = Board::default_position();
+        for (depth, expected) in [(0, 1), (1, 20), (2, 400), (3, 8902), (4, 197281)] {
+            assert_eq!(perft(&board, depth), expected, "perft({})", depth);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        ignore = "magic bitboards are only generated in release builds"
+    )]
+    fn test_perft_magic_matches_legacy_sliders() {
+        use crate::bitboard::tests::LEGACY_SLIDERS;
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let magic = perft(&board, 4);
+            LEGACY_SLIDERS.set(true);
+            let legacy = perft(&board, 4);
+            LEGACY_SLIDERS.set(false);
+            assert_eq!(magic, legacy, "perft(4) of \"{}\"", fen);
+        }
+    }
+
+    #[test]
+    fn test_pawn_double_pushes() {
+        // Only pawns on their starting rank double push, and never through or onto a piece
+        for (fen, expected) in [
+            ("4k3/8/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 8),
+            ("4k3/pppppppp/8/8/8/8/8/4K3 b - - 0 1", 8),
+            ("4k3/8/8/8/8/P7/1P6/4K3 w - - 0 1", 1), // a3 pawn already moved
+            ("4k3/8/8/8/1n6/n7/PP6/4K3 w - - 0 1", 0), // Blocked on a3 and b4
+            ("4k3/p1p5/1N6/2N5/8/8/8/4K3 b - - 0 1", 1), // c-pawn blocked on c5 only
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            let double_pushes = moves
+                .iter()
+                .filter(|mv| mv.piece == Piece::Pawn && mv.from.abs_diff(mv.to) == 16)
+                .count();
+            assert_eq!(double_pushes, expected, "\"{}\"", fen);
+        }
+    }
+
+    #[test]
+    fn test_en_passant_does_not_wrap_around() {
+        // A pawn on the other edge of the board is one shift away from the en passant square once wrapped around
+        // (h3 for a3, h4 for a6, a5 for h3 and a6 for h6)
+        for (fen, expected) in [
+            ("4k3/8/8/8/Pp6/7p/8/4K3 b - a3 0 1", vec!["b4a3"]),
+            ("4k3/8/8/pP6/7P/8/8/4K3 w - a6 0 1", vec!["b5a6"]),
+            ("4k3/8/8/p7/6pP/8/8/4K3 b - h3 0 1", vec!["g4h3"]),
+            ("4k3/8/P7/6Pp/8/8/8/4K3 w - h6 0 1", vec!["g5h6"]),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mut moves = Vec::new();
+            generate_moves(&board, &mut moves, &mut false);
+            let en_passant: Vec<String> = moves
+                .iter()
+                .filter(|mv| mv.flags.contains(MoveFlags::EN_PASSANT))
+                .map(|mv| mv.uci().to_string())
+                .collect();
+            assert_eq!(en_passant, expected, "\"{}\"", fen);
+        }
+    }
+
+    #[test]
+    fn test_en_passant_discovered_check() {
+        for (fen, legal) in [
+            ("8/8/8/K1Pp3r/8/8/8/k7 w - d6 0 1", false), // Both pawns leave the rank of the king
+            ("8/8/8/K1Pp4/8/8/8/k6r w - d6 0 1", true),
+            ("k7/8/8/8/R2pP2K/8/8/8 b - e3 0 1", false),
+            ("8/8/8/K1Pp1n1r/8/8/8/k7 w - d6 0 1", true), // The knight still shields the king
+            ("6b1/8/8/2Pp4/8/8/K7/7k w - d6 0 1", false), // The captured pawn was blocking the bishop
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            let en_passant = moves
+                .iter()
+                .any(|mv| mv.flags.contains(MoveFlags::EN_PASSANT));
+            assert_eq!(en_passant, legal, "\"{}\"", fen);
+        }
+
+        // Position 3 of the usual perft suite, where this capture shows up from depth 2 on
+        let board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        for (depth, expected) in [(1, 14), (2, 191), (3, 2812), (4, 43238)] {
+            assert_eq!(perft(&board, depth), expected, "perft({})", depth);
+        }
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // (position, castling in UCI, position after castling)
+        for (fen, uci, expected) in [
+            // Queen side with the king on g1, jumping over the other rook's initial square
+            (
+                "4k3/8/8/8/8/8/8/5RKR w HF - 0 1",
+                "g1f1",
+                "4k3/8/8/8/8/8/8/2KR3R b - - 1 1",
+            ),
+            // The king already stands on its destination, only the rook moves
+            (
+                "4k3/8/8/8/8/8/8/6KR w H - 0 1",
+                "g1h1",
+                "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            ),
+            // The king and the rook swap their squares
+            (
+                "4k3/8/8/8/8/8/8/5KR1 w G - 0 1",
+                "f1g1",
+                "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            ),
+            (
+                "1r4k1/8/8/8/8/8/8/4K3 b b - 0 1",
+                "g8b8",
+                "2kr4/8/8/8/8/8/8/4K3 w - - 1 2",
+            ),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.fen().to_string(), fen[..fen.len() - 4]);
+
+            let mv = Move::from_uci(uci, &board).unwrap();
+            assert!(
+                mv.flags.contains(MoveFlags::CASTLE),
+                "{} in \"{}\"",
+                uci,
+                fen
             );
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            assert!(moves.contains(&mv), "{} in \"{}\"", uci, fen);
+
+            let mut after = board;
+            let undo = mv.make(&mut after);
+            assert_eq!(after.fen().to_string(), expected[..expected.len() - 4]);
+            assert_eq!(after.zobrist, after.compute_zobrist());
+
+            let mut square_centric = board.square_centric();
+            let mut both = board;
+            mv.apply_to_both(&mut both, &mut square_centric);
+            assert_eq!(square_centric, after.square_centric());
+
+            mv.unmake(&mut after, undo);
+            assert_eq!(after, board);
+        }
+
+        // Castling through a piece, into check, or with the rook shielding the destination of the king
+        for (fen, expected) in [
+            ("4k3/8/8/8/8/8/8/1R1NK2R w HB - 0 1", vec!["e1g1"]),
+            ("4k3/8/8/8/8/8/6r1/1R2K2R w HB - 0 1", vec!["e1c1"]),
+            ("4k3/8/8/8/8/8/8/1RK5 w B - 0 1", vec!["c1b1"]),
+            ("4k3/8/8/8/8/8/8/rRK5 w B - 0 1", vec![]),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(&board, &mut moves, &mut currently_in_check);
+            let castles: Vec<String> = moves
+                .iter()
+                .filter(|mv| mv.flags.contains(MoveFlags::CASTLE))
+                .map(|mv| mv.to_uci(&board))
+                .collect();
+            assert_eq!(castles, expected, "\"{}\"", fen);
+        }
+
+        // Positions of the usual Chess960 perft suite
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471],
+            ),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            for (depth, expected) in (1..).zip(expected) {
+                assert_eq!(
+                    perft(&board, depth),
+                    expected,
+                    "perft({}) of \"{}\"",
+                    depth,
+                    fen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_uci_round_trip() {
+        fn check(board: &Board, depth: usize) {
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(board, &mut moves, &mut currently_in_check);
+            for mv in moves {
+                let uci = mv.to_uci(board);
+                assert_eq!(
+                    Move::from_uci(&uci, board),
+                    Some(mv),
+                    "{} in \"{}\"",
+                    uci,
+                    board.fen()
+                );
+
+                if depth > 1 {
+                    let mut new_board = *board;
+                    mv.apply(&mut new_board);
+                    check(&new_board, depth - 1);
+                }
+            }
+        }
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/1RK5 w B - 0 1",
+            "4k3/8/8/8/8/8/8/5RKR w HF - 0 1",
+            "4k3/8/8/8/8/8/8/5KR1 w G - 0 1",
+            "1r4k1/8/8/8/8/8/8/4K3 b b - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        ] {
+            check(&Board::from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn test_perft_divide_sums_to_perft() {
+        let board = Board::default_position();
+        let divide = perft_divide(&board, 3);
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), 8902);
+
+        let e2e4 = divide
+            .iter()
+            .find(|(mv, _)| mv.uci().to_string() == "e2e4")
+            .unwrap();
+        assert_eq!(e2e4.1, 600);
+    }
+
+    #[test]
+    fn test_generate_captures_matches_generate_moves() {
+        fn check(board: &Board, depth: usize) {
+            let mut moves = Vec::new();
+            let mut captures = Vec::new();
+            let mut in_check = false;
+            let mut in_check_captures = false;
+            generate_moves(board, &mut moves, &mut in_check);
+            generate_captures(board, &mut captures, &mut in_check_captures);
+            assert_eq!(in_check, in_check_captures);
+
+            let mut expected: Vec<String> = moves
+                .iter()
+                .filter(|mv| {
+                    board.enemy_bitboard().get(mv.to) || mv.flags.contains(MoveFlags::EN_PASSANT)
+                })
+                .map(|mv| mv.uci().to_string())
+                .collect();
+            let mut actual: Vec<String> = captures.iter().map(|mv| mv.uci().to_string()).collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "Captures mismatch in \"{}\"", board.fen());
+
+            if depth > 1 {
+                for mv in moves {
+                    let mut new_board = *board;
+                    mv.apply(&mut new_board);
+                    check(&new_board, depth - 1);
+                }
+            }
+        }
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            check(&Board::from_fen(fen).unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn test_static_exchange_evaluation() {
+        let see = |fen: &str, uci: &str| {
+            let board = Board::from_fen(fen).unwrap();
+            Move::from_uci(uci, &board).unwrap().see(&board)
+        };
+
+        // Undefended queen
+        assert_eq!(see("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", "d1d5"), 900);
+        // Queen takes a pawn defended by a pawn
+        assert_eq!(see("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"), -800);
+        // Rook takes a pawn defended by a rook, but backed by a second rook (x-ray)
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+        // Without the second rook the exchange loses the rook for a pawn
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+        // Capture with promotion, the new queen is then taken back by the king
+        assert_eq!(
+            see("4k3/8/8/8/8/8/3p4/3RK3 b - - 0 1", "d2d1q"),
+            800 - 900 + 500
+        );
+        // En passant
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+    }
+
+    #[test]
+    fn test_mvv_lva_score() {
+        let board = Board::from_fen("4k3/8/2q5/1P1r4/4N3/2n5/1P6/3QK3 w - - 0 1").unwrap();
+        let score = |uci: &str| Move::from_uci(uci, &board).unwrap().mvv_lva_score(&board);
+
+        // Most valuable victim first
+        assert!(score("b5c6") > score("d1d5"));
+        assert!(score("d1d5") > score("b2c3"));
+        // Then least valuable attacker
+        assert!(score("b2c3") > score("e4c3"));
+        // Quiet moves come last
+        assert!(score("e4c3") > 0);
+        assert_eq!(score("e4g5"), 0);
+    }
+
+    #[test]
+    fn test_from_san() {
+        let san = |fen: &str, value: &str| {
+            let board = Board::from_fen(fen).unwrap();
+            Move::from_san(value, &board).map(|mv| mv.uci().to_string())
+        };
+
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(start, "e4").as_deref(), Some("e2e4"));
+        assert_eq!(san(start, "Nf3").as_deref(), Some("g1f3"));
+        assert_eq!(san(start, "Nf3!?").as_deref(), Some("g1f3"));
+        assert_eq!(san(start, "e5"), None);
+        assert_eq!(san(start, "Zf3"), None);
+
+        // Disambiguation by file and by rank
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(fen, "Ra3"), None);
+        assert_eq!(san(fen, "R5a3").as_deref(), Some("a5a3"));
+        let fen = "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1";
+        assert_eq!(san(fen, "Rd1"), None);
+        assert_eq!(san(fen, "Rad1").as_deref(), Some("a1d1"));
+        assert_eq!(san(fen, "Rab1").as_deref(), Some("a1b1"));
+        assert_eq!(san(fen, "Rfb1").as_deref(), Some("f1b1"));
+        let fen = "4k3/8/8/6N1/8/8/8/4K1N1 w - - 0 1";
+        assert_eq!(san(fen, "Nf3"), None);
+        assert_eq!(san(fen, "Ngf3"), None);
+        assert_eq!(san(fen, "N1f3").as_deref(), Some("g1f3"));
+        assert_eq!(san(fen, "Ng5f3").as_deref(), Some("g5f3"));
+        let fen = "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+        assert_eq!(san(fen, "O-O").as_deref(), Some("e1g1"));
+        assert_eq!(san(fen, "0-0-0").as_deref(), Some("e1c1"));
+
+        // Captures, en passant and promotions
+        let fen = "1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san(fen, "exd6").as_deref(), Some("e5d6"));
+        assert_eq!(san(fen, "a8=Q+").as_deref(), Some("a7a8q"));
+        assert_eq!(san(fen, "axb8N").as_deref(), Some("a7b8n"));
+        assert_eq!(san(fen, "a8"), None);
+    }
 
-            let a = to_dir.0 * from_dir_unit.0; // >= 0, if 0 should ensure that to_dir.0 == 0 as well
-            let b = to_dir.1 * from_dir_unit.1; // >= 0, if 0 should ensure that to_dir.1 == 0 as well
+    #[test]
+    fn test_san_matches_algebraic_notation() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(&board, &mut moves, &mut currently_in_check);
+
+        let mv = Move::from_uci("a1d1", &board).unwrap();
+        assert_eq!(mv.san(&board), "Rad1");
+        for mv in &moves {
+            assert_eq!(
+                mv.san(&board),
+                mv.algebraic_notation(&board, &moves).to_string()
+            );
+        }
+    }
 
-            if a == 0 {
-                return (to_dir.0 == 0) && (from_dir_unit.0 == 0);
-            } else if b == 0 {
-                return (to_dir.1 == 0) && (from_dir_unit.1 == 0);
-            } else if a != b {
-                return false;
+    #[test]
+    fn test_from_san_inverts_algebraic_notation() {
+        fn check(board: &Board, depth: usize) {
+            let mut moves = Vec::new();
+            let mut currently_in_check = false;
+            generate_moves(board, &mut moves, &mut currently_in_check);
+
+            for mv in &moves {
+                let notation = mv.algebraic_notation(board, &moves).to_string();
+                assert_eq!(
+                    Move::from_san(&notation, board),
+                    Some(*mv),
+                    "{} in \"{}\"",
+                    notation,
+                    board.fen()
+                );
+
+                if depth > 1 {
+                    let mut new_board = *board;
+                    mv.apply(&mut new_board);
+                    check(&new_board, depth - 1);
+                }
             }
         }
 
-        true
-    });
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "4k3/8/8/6N1/8/8/1Q3Q2/R3KN1R w KQ - 0 1",
+        ] {
+            check(&Board::from_fen(fen).unwrap(), 2);
+        }
+    }
 }
@@ -0,0 +1,289 @@
+use crate::{
+    bitboard::Bitboard,
+    board::{Board, Color, Piece},
+    game::Game,
+    moves::generate_moves,
+};
+
+/// Why a game ended in a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawReason {
+    /// The side to move has no legal move but is not in check.
+    Stalemate,
+
+    /// Neither side has enough material left to ever deliver checkmate.
+    InsufficientMaterial,
+
+    /// No capture nor pawn move happened in the last 150 halfmoves.
+    SeventyFiveMoveRule,
+
+    /// The same position occurred five times.
+    FivefoldRepetition,
+}
+
+/// How a game ended. Only outcomes that end the game by themselves are reported, draws that have to be
+/// claimed by a player (such as the fifty-move rule or threefold repetition) are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameOutcome {
+    Checkmate { winner: Color },
+    Draw(DrawReason),
+}
+
+/// The state of a finished game, including the draws that may be claimed (the fifty-move rule and threefold
+/// repetition). Repetitions depend on the history of the game and are only reported by [`Game::result`]. See
+/// [`GameOutcome`] for the outcomes that end the game by themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameResult {
+    /// The given color won by checkmate.
+    Checkmate(Color),
+    Stalemate,
+    FiftyMoveDraw,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+}
+
+impl std::fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameOutcome::Checkmate { winner } => {
+                let winner = match winner {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                write!(f, "Checkmate, {} wins", winner)
+            }
+            GameOutcome::Draw(DrawReason::Stalemate) => write!(f, "Draw by stalemate"),
+            GameOutcome::Draw(DrawReason::InsufficientMaterial) => {
+                write!(f, "Draw by insufficient material")
+            }
+            GameOutcome::Draw(DrawReason::SeventyFiveMoveRule) => {
+                write!(f, "Draw by the seventy-five-move rule")
+            }
+            GameOutcome::Draw(DrawReason::FivefoldRepetition) => {
+                write!(f, "Draw by fivefold repetition")
+            }
+        }
+    }
+}
+
+impl Board {
+    /// Whether checkmate is impossible for both sides: lone kings, a single minor piece, or only bishops
+    /// all standing on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy_or_pawns =
+            *self.get(Piece::Pawn) | *self.get(Piece::Rook) | *self.get(Piece::Queen);
+        if !heavy_or_pawns.is_empty() {
+            return false;
+        }
+
+        let knights = *self.get(Piece::Knight);
+        let bishops = *self.get(Piece::Bishop);
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+
+        knights.is_empty()
+            && ((bishops & Bitboard(Bitboard::LIGHT_SQUARES)).is_empty()
+                || (bishops & Bitboard(Bitboard::DARK_SQUARES)).is_empty())
+    }
+
+    /// Whether the seventy-five-move rule applies, i.e. no capture nor pawn move happened in the last 150
+    /// halfmoves. Unlike the fifty-move rule, this draw does not need to be claimed.
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove_clock >= 150
+    }
+
+    /// The result of the game if the position ends it (or allows claiming a draw), `None` if the game
+    /// continues. The moves are generated only once.
+    pub fn result(&self) -> Option<GameResult> {
+        let mut moves = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(self, &mut moves, &mut currently_in_check);
+
+        if moves.is_empty() {
+            return Some(if currently_in_check {
+                GameResult::Checkmate(self.next_to_move().opposite())
+            } else {
+                GameResult::Stalemate
+            });
+        }
+
+        if self.is_insufficient_material() {
+            return Some(GameResult::InsufficientMaterial);
+        }
+
+        // A checkmate delivered on the last allowed move takes precedence, hence the check comes last
+        if self.is_fifty_move_draw() {
+            return Some(GameResult::FiftyMoveDraw);
+        }
+
+        None
+    }
+
+    /// The outcome of the game if the position ends it, `None` if the game continues. Repetitions are not
+    /// detected since they depend on the history of the game, see [`Game::outcome`].
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        match self.result()? {
+            GameResult::Checkmate(winner) => Some(GameOutcome::Checkmate { winner }),
+            GameResult::Stalemate => Some(GameOutcome::Draw(DrawReason::Stalemate)),
+            GameResult::InsufficientMaterial => {
+                Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+            }
+            // Only the seventy-five-move rule ends the game without a claim
+            GameResult::FiftyMoveDraw => self
+                .is_seventy_five_move_draw()
+                .then_some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule)),
+            // Never reported for a single position
+            GameResult::ThreefoldRepetition => None,
+        }
+    }
+}
+
+impl Game {
+    /// The result of the game in its current position (see [`Board::result`]), or a threefold repetition,
+    /// `None` if the game continues.
+    pub fn result(&self) -> Option<GameResult> {
+        self.board().result().or_else(|| {
+            self.is_threefold_repetition()
+                .then_some(GameResult::ThreefoldRepetition)
+        })
+    }
+
+    /// The outcome of the game in its current position, `None` if the game continues. On top of
+    /// [`Board::outcome`], this detects fivefold repetitions from the positions reached during the game.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if let Some(outcome) = self.board().outcome() {
+            return Some(outcome);
+        }
+
+        if self.repetitions() >= 5 {
+            return Some(GameOutcome::Draw(DrawReason::FivefoldRepetition));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+
+    #[test]
+    fn test_outcome() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        assert_eq!(board.outcome(), None);
+
+        let board = Board::from_fen("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(
+            board.outcome(),
+            Some(GameOutcome::Checkmate {
+                winner: Color::White
+            })
+        );
+
+        let board = Board::from_fen("7k/5Q2/8/8/8/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(
+            board.outcome(),
+            Some(GameOutcome::Draw(DrawReason::Stalemate))
+        );
+    }
+
+    /// Shuffles the knights back and forth, coming back to the starting position every four plies.
+    fn knight_shuffle(board: &Board, plies: usize) -> Vec<Move> {
+        let mut board = *board;
+        let mut moves = Vec::new();
+        for ply in 0..plies {
+            let uci = ["g1f3", "g8f6", "f3g1", "f6g8"][ply % 4];
+            let mv = Move::from_uci(uci, &board).unwrap();
+            mv.apply(&mut board);
+            moves.push(mv);
+        }
+        moves
+    }
+
+    #[test]
+    fn test_seventy_five_move_rule() {
+        let start = Board::from_fen("4k1n1/8/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        let mut board = start;
+        for (ply, mv) in knight_shuffle(&start, 150).iter().enumerate() {
+            assert_eq!(board.outcome(), None, "ply {}", ply);
+            mv.apply(&mut board);
+        }
+        assert_eq!(board.halfmove_clock, 150);
+        assert_eq!(
+            board.outcome(),
+            Some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule))
+        );
+    }
+
+    #[test]
+    fn test_fivefold_repetition() {
+        let mut game = Game::new(Board::from_fen("4k1n1/8/8/8/8/8/8/4K1N1 w - - 0 1").unwrap());
+        let moves = knight_shuffle(&game.start(), 16);
+        for mv in &moves[..15] {
+            game.push(*mv);
+        }
+        assert_eq!(game.outcome(), None); // Repeated four times only
+        assert_eq!(game.result(), Some(GameResult::ThreefoldRepetition)); // But can be claimed
+
+        game.push(moves[15]);
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::FivefoldRepetition))
+        );
+        assert_eq!(game.board().outcome(), None);
+    }
+
+    #[test]
+    fn test_result() {
+        // Back-rank mate
+        let board = Board::from_fen("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(board.result(), Some(GameResult::Checkmate(Color::White)));
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        assert_eq!(board.result(), None);
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 100 80").unwrap();
+        assert_eq!(board.result(), Some(GameResult::FiftyMoveDraw));
+        assert_eq!(board.outcome(), None); // Has to be claimed
+
+        let board = Board::from_fen("8/8/4k3/8/8/3KN3/8/8 w - - 0 1").unwrap();
+        assert_eq!(board.result(), Some(GameResult::InsufficientMaterial));
+    }
+
+    #[cfg(feature = "study")]
+    #[test]
+    fn test_result_stalemate_study() {
+        for study in crate::study::get_stalemates_study() {
+            let board = Board::from_fen(&study.start.fen).unwrap();
+            assert_eq!(
+                board.result(),
+                Some(GameResult::Stalemate),
+                "\"{}\"",
+                study.start.fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for (fen, insufficient) in [
+            ("8/8/4k3/8/8/3K4/8/8 w - - 0 1", true),
+            ("8/8/4k3/8/8/3KN3/8/8 w - - 0 1", true),
+            ("8/8/4kb2/8/8/3KB3/8/8 w - - 0 1", true), // Both bishops on dark squares
+            ("8/8/4k1b1/8/8/3KB3/8/8 w - - 0 1", false), // Opposite-colored bishops
+            ("8/8/4kn2/8/8/3KN3/8/8 w - - 0 1", false),
+            ("8/8/4k3/8/8/3KP3/8/8 w - - 0 1", false),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.is_insufficient_material(), insufficient, "{}", fen);
+        }
+
+        let board = Board::from_fen("8/8/4k3/8/8/3KN3/8/8 w - - 0 1").unwrap();
+        assert_eq!(
+            board.outcome(),
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+        );
+    }
+}
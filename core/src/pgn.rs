@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    board::{Board, Color},
+    game::Game,
+    moves::{Move, generate_moves},
+};
+
+/// Error raised while parsing a PGN game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// A tag pair line that is not of the form `[Name "Value"]`.
+    InvalidTag(String),
+
+    /// The FEN of the `FEN` tag could not be parsed.
+    InvalidFen(String),
+
+    /// A comment or a variation is not closed before the end of the text.
+    UnterminatedComment,
+
+    /// A movetext token that does not resolve to exactly one legal move, `ply` is counted from 1.
+    IllegalMove { ply: usize, san: String },
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::InvalidTag(line) => write!(f, "Invalid tag pair: {}", line),
+            PgnError::InvalidFen(e) => write!(f, "Invalid FEN tag: {}", e),
+            PgnError::UnterminatedComment => write!(f, "Unterminated comment or variation"),
+            PgnError::IllegalMove { ply, san } => write!(f, "Illegal move {} at ply {}", san, ply),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+const RESULT_TOKENS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Parse a single PGN game: the tag pairs go into [`Game::tags`] and the moves of the movetext (in SAN) are
+/// resolved against the running position. Comments (`{...}` and `;...`), variations, numeric annotation glyphs
+/// and move numbers are skipped. The result token of the movetext, if any, is stored as the `Result` tag.
+pub fn parse_pgn(text: &str) -> Result<Game, PgnError> {
+    let mut tags = BTreeMap::new();
+
+    // Tag pairs come first, one per line
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next_if(|line| {
+        let line = line.trim();
+        line.is_empty() || line.starts_with('[')
+    }) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, value) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .and_then(|line| line.split_once(char::is_whitespace))
+            .and_then(|(name, value)| {
+                let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+                Some((name, value.replace("\\\"", "\"").replace("\\\\", "\\")))
+            })
+            .ok_or_else(|| PgnError::InvalidTag(line.to_string()))?;
+        tags.insert(name.to_string(), value);
+    }
+
+    let start = match tags.get("FEN") {
+        Some(fen) => Board::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Board::default_position(),
+    };
+    let mut game = Game::new(start);
+    game.tags = tags;
+
+    let movetext = lines.collect::<Vec<_>>().join("\n");
+    for token in movetext_tokens(&movetext)? {
+        if RESULT_TOKENS.contains(&token) {
+            game.tags.insert("Result".to_string(), token.to_string());
+            break;
+        }
+
+        let san = strip_move_number(token);
+        if san.is_empty() || san.starts_with('$') {
+            continue;
+        }
+
+        let mv = Move::from_san(san, &game.board()).ok_or_else(|| PgnError::IllegalMove {
+            ply: game.ply_count() + 1,
+            san: san.to_string(),
+        })?;
+        game.push(mv);
+    }
+
+    Ok(game)
+}
+
+/// Remove the move number of a token, which may be glued to the move (`1.e4`, `12...Nf6`). The digits only
+/// make a move number when followed by a dot, `0-0` being castling.
+fn strip_move_number(token: &str) -> &str {
+    let san = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if san.len() == token.len() || san.starts_with('.') {
+        san.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+/// Split the movetext in whitespace separated tokens, leaving out comments and (nested) variations.
+fn movetext_tokens(movetext: &str) -> Result<Vec<&str>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+    let mut variation_depth = 0;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' => {
+                let end = rest.find('}').ok_or(PgnError::UnterminatedComment)?;
+                rest = &rest[end + 1..];
+            }
+            ';' => {
+                rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+            }
+            '(' => {
+                variation_depth += 1;
+                rest = &rest[1..];
+            }
+            ')' => {
+                variation_depth -= 1;
+                rest = &rest[1..];
+            }
+            c if c.is_whitespace() => {
+                rest = &rest[c.len_utf8()..];
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{;()".contains(c))
+                    .unwrap_or(rest.len());
+                if variation_depth == 0 {
+                    tokens.push(&rest[..end]);
+                }
+                rest = &rest[end..];
+            }
+        }
+    }
+
+    if variation_depth != 0 {
+        return Err(PgnError::UnterminatedComment);
+    }
+    Ok(tokens)
+}
+
+/// The seven tags every PGN game must have, in the order they have to be exported, with their default value.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Movetext lines are wrapped at this width, as recommended by the PGN standard.
+const MAX_LINE_LENGTH: usize = 80;
+
+impl Game {
+    /// Export the game in PGN: the seven tag roster (missing tags get their default value), then the other
+    /// tags, and the movetext in SAN ended by the result token. A game that does not start from the standard
+    /// position also gets the `SetUp` and `FEN` tags.
+    pub fn to_pgn(&self) -> String {
+        let mut tags: Vec<(&str, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|(name, default)| {
+                let value = self.tags.get(*name).map_or(*default, String::as_str);
+                (*name, value.to_string())
+            })
+            .collect();
+        if self.start() != Board::default_position() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push((
+                "FEN",
+                format!("{} {} 1", self.start().fen(), self.start().halfmove_clock),
+            ));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(tag, _)| tag == name) {
+                tags.push((name, value.clone()));
+            }
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in &tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        // Movetext tokens, a move number precedes every white move (and the first move if black starts)
+        let mut tokens = Vec::new();
+        let mut board = self.start();
+        let mut legal_moves = Vec::new();
+        let mut currently_in_check = false;
+        let black_starts = self.start().next_to_move() == Color::Black;
+        for (index, mv) in self.moves().iter().enumerate() {
+            let move_number = (index + black_starts as usize) / 2 + 1;
+            let white_to_move = board.next_to_move() == Color::White;
+            if white_to_move {
+                tokens.push(format!("{}.", move_number));
+            } else if index == 0 {
+                tokens.push(format!("{}...", move_number));
+            }
+
+            generate_moves(&board, &mut legal_moves, &mut currently_in_check);
+            tokens.push(mv.algebraic_notation(&board, &legal_moves).to_string());
+            mv.apply(&mut board);
+        }
+        tokens.push(tags[6].1.clone());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+        pgn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual game"]
+[Site "?"]
+[Date "2026.01.01"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {The king's pawn} e5 2. Bc4 Nc6 (2... Nf6 3. d3) 3. Qh5 Nf6?? $4 ; Missing the threat
+4. Qxf7# 1-0
+"#;
+
+    #[test]
+    fn test_parse_annotated_game() {
+        let game = parse_pgn(SCHOLARS_MATE).unwrap();
+        assert_eq!(game.tags.get("White").map(String::as_str), Some("Alice"));
+        assert_eq!(game.tags.get("Result").map(String::as_str), Some("1-0"));
+        assert_eq!(game.ply_count(), 7);
+        assert_eq!(
+            game.board().fen().to_string(),
+            "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq -"
+        );
+    }
+
+    #[test]
+    fn test_parse_from_fen_tag() {
+        let game = parse_pgn(
+            "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n\n1.O-O-O Kf7 2.Rd7+ *",
+        )
+        .unwrap();
+        assert_eq!(game.ply_count(), 3);
+        assert_eq!(game.tags.get("Result").map(String::as_str), Some("*"));
+        assert_eq!(
+            game.board().fen().to_string(),
+            "8/3R1k2/8/8/8/8/8/2K5 b - -"
+        );
+    }
+
+    #[test]
+    fn test_parse_castling_with_zeros() {
+        // Castling may be written with zeros, which must not be taken for a move number
+        let game = parse_pgn(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 5.d3 d6 6. Bg5 h6 7. Bh4 Bd7 8. Nc3 Qe7 9. a3 0-0-0 *",
+        )
+        .unwrap();
+        assert_eq!(game.ply_count(), 18);
+        assert_eq!(
+            game.board().fen().to_string(),
+            "2kr3r/pppbqpp1/2np1n1p/2b1p3/2B1P2B/P1NP1N2/1PP2PPP/R2Q1RK1 w - -"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_pgn("1. e4 e5 2. Ke3").unwrap_err(),
+            PgnError::IllegalMove {
+                ply: 3,
+                san: "Ke3".to_string()
+            }
+        );
+        assert_eq!(
+            parse_pgn("1. e4 {unterminated").unwrap_err(),
+            PgnError::UnterminatedComment
+        );
+        assert!(matches!(
+            parse_pgn("[Event]\n1. e4").unwrap_err(),
+            PgnError::InvalidTag(_)
+        ));
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let game = parse_pgn(SCHOLARS_MATE).unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"Casual game\"]\n[Site \"?\"]\n[Date \"2026.01.01\"]"));
+        assert!(pgn.ends_with("\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n"));
+        assert!(!pgn.contains("[FEN"));
+
+        let reparsed = parse_pgn(&pgn).unwrap();
+        assert_eq!(reparsed.moves(), game.moves());
+        assert_eq!(reparsed.tags, game.tags);
+    }
+
+    #[test]
+    fn test_export_from_position() {
+        let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 3 1").unwrap());
+        for san in ["Kf7", "O-O-O", "Ke6"] {
+            let mv = Move::from_san(san, &game.board()).unwrap();
+            game.push(mv);
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 3 1\"]\n"));
+        assert!(pgn.contains("[Result \"*\"]"));
+        assert!(pgn.ends_with("\n\n1... Kf7 2. O-O-O Ke6 *\n"));
+
+        let reparsed = parse_pgn(&pgn).unwrap();
+        assert_eq!(reparsed.start(), game.start());
+        assert_eq!(reparsed.moves(), game.moves());
+    }
+}
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StudyEntryStart {
@@ -19,26 +19,34 @@ pub struct StudyEntry {
     pub expected: Vec<StudyEntryExpected>,
 }
 
+/// A position with its known perft node counts, as an EPD record with `D1`, `D2`, ... operations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerftEntry {
+    pub description: Option<String>,
+    pub epd: String,
+}
+
 // Include the study data as a JSON string at compile time
 const STUDY_CASTLING: &str = include_str!("../data/castling.json");
 const STUDY_CHECKMATES: &str = include_str!("../data/checkmates.json");
 const STUDY_FAMOUS: &str = include_str!("../data/famous.json");
 const STUDY_PAWNS: &str = include_str!("../data/pawns.json");
+const STUDY_PERFT: &str = include_str!("../data/perft.json");
 const STUDY_PROMOTIONS: &str = include_str!("../data/promotions.json");
 const STUDY_STALEMATES: &str = include_str!("../data/stalemates.json");
 const STUDY_STANDARD: &str = include_str!("../data/standard.json");
 const STUDY_TAXING: &str = include_str!("../data/taxing.json");
 
-// Deserialize the JSON strings into vectors of StudyEntry
-fn load_study_entries(json_data: &str) -> Vec<StudyEntry> {
+// Deserialize the JSON strings into vectors of entries
+fn load_study_entries<T: DeserializeOwned>(json_data: &str) -> Vec<T> {
     #[derive(Debug, Clone, Serialize, Deserialize)]
-    struct StudyData {
+    struct StudyData<T> {
         description: Option<String>,
         #[serde(rename = "testCases")]
-        test_cases: Vec<StudyEntry>,
+        test_cases: Vec<T>,
     }
 
-    let study_data: StudyData =
+    let study_data: StudyData<T> =
         serde_json::from_str(json_data).expect("Failed to parse study data");
     study_data.test_cases
 }
@@ -60,6 +68,10 @@ pub fn get_pawns_study() -> Vec<StudyEntry> {
     load_study_entries(STUDY_PAWNS)
 }
 
+pub fn get_perft_study() -> Vec<PerftEntry> {
+    load_study_entries(STUDY_PERFT)
+}
+
 pub fn get_promotions_study() -> Vec<StudyEntry> {
     load_study_entries(STUDY_PROMOTIONS)
 }
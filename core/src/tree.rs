@@ -56,6 +56,61 @@ impl<T> Tree<T> {
     pub fn node_count(&self) -> usize {
         self.container.len()
     }
+
+    /// Build a new tree from the subtree rooted at `node_ref`, each value being converted with `f`. The children
+    /// keep their order, the rest of the tree is left out.
+    pub fn map_subtree<U>(&self, node_ref: TreeNodeRef, mut f: impl FnMut(&T) -> U) -> Tree<U> {
+        let mut tree = Tree::new(f(&self.container[node_ref.0 as usize].value));
+        let mut stack = vec![(node_ref, TreeNodeRef::ROOT)];
+        let mut children = Vec::new();
+        while let Some((source, target)) = stack.pop() {
+            let mut child = self.container[source.0 as usize].first_child;
+            while let Some(child_ref) = child {
+                children.push(TreeNodeRef(child_ref.get()));
+                child = self.container[child_ref.get() as usize].next_siblings;
+            }
+
+            // Children are prepended, hence pushed from the last one
+            for child_ref in children.drain(..).rev() {
+                let value = f(&self.container[child_ref.0 as usize].value);
+                stack.push((child_ref, tree.get_mut(target).push_child(value)));
+            }
+        }
+        tree
+    }
+}
+
+impl<T: std::fmt::Display> Tree<T> {
+    /// Display the tree down to `max_depth` (0 displays only the root), one node per line indented by its depth.
+    pub fn debug_print(&self, max_depth: usize) -> TreeDisplay<'_, T> {
+        TreeDisplay {
+            tree: self,
+            max_depth,
+        }
+    }
+}
+
+/// Indented rendering of a tree, see [`Tree::debug_print`].
+pub struct TreeDisplay<'a, T> {
+    tree: &'a Tree<T>,
+    max_depth: usize,
+}
+
+impl<'a, T: std::fmt::Display> std::fmt::Display for TreeDisplay<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut stack = vec![(self.tree.root(), 0)];
+        while let Some((node, depth)) = stack.pop() {
+            writeln!(f, "{}{}", "  ".repeat(depth), *node)?;
+
+            if let Some(next) = node.next().filter(|_| depth > 0) {
+                stack.push((next, depth));
+            }
+            if let Some(child) = node.child().filter(|_| depth < self.max_depth) {
+                stack.push((child, depth + 1));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// A reference to a node in the tree, which allows us to navigate the tree structure.
@@ -172,6 +227,24 @@ impl<'a, T> TreeRefMut<'a, T> {
             .map(|sibling_ref| TreeNodeRef(sibling_ref.get()))
     }
 
+    /// Remove the children of this node, which must be leaves and the last nodes pushed to the tree (e.g. the
+    /// nodes of an expansion left unfinished).
+    pub fn drop_children(&mut self) {
+        let mut child = self.tree.container[self.node_ref.0 as usize]
+            .first_child
+            .take();
+        while let Some(child_ref) = child {
+            assert_eq!(
+                child_ref.get() as usize,
+                self.tree.container.len() - 1,
+                "Only the last nodes of the tree can be dropped"
+            );
+            let node = self.tree.container.pop().unwrap();
+            assert!(node.first_child.is_none(), "Only leaves can be dropped");
+            child = node.next_siblings;
+        }
+    }
+
     pub fn push_child(&mut self, value: T) -> TreeNodeRef {
         let new_node_index = self.tree.container.len() as u32;
         let current_first_child = self.tree.container[self.node_ref.0 as usize].first_child;
@@ -0,0 +1,157 @@
+use crate::board::{BoardFlags, Piece};
+
+/// Random keys used to hash a position, a position's key is the XOR of the keys of every feature
+/// present on the board (piece on a square, castling right, en passant file, side to move). This allows
+/// the key to be updated incrementally when a move is applied, by XORing in and out the features
+/// that changed.
+pub struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    white_to_move: u64,
+}
+
+/// The keys are generated at compile time from a fixed seed, so that hashes are stable across runs.
+pub const ZOBRIST: ZobristKeys = ZobristKeys::generate(0x4368_6573_736F_7465);
+
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+impl ZobristKeys {
+    const CASTLING_FLAGS: [BoardFlags; 4] = [
+        BoardFlags::WHITE_KING_SIDE_CASTLE,
+        BoardFlags::WHITE_QUEEN_SIDE_CASTLE,
+        BoardFlags::BLACK_KING_SIDE_CASTLE,
+        BoardFlags::BLACK_QUEEN_SIDE_CASTLE,
+    ];
+
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = ZobristKeys {
+            pieces: [[0; 64]; 12],
+            castling: [0; 4],
+            en_passant: [0; 8],
+            white_to_move: 0,
+        };
+
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[piece][square] = splitmix64(&mut state);
+                square += 1;
+            }
+            piece += 1;
+        }
+
+        let mut i = 0;
+        while i < 4 {
+            keys.castling[i] = splitmix64(&mut state);
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < 8 {
+            keys.en_passant[i] = splitmix64(&mut state);
+            i += 1;
+        }
+
+        keys.white_to_move = splitmix64(&mut state);
+        keys
+    }
+
+    /// Key of a (colored) piece standing on the given square.
+    pub fn piece(&self, piece: Piece, square: u8) -> u64 {
+        self.pieces[piece as usize][square as usize]
+    }
+
+    /// Combined key of all the castling rights present in the flags.
+    pub fn castling(&self, flags: BoardFlags) -> u64 {
+        let mut key = 0;
+        for (index, flag) in Self::CASTLING_FLAGS.iter().enumerate() {
+            if flags.contains(*flag) {
+                key ^= self.castling[index];
+            }
+        }
+        key
+    }
+
+    /// Key of the en passant target square (only its file is hashed), 0 if there is none.
+    pub fn en_passant(&self, en_passant_square: u8) -> u64 {
+        if en_passant_square < 64 {
+            self.en_passant[(en_passant_square % 8) as usize]
+        } else {
+            0
+        }
+    }
+
+    /// Key of the side to move, 0 when black is to move.
+    pub fn side_to_move(&self, flags: BoardFlags) -> u64 {
+        if flags.contains(BoardFlags::WHITE_TO_MOVE) {
+            self.white_to_move
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::Board,
+        moves::{Move, generate_moves},
+    };
+
+    fn check_incremental_hash(board: &Board, depth: usize) {
+        let mut moves: Vec<Move> = Vec::new();
+        let mut currently_in_check = false;
+        generate_moves(board, &mut moves, &mut currently_in_check);
+
+        for mv in moves {
+            let mut new_board = *board;
+            mv.apply(&mut new_board);
+            assert_eq!(
+                new_board.zobrist,
+                new_board.compute_zobrist(),
+                "Incremental hash mismatch after {} from \"{}\"",
+                mv.uci(),
+                board.fen()
+            );
+
+            if depth > 1 {
+                check_incremental_hash(&new_board, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_hash_castling_rights() {
+        // Positions where castling rights are lost through king moves, rook moves and rook captures
+        for fen in [
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.zobrist, board.compute_zobrist());
+            check_incremental_hash(&board, 2);
+        }
+    }
+
+    #[cfg(feature = "study")]
+    #[test]
+    fn test_incremental_hash_castling_study() {
+        for study in crate::study::get_castling_study() {
+            let board = Board::from_fen(&study.start.fen).unwrap();
+            check_incremental_hash(&board, 2);
+        }
+    }
+}
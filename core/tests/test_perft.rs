@@ -0,0 +1,27 @@
+#[cfg(feature = "study")]
+mod perft {
+    use chessoteric_core::{epd::parse_epd_line, moves::perft, study::get_perft_study};
+
+    const MAX_DEPTH: u32 = 4;
+
+    #[test]
+    fn test_perft_study() {
+        for entry in get_perft_study() {
+            let epd = parse_epd_line(&entry.epd)
+                .unwrap_or_else(|e| panic!("Invalid perft entry \"{}\": {}", entry.epd, e));
+            let counts = epd.perft_counts();
+            assert!(!counts.is_empty(), "No perft counts in \"{}\"", entry.epd);
+
+            for (depth, nodes) in counts.into_iter().filter(|(depth, _)| *depth <= MAX_DEPTH) {
+                assert_eq!(
+                    perft(&epd.board, depth),
+                    nodes,
+                    "Perft at depth {} of \"{}\" ({})",
+                    depth,
+                    entry.epd,
+                    entry.description.as_deref().unwrap_or("no description")
+                );
+            }
+        }
+    }
+}
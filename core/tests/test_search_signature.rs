@@ -0,0 +1,73 @@
+use chessoteric_core::{
+    ai::{AiLimit, chessoteric::search},
+    board::Board,
+};
+
+const DEPTH: u16 = 4;
+
+const POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1",
+];
+
+/// Signature of the principal variation found at [`DEPTH`] for each of the [`POSITIONS`]. The lines depend on
+/// the evaluation, each set of evaluation features has its own baseline. A change means the search picks
+/// different lines: if it is intended, replace the baseline with the signatures printed by the failing test.
+#[cfg(not(feature = "eval_larry_kaufman"))]
+const BASELINE: [u64; 6] = [
+    0xd4bfb2994a574ab7,
+    0xd869555952878e1c,
+    0xe391d7ea5a300268,
+    0x3fb02b61b297592b,
+    0x56707920fb39e203,
+    0x8e7a67fddfc0b2ef,
+];
+#[cfg(all(feature = "eval_larry_kaufman", not(feature = "eval_mobility")))]
+const BASELINE: [u64; 6] = [
+    0xc4ba2ccf72294d28,
+    0x0d11b681d3ea72e2,
+    0x354bda471ee62a36,
+    0x59cf371b955d20d2,
+    0x56707920fb39e203,
+    0x8e7a67fddfc0b2ef,
+];
+#[cfg(all(feature = "eval_larry_kaufman", feature = "eval_mobility"))]
+const BASELINE: [u64; 6] = [
+    0x3098c2fa79f9c7a1,
+    0xcc4ac9811e24adf6,
+    0x3ec899738cbabd20,
+    0x1e6f181b74166d48,
+    0x56707920fb39e203,
+    0x8e7a67fddfc0b2ef,
+];
+
+#[test]
+fn test_pv_signatures() {
+    let limits = AiLimit {
+        depth: Some(DEPTH),
+        ..Default::default()
+    };
+
+    let signatures: Vec<u64> = POSITIONS
+        .iter()
+        .map(|fen| {
+            let board = Board::from_fen(fen).unwrap();
+            search(&board, limits.clone()).unwrap().pv_signature()
+        })
+        .collect();
+
+    let report = signatures
+        .iter()
+        .map(|signature| format!("{:#018x},", signature))
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(
+        signatures, BASELINE,
+        "The search behavior changed, new signatures:\n{}",
+        report
+    );
+}
@@ -1,6 +1,6 @@
 #[cfg(feature = "study")]
 mod study {
-    use chessoteric_core::{moves::generate_moves, study::StudyEntry};
+    use chessoteric_core::{eval::evaluate, moves::generate_moves, study::StudyEntry};
     use pretty_assertions::assert_eq;
 
     fn handle_test_study(study: StudyEntry) {
@@ -32,6 +32,33 @@ mod study {
         );
     }
 
+    #[test]
+    fn test_evaluation_symmetry() {
+        let studies = [
+            chessoteric_core::study::get_castling_study(),
+            chessoteric_core::study::get_checkmates_study(),
+            chessoteric_core::study::get_famous_study(),
+            chessoteric_core::study::get_pawns_study(),
+            chessoteric_core::study::get_promotions_study(),
+            chessoteric_core::study::get_stalemates_study(),
+            chessoteric_core::study::get_standard_study(),
+            chessoteric_core::study::get_taxing_study(),
+        ];
+
+        for study in studies.iter().flatten() {
+            let board = chessoteric_core::board::Board::from_fen(&study.start.fen).unwrap();
+            let score = evaluate(&board);
+            let mirrored_score = evaluate(&board.flip_colors());
+            assert!(
+                (score + mirrored_score).abs() < 1e-4,
+                "Asymmetric evaluation for \"{}\": {} vs {} once mirrored",
+                study.start.fen,
+                score,
+                mirrored_score
+            );
+        }
+    }
+
     #[test]
     fn test_castling_move_generation() {
         let studies = chessoteric_core::study::get_castling_study();
@@ -1,5 +1,10 @@
-use crate::board::BoardWidget;
-use chessoteric_core::{ai::AiLimit, bitboard::Bitboard, moves::generate_moves};
+use crate::board::{BoardWidget, screen_to_square, square_to_screen};
+use chessoteric_core::{
+    ai::{AiLimit, Capabilities},
+    bitboard::Bitboard,
+    moves::{Move, generate_moves},
+    outcome::GameOutcome,
+};
 use ratatui::{
     DefaultTerminal, Frame,
     layout::{Constraint, Direction, Layout, Offset},
@@ -19,6 +24,10 @@ const TITLE_STR: &str = r"   █████████  █████
   ░░░░░░░░░  ░░░░ ░░░░░  ░░░░░░ ░░░░░░ ░░░░░░   ░░░░░░     ░░░░░   ░░░░░░  ░░░░░     ░░░░░  ░░░░░░  
 ";
 
+/// The features of the AI that can be turned on and off, with the key toggling each of them (with Ctrl). Only
+/// those the AI supports are offered.
+const TOGGLES: &[(Capabilities, char, &str)] = &[(Capabilities::PONDER, 'p', "Ponder")];
+
 struct AppState {
     board: chessoteric_core::board::SquareCentricBoard,
     moves: Vec<String>,
@@ -28,6 +37,11 @@ struct AppState {
     selected_position: Option<u8>,
     current_moves: Vec<chessoteric_core::moves::Move>,
     current_score: f32,
+    game_over: Option<GameOutcome>,
+    flipped: bool,
+    supported: Capabilities,     // Features of the AI, see Ai::capabilities
+    features: Capabilities,      // Features turned on, among the supported ones
+    expected_move: Option<Move>, // Reply expected from the player, pondered on during their turn
 }
 
 impl Default for AppState {
@@ -41,24 +55,77 @@ impl Default for AppState {
             current_moves: Vec::new(),
             selected_position: None,
             current_score: 0.0,
+            game_over: None,
+            flipped: false,
+            supported: Capabilities::empty(),
+            features: Capabilities::empty(),
+            expected_move: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Replace the current position by the one described by the FEN string, starting a new game from it
+    /// (the move history, selection and score are reset). The state is left untouched if the FEN is invalid.
+    fn load_fen(&mut self, fen: &str) -> Result<(), String> {
+        let board = chessoteric_core::board::SquareCentricBoard::parse_fen(fen.trim())?;
+        *self = Self {
+            board,
+            cursor_position: self.cursor_position,
+            flipped: self.flipped,
+            supported: self.supported,
+            features: self.features,
+            ..Self::default()
+        };
+        self.regenerate_moves();
+        Ok(())
+    }
+
+    /// Move the cursor by the given offset on the rendered grid, staying within the board.
+    fn move_cursor(&mut self, d_row: i8, d_col: i8) {
+        let (row, col) = square_to_screen(self.cursor_position, self.flipped);
+        let row = (row as i8 + d_row).clamp(0, 7) as u8;
+        let col = (col as i8 + d_col).clamp(0, 7) as u8;
+        self.cursor_position = screen_to_square(row, col, self.flipped);
+    }
+
+    fn regenerate_moves(&mut self) {
+        let board: chessoteric_core::board::Board = self.board.clone().into();
+        let mut in_check = false;
+        generate_moves(&board, &mut self.current_moves, &mut in_check);
+        self.game_over = board.outcome();
+    }
+
+    /// Turn a feature of the AI on or off, the features it does not support stay off.
+    fn toggle_feature(&mut self, feature: Capabilities) {
+        if self.supported.contains(feature) {
+            self.features.toggle(feature);
         }
     }
+
+    /// Play a move on the current position, record it in the history and check whether the game is over.
+    fn play_move(&mut self, mv: Move) {
+        self.moves.push(mv.to_string());
+        let mut board = self.board.clone().into();
+        mv.apply(&mut board);
+        self.board = chessoteric_core::board::SquareCentricBoard::from(board);
+        self.regenerate_moves();
+    }
 }
 
 pub fn app(terminal: &mut DefaultTerminal) -> std::io::Result<String> {
     let ai = chessoteric_core::ai::get_ai("simple").unwrap();
-    let mut state = AppState::default();
+    let mut state = AppState {
+        supported: ai.capabilities(),
+        ..AppState::default()
+    };
 
     if args().len() > 1 {
-        state.board =
-            chessoteric_core::board::SquareCentricBoard::parse_fen(&args().nth(1).unwrap())
-                .unwrap();
+        state.load_fen(&args().nth(1).unwrap()).unwrap();
+    } else {
+        state.regenerate_moves();
     }
 
-    let board = state.board.clone().into();
-    let mut in_check = false;
-    generate_moves(&board, &mut state.current_moves, &mut in_check);
-
     loop {
         terminal.draw(|frame| render(frame, &mut state))?;
 
@@ -74,83 +141,100 @@ pub fn app(terminal: &mut DefaultTerminal) -> std::io::Result<String> {
                 {
                     break Ok(format!("You entered: {}", state.board.fen()));
                 }
+                crossterm::event::Event::Key(key_event)
+                    if key_event.code == crossterm::event::KeyCode::Char('l')
+                        && key_event
+                            .modifiers
+                            .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    // Load the FEN typed in the buffer, keep the buffer if it is invalid so it can be fixed
+                    let fen = state.buffer.clone();
+                    if state.load_fen(&fen).is_ok() {
+                        state.buffer.clear();
+                    }
+                }
+                crossterm::event::Event::Key(key_event)
+                    if key_event
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL)
+                        && toggled_feature(key_event.code).is_some() =>
+                {
+                    state.toggle_feature(toggled_feature(key_event.code).unwrap());
+                }
                 crossterm::event::Event::Key(key_event) => {
                     if key_event.is_press() || key_event.is_repeat() {
                         match key_event.code {
-                            crossterm::event::KeyCode::Left => {
-                                if state.cursor_position > 0 {
-                                    state.cursor_position -= 1;
-                                }
-                            }
-                            crossterm::event::KeyCode::Right => {
-                                if state.cursor_position < 63 {
-                                    state.cursor_position += 1;
-                                }
-                            }
-                            crossterm::event::KeyCode::Down => {
-                                if state.cursor_position >= 8 {
-                                    state.cursor_position -= 8;
-                                }
-                            }
-                            crossterm::event::KeyCode::Up => {
-                                if state.cursor_position < 56 {
-                                    state.cursor_position += 8;
-                                }
-                            }
+                            crossterm::event::KeyCode::Left => state.move_cursor(0, -1),
+                            crossterm::event::KeyCode::Right => state.move_cursor(0, 1),
+                            crossterm::event::KeyCode::Up => state.move_cursor(-1, 0),
+                            crossterm::event::KeyCode::Down => state.move_cursor(1, 0),
                             crossterm::event::KeyCode::Esc => {
                                 state.selected_position = None;
                                 state.highlighted_moves = Bitboard::empty();
                             }
                             crossterm::event::KeyCode::Enter
                             | crossterm::event::KeyCode::Char(' ') => {
-                                if let Some(selected_position) = state.selected_position
+                                if state.game_over.is_some() {
+                                    // The game is over, no more moves are accepted
+                                } else if let Some(selected_position) = state.selected_position
                                     && (state.highlighted_moves.0 & (1 << state.cursor_position))
                                         != 0
                                 {
                                     state.highlighted_moves = Bitboard::empty();
 
                                     // Find the move that has the current cursor position as the destination square
-                                    if let Some(mv) = state.current_moves.iter().find(|mv| {
-                                        mv.to == state.cursor_position
-                                            && mv.from == selected_position
-                                    }) {
-                                        state.moves.push(mv.to_string());
-
-                                        let mut board = state.board.clone().into();
-                                        mv.apply(&mut board);
-
-                                        // Get the best move from the AI and apply it to the board
-                                        if ai.start(&board, AiLimit::default(), false)
-                                            == chessoteric_core::ai::AiType::Async
-                                        {
-                                            // Wait for 500 milliseconds before checking
-                                            std::thread::sleep(std::time::Duration::from_millis(
-                                                500,
-                                            ));
-                                        }
-                                        let ai_result = ai.stop();
-                                        if let Some(ai_result) = ai_result {
-                                            let ai_move = ai_result.best_move;
-                                            state.moves.push(ai_move.to_string());
-                                            ai_move.apply(&mut board);
-                                            state.current_score = ai_result.score;
-                                        }
+                                    if let Some(mv) = state
+                                        .current_moves
+                                        .iter()
+                                        .find(|mv| {
+                                            mv.to == state.cursor_position
+                                                && mv.from == selected_position
+                                        })
+                                        .copied()
+                                    {
+                                        state.play_move(mv);
+
+                                        // Get the best move from the AI and apply it to the board, the
+                                        // search pondered on during the player's turn goes on if they
+                                        // played the expected move
+                                        if state.game_over.is_none() {
+                                            let board = state.board.clone().into();
+                                            let search =
+                                                match state.expected_move.take() == Some(mv) {
+                                                    true => {
+                                                        ai.ponderhit();
+                                                        chessoteric_core::ai::AiType::Async
+                                                    }
+                                                    false => {
+                                                        ai.start(&board, AiLimit::default(), false)
+                                                    }
+                                                };
+                                            if search == chessoteric_core::ai::AiType::Async {
+                                                // Wait for 500 milliseconds before checking
+                                                std::thread::sleep(
+                                                    std::time::Duration::from_millis(500),
+                                                );
+                                            }
+                                            if let Some(ai_result) = ai.stop() {
+                                                state.current_score = ai_result.score;
+                                                state.play_move(ai_result.best_move);
 
-                                        // Finally, get the best move from the AI and apply it to the board
-                                        let new_board =
-                                            chessoteric_core::board::SquareCentricBoard::from(
-                                                board,
-                                            );
-                                        state.board = new_board;
-
-                                        // Regenerate moves for the new board state
-                                        state.current_moves.clear();
-                                        let mut in_check = false;
-                                        generate_moves(
-                                            &board,
-                                            &mut state.current_moves,
-                                            &mut in_check,
-                                        );
+                                                // Ponder on the reply of the principal variation
+                                                if let Some(&expected_move) = ai_result.pv.get(1)
+                                                    && state.features.contains(Capabilities::PONDER)
+                                                    && state.game_over.is_none()
+                                                {
+                                                    let board = state.board.clone().into();
+                                                    ai.ponder(
+                                                        &board,
+                                                        expected_move,
+                                                        AiLimit::default(),
+                                                        false,
+                                                    );
+                                                    state.expected_move = Some(expected_move);
+                                                }
+                                            }
+                                        }
                                     }
 
                                     state.selected_position = None;
@@ -221,6 +305,7 @@ fn render(frame: &mut Frame, state: &mut AppState) {
             selected: Some(state.cursor_position),
             screen: frame.area(),
             highlighted: state.highlighted_moves,
+            flipped: state.flipped,
         },
         middle_layout[0],
     );
@@ -266,13 +351,150 @@ fn render(frame: &mut Frame, state: &mut AppState) {
             .padding(Padding::vertical(1))
             .title(title)
     }
-    let ratio = ((state.current_score as f64 + 5.0) / 10.0).clamp(0.0, 1.0);
+    // Once the game is over, the banner replaces the evaluation gauge
+    if let Some(outcome) = state.game_over {
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(format!("Game over: {}", outcome))
+                .centered()
+                .block(title_block("Game over")),
+            layout[2],
+        );
+        return;
+    }
+
+    let ratio = gauge_ratio(state.current_score);
+
+    // The toggles of the features supported by the AI are listed in the title of the gauge
+    let toggles = TOGGLES
+        .iter()
+        .filter(|(feature, ..)| state.supported.contains(*feature))
+        .map(|(feature, key, name)| {
+            let on = match state.features.contains(*feature) {
+                true => "on",
+                false => "off",
+            };
+            format!("Ctrl+{}: {} {}", key.to_ascii_uppercase(), name, on)
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
 
     frame.render_widget(
         Gauge::default()
-            .block(title_block("title"))
+            .block(title_block(&toggles))
             .ratio(ratio)
             .label(format!("{:.1}", state.current_score)),
         layout[2],
     );
 }
+
+/// The feature toggled by a key pressed with Ctrl, if any.
+fn toggled_feature(code: crossterm::event::KeyCode) -> Option<Capabilities> {
+    TOGGLES
+        .iter()
+        .find(|(_, key, _)| code == crossterm::event::KeyCode::Char(*key))
+        .map(|(feature, ..)| *feature)
+}
+
+/// Score (in pawns) at which the gauge is about 73% filled, the sigmoid below keeps large scores (or richer
+/// evaluations with a wider range) from pegging the bar.
+const GAUGE_SCALE: f64 = 2.0;
+
+/// Map a score to the filling ratio of the gauge, 0.5 for an equal position and tending to 0 (resp. 1) as
+/// black (resp. white) gets a decisive advantage.
+fn gauge_ratio(score: f32) -> f64 {
+    1.0 / (1.0 + (-score as f64 / GAUGE_SCALE).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stalemate_ends_game() {
+        let mut state = AppState::default();
+        state.load_fen("7k/8/6Q1/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(state.game_over, None);
+
+        let mv = state
+            .current_moves
+            .iter()
+            .find(|mv| mv.uci().to_string() == "g6f7")
+            .copied()
+            .unwrap();
+        state.play_move(mv);
+        assert_eq!(
+            state.game_over,
+            Some(GameOutcome::Draw(
+                chessoteric_core::outcome::DrawReason::Stalemate
+            ))
+        );
+        assert_eq!(state.game_over.unwrap().to_string(), "Draw by stalemate");
+    }
+
+    #[test]
+    fn test_move_cursor_follows_orientation() {
+        let mut state = AppState::default();
+        state.move_cursor(-1, 1);
+        assert_eq!(state.cursor_position, 9); // a1 -> b2
+
+        state.flipped = true;
+        state.move_cursor(-1, 1);
+        assert_eq!(state.cursor_position, 0); // b2 -> a1 when seen from black's side
+        state.move_cursor(0, 1);
+        assert_eq!(state.cursor_position, 0); // Stays on the board
+    }
+
+    #[test]
+    fn test_load_fen_resets_game() {
+        let mut state = AppState::default();
+        state.regenerate_moves();
+        state.moves = vec!["e2e4".to_string(), "e7e5".to_string()];
+        state.selected_position = Some(12);
+        state.current_score = 1.0;
+
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        state.load_fen(fen).unwrap();
+        assert_eq!(
+            state.board,
+            chessoteric_core::board::SquareCentricBoard::parse_fen(fen).unwrap()
+        );
+        assert!(state.moves.is_empty());
+        assert_eq!(state.selected_position, None);
+        assert_eq!(state.current_score, 0.0);
+        assert_eq!(state.current_moves.len(), 15); // 5 king moves, 9 rook moves and castling
+
+        // An invalid FEN leaves the state untouched
+        assert!(state.load_fen("not a fen").is_err());
+        assert_eq!(state.current_moves.len(), 15);
+    }
+
+    #[test]
+    fn test_toggles_follow_capabilities() {
+        // Only the features supported by the AI can be turned on
+        let mut state = AppState::default();
+        state.toggle_feature(Capabilities::PONDER);
+        assert!(state.features.is_empty());
+
+        let ai = chessoteric_core::ai::get_ai("simple").unwrap();
+        state.supported = ai.capabilities();
+        let ponder = toggled_feature(crossterm::event::KeyCode::Char('p')).unwrap();
+        state.toggle_feature(ponder);
+        assert_eq!(state.features, Capabilities::PONDER);
+        state.toggle_feature(ponder);
+        assert!(state.features.is_empty());
+
+        assert_eq!(toggled_feature(crossterm::event::KeyCode::Char('x')), None);
+    }
+
+    #[test]
+    fn test_gauge_ratio_sigmoid() {
+        assert_eq!(gauge_ratio(0.0), 0.5);
+        assert!(gauge_ratio(1.0) > 0.5 && gauge_ratio(-1.0) < 0.5);
+        assert!((gauge_ratio(1.0) + gauge_ratio(-1.0) - 1.0).abs() < 1e-9);
+
+        assert!(gauge_ratio(20.0) > 0.99);
+        assert!(gauge_ratio(-20.0) < 0.01);
+        assert_eq!(gauge_ratio(f32::INFINITY), 1.0);
+        assert_eq!(gauge_ratio(f32::NEG_INFINITY), 0.0);
+    }
+}
@@ -9,6 +9,27 @@ pub struct BoardWidget<'a> {
     pub highlighted: Bitboard,
     pub selected: Option<u8>,
     pub screen: Rect,
+    pub flipped: bool,
+}
+
+/// Convert a position on the rendered grid (row 0 is the top of the screen, column 0 the left) into
+/// a square index (0 = a1, 63 = h8). When `flipped` is set the board is seen from black's side.
+pub fn screen_to_square(row: u8, col: u8, flipped: bool) -> u8 {
+    if flipped {
+        row * 8 + (7 - col)
+    } else {
+        (7 - row) * 8 + col
+    }
+}
+
+/// Inverse of [`screen_to_square`], returns the `(row, col)` at which the square is rendered.
+pub fn square_to_screen(square: u8, flipped: bool) -> (u8, u8) {
+    let (rank, file) = (square / 8, square % 8);
+    if flipped {
+        (rank, 7 - file)
+    } else {
+        (7 - rank, file)
+    }
 }
 
 impl<'a> BoardWidget<'a> {
@@ -45,10 +66,10 @@ impl<'a> Widget for BoardWidget<'a> {
         };
 
         // Then render the board squares
-        for rank in 0..8 {
-            for file in 0..8 {
-                let is_light_square = (rank + file) % 2 == 0;
-                let piece_index = ((7 - rank) * 8 + file) as usize;
+        for row in 0..8 {
+            for col in 0..8 {
+                let is_light_square = (row + col) % 2 == 0;
+                let piece_index = screen_to_square(row as u8, col as u8, self.flipped) as usize;
 
                 let mut square_color = if is_light_square {
                     Color::Rgb(122, 133, 147)
@@ -76,8 +97,8 @@ impl<'a> Widget for BoardWidget<'a> {
                 };
 
                 let square_rect = Rect {
-                    x: board_area.x + file * (board_area.width / 8),
-                    y: board_area.y + rank * (board_area.height / 8),
+                    x: board_area.x + col * (board_area.width / 8),
+                    y: board_area.y + row * (board_area.height / 8),
                     width: board_area.width / 8,
                     height: board_area.height / 8,
                 };
@@ -112,3 +133,37 @@ impl<'a> Widget for BoardWidget<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_to_square_corners() {
+        // (row, col, flipped) -> square, a1 = 0, h1 = 7, a8 = 56, h8 = 63
+        let cases = [
+            (7, 0, false, 0),
+            (7, 7, false, 7),
+            (0, 0, false, 56),
+            (0, 7, false, 63),
+            (0, 7, true, 0),
+            (0, 0, true, 7),
+            (7, 7, true, 56),
+            (7, 0, true, 63),
+        ];
+        for (row, col, flipped, square) in cases {
+            assert_eq!(screen_to_square(row, col, flipped), square);
+            assert_eq!(square_to_screen(square, flipped), (row, col));
+        }
+    }
+
+    #[test]
+    fn test_screen_square_round_trip() {
+        for flipped in [false, true] {
+            for square in 0..64 {
+                let (row, col) = square_to_screen(square, flipped);
+                assert_eq!(screen_to_square(row, col, flipped), square);
+            }
+        }
+    }
+}
@@ -1,6 +1,5 @@
 use std::io::{self, Write};
 
-use chessoteric_core::bitboard::Bitboard;
 use clap::Parser;
 pub mod state;
 
@@ -22,15 +21,39 @@ pub struct StermArgs {
     /// Load a specific ai at startup
     #[clap(long)]
     pub ai: Option<String>,
+
+    /// Guarantee that only UCI protocol lines are written to stdout, everything else (board display,
+    /// listings, human messages, prompt) is sent to stderr. Independent of `--human`.
+    #[clap(long)]
+    pub silent: bool,
+
+    /// Print the FEN of the board to stderr after every `move` and `position` command, even outside of
+    /// `--human` mode, to follow automated play without disturbing the protocol on stdout
+    #[clap(long)]
+    pub echo_board: bool,
+
+    /// Reject the FENs (of `--fen` and `position fen`) that are not complete six-field FENs consistent with
+    /// the position, instead of accepting whatever can be made sense of
+    #[clap(long)]
+    pub fen_strict: bool,
+
+    /// Enable the UCI Ponder option at startup (it can also be set with `setoption name Ponder value true`)
+    #[clap(long)]
+    pub ponder: bool,
 }
 
-fn _main() {
+fn main() {
     let args = StermArgs::parse();
 
     // Load the chess position from the FEN string
     let mut state = state::AppState {
-        board: chessoteric_core::board::Board::from_fen(&args.fen).expect("Invalid FEN string"),
+        game: chessoteric_core::game::Game::new(
+            state::AppState::parse_fen(&args.fen, args.fen_strict).expect("Invalid FEN string"),
+        ),
         ai: None,
+        ai_name: String::new(),
+        ponder: args.ponder,
+        multipv: 1,
         args,
     };
     let commands = state::all_commands();
@@ -40,27 +63,40 @@ fn _main() {
         match chessoteric_core::ai::get_ai(ai_name) {
             Some(ai) => {
                 state.ai = Some(ai);
+                state.ai_name = ai_name.clone();
             }
             None => {
-                eprintln!("Unknown AI: {}, available AIs are: simple, random", ai_name);
+                eprintln!(
+                    "Unknown AI: {}, available AIs are: simple, random, chessoteric",
+                    ai_name
+                );
                 std::process::exit(1);
             }
         }
     } else {
         state.ai = Some(chessoteric_core::ai::get_ai("simple").expect("Failed to load default AI"));
+        state.ai_name = "simple".to_string();
     }
 
     'mainloop: loop {
         // Read user input for a move
         if state.args.human {
-            print!("[chess] $ ");
-            io::stdout().flush().expect("Failed to flush stdout");
+            if state.args.silent {
+                eprint!("[chess] $ ");
+            } else {
+                print!("[chess] $ ");
+                io::stdout().flush().expect("Failed to flush stdout");
+            }
         }
         let mut input = String::new();
-        if let Err(e) = std::io::stdin().read_line(&mut input) {
-            eprintln!("Error reading input: {}", e);
-            std::process::exit(1);
-        };
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) => break, // End of input
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error reading input: {}", e);
+                std::process::exit(1);
+            }
+        }
 
         // Process the input move
         let input = input.trim();
@@ -87,11 +123,15 @@ fn _main() {
 
         // Help command
         if args[0] == "help" {
-            println!("Available commands:");
+            state.output(format_args!("Available commands:"));
             for command in &commands {
-                println!(" - {}: {}", command.name(), command.description());
+                state.output(format_args!(
+                    " - {}: {}",
+                    command.name(),
+                    command.description()
+                ));
             }
-            println!(" - help: Show this help message");
+            state.output(format_args!(" - help: Show this help message"));
             continue;
         }
 
@@ -102,13 +142,3 @@ fn _main() {
         );
     }
 }
-
-fn main() {
-    let square = 37u8;
-    let occ = Bitboard(0x8004206a04603020);
-    // let occ = Bitboard::empty();
-    let raycast = Bitboard::rook_eff_raycast(square, occ).0;
-    let mask = Bitboard::rook_mask(square).0;
-    println!("Raycast result: {raycast:016x}");
-    println!("Mask result: {mask:016x}");
-}
@@ -1,12 +1,67 @@
-use chessoteric_core::ai::{Ai, AiLimit, get_ai};
+use chessoteric_core::{
+    ai::{Ai, AiLimit, Capabilities, get_ai_with_multipv},
+    game::Game,
+};
 use clap::Parser;
 
 use crate::StermArgs;
 
 pub struct AppState {
     pub args: StermArgs,
-    pub board: chessoteric_core::board::Board,
+    /// The current position and the moves that led to it (since the last `position` command).
+    pub game: Game,
     pub ai: Option<Box<dyn Ai>>,
+    /// Name the AI was loaded with, to load it again when an option of its constructor changes.
+    pub ai_name: String,
+
+    /// Value of the UCI `Ponder` option, `go ponder` is treated as a regular search when disabled.
+    pub ponder: bool,
+
+    /// Value of the UCI `MultiPV` option, the number of lines reported by the AIs that support it.
+    pub multipv: usize,
+}
+
+impl AppState {
+    /// Print a line that is not part of the UCI protocol (board display, listings, human messages...).
+    /// In silent mode it is sent to stderr instead, so that stdout only ever carries protocol lines.
+    pub fn output(&self, args: std::fmt::Arguments) {
+        if self.args.silent {
+            eprintln!("{}", args);
+        } else {
+            println!("{}", args);
+        }
+    }
+
+    /// Parse a FEN with the strict parser when `--fen-strict` is set, the lenient one otherwise.
+    pub fn parse_fen(fen: &str, strict: bool) -> Result<chessoteric_core::board::Board, String> {
+        if strict {
+            chessoteric_core::board::Board::from_fen_strict(fen)
+        } else {
+            chessoteric_core::board::Board::from_fen(fen)
+        }
+    }
+
+    /// Print the FEN of the board to stderr when `--echo-board` is set, called whenever the board changes.
+    pub fn echo_board(&self) {
+        if self.args.echo_board {
+            eprintln!("{}", self.game.board().fen());
+        }
+    }
+
+    /// Give the game to the AI, called whenever the game changes, see [`Ai::set_position`].
+    pub fn set_ai_position(&self) {
+        if let Some(ai) = &self.ai {
+            ai.set_position(&self.game.start(), &self.game.moves());
+        }
+    }
+
+    /// Tell the human player that a draw can be claimed once the position occurred three times, called
+    /// after every move played.
+    pub fn announce_repetition(&self) {
+        if self.args.human && self.game.is_threefold_repetition() {
+            self.output(format_args!("Draw by threefold repetition"));
+        }
+    }
 }
 
 pub trait Command {
@@ -23,14 +78,18 @@ pub fn all_commands() -> Vec<Box<dyn Command>> {
         Box::new(LoadAiCommand),
         Box::new(DisplayBoardCommand),
         Box::new(MoveCommand),
+        Box::new(UndoCommand),
         Box::new(EvalCommand),
         Box::new(UciCommand),
         Box::new(ListMovesCommand),
+        Box::new(PerftCommand),
         Box::new(GoCommand),
         Box::new(StopCommand),
         Box::new(ColorCommand),
         Box::new(UciNewGameCommand),
         Box::new(IsReadyCommand),
+        Box::new(SetOptionCommand),
+        Box::new(PonderHitCommand),
     ]
 }
 
@@ -53,6 +112,7 @@ impl Command for PositionCommand {
 
         // We will parse the arguments in two steps
         let mut board = None;
+        let mut game = None;
 
         {
             let mut index = 1;
@@ -68,13 +128,13 @@ impl Command for PositionCommand {
                         }
 
                         let fen = fen_parts.join(" ");
-                        match chessoteric_core::board::Board::from_fen(&fen) {
+                        match AppState::parse_fen(&fen, state.args.fen_strict) {
                             Ok(parsed_board) => {
                                 assert!(board.is_none(), "Multiple position specifications found");
                                 board = Some(parsed_board);
                             }
-                            Err(_) => {
-                                eprintln!("Invalid FEN string");
+                            Err(e) => {
+                                eprintln!("Invalid FEN string: {}", e);
                                 return;
                             }
                         }
@@ -103,14 +163,12 @@ impl Command for PositionCommand {
                     eprintln!("Position must be specified before moves");
                     return;
                 }
-                let board = board.as_mut().unwrap();
-                for move_str in &args[index..] {
-                    match chessoteric_core::moves::Move::from_uci(move_str.as_str(), board) {
-                        Some(mv) => mv.apply(board),
-                        None => {
-                            eprintln!("Invalid move: {}", move_str);
-                            return;
-                        }
+                let mut played = Game::new(board.unwrap());
+                match apply_moves(&mut played, &args[index..]) {
+                    Ok(()) => game = Some(played),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
                     }
                 }
             } else if board.is_none() {
@@ -128,12 +186,40 @@ impl Command for PositionCommand {
             return;
         }
 
-        state.board = board.unwrap();
+        state.game = game.unwrap_or_else(|| Game::new(board.unwrap()));
+        state.echo_board();
+        state.set_ai_position();
+        state.announce_repetition();
         if state.args.human {
-            println!("Board reset to:\n{}", state.board);
+            state.output(format_args!("Board reset to:\n{}", state.game.board()));
+        }
+    }
+}
+/// Play a sequence of UCI moves in the game, stopping at the first illegal one. The error gives the ply
+/// (counted from 1 within the sequence), the side to move and the FEN of the position it was played in.
+fn apply_moves(game: &mut Game, moves: &[String]) -> Result<(), String> {
+    for (ply, move_str) in (1..).zip(moves) {
+        let board = game.board();
+        match chessoteric_core::moves::Move::from_uci(move_str.as_str(), &board) {
+            Some(mv) => game.push(mv),
+            None => {
+                let side = match board.next_to_move() {
+                    chessoteric_core::board::Color::White => "white",
+                    chessoteric_core::board::Color::Black => "black",
+                };
+                return Err(format!(
+                    "Invalid move: {} (ply {}, {} to move) in position {}",
+                    move_str,
+                    ply,
+                    side,
+                    board.fen()
+                ));
+            }
         }
     }
+    Ok(())
 }
+
 pub struct QuitCommand;
 impl Command for QuitCommand {
     fn name(&self) -> &str {
@@ -165,11 +251,13 @@ impl Command for LoadAiCommand {
             return;
         }
         let ai_name = &args[1];
-        match get_ai(ai_name) {
+        match get_ai_with_multipv(ai_name, state.multipv) {
             Some(ai) => {
                 state.ai = Some(ai);
+                state.ai_name = ai_name.clone();
+                state.set_ai_position();
                 if state.args.human {
-                    println!("Loaded AI: {}", ai_name);
+                    state.output(format_args!("Loaded AI: {}", ai_name));
                 }
             }
             None => eprintln!("Unknown AI name: {}", ai_name),
@@ -203,9 +291,9 @@ impl Command for DisplayBoardCommand {
         };
 
         if args.fen {
-            println!("{}", state.board.fen());
+            state.output(format_args!("{}", state.game.board().fen()));
         } else {
-            println!("{}", state.board);
+            state.output(format_args!("{}", state.game.board()));
         }
     }
 }
@@ -217,20 +305,74 @@ impl Command for MoveCommand {
     }
 
     fn description(&self) -> &str {
-        "Make a move in UCI format (e.g. 'e2e4')"
+        "Make a move in UCI (e.g. 'g1f3') or SAN (e.g. 'Nf3') format"
     }
 
     fn execute(&self, state: &mut AppState, args: &[String]) {
         if args.len() != 2 {
-            eprintln!("Usage: move <uci_move>");
+            eprintln!("Usage: move <uci_or_san_move>");
             return;
         }
-        let uci_move = &args[1];
-        match chessoteric_core::moves::Move::from_uci(uci_move.as_str(), &state.board) {
+
+        // UCI first, then SAN. Parsing UCI only looks at the squares, hence the legality check
+        let input = args[1].as_str();
+        let board = state.game.board();
+        let legal_moves = state.game.legal_moves();
+        let mv = chessoteric_core::moves::Move::from_uci(input, &board)
+            .filter(|mv| legal_moves.contains(mv))
+            .or_else(|| chessoteric_core::moves::Move::from_san(input, &board));
+        match mv {
             Some(mv) => {
-                mv.apply(&mut state.board);
+                state.game.push(mv);
+                state.echo_board();
+                state.set_ai_position();
+                state.announce_repetition();
+            }
+            None => {
+                let mut sans: Vec<String> = legal_moves
+                    .iter()
+                    .map(|mv| mv.algebraic_notation(&board, &legal_moves).to_string())
+                    .collect();
+                sans.sort();
+                eprintln!(
+                    "Invalid, illegal or ambiguous move: {}. Legal moves are: {}",
+                    input,
+                    sans.join(" ")
+                );
             }
-            None => eprintln!("Invalid UCI move format"),
+        }
+    }
+}
+
+pub struct UndoCommand;
+impl Command for UndoCommand {
+    fn name(&self) -> &str {
+        "undo"
+    }
+
+    fn description(&self) -> &str {
+        "Take back the last move played (with 'move' or in the last 'position' command)"
+    }
+
+    fn execute(&self, state: &mut AppState, args: &[String]) {
+        if args.len() != 1 {
+            eprintln!("Usage: undo");
+            return;
+        }
+
+        match state.game.pop() {
+            Some(mv) => {
+                state.echo_board();
+                state.set_ai_position();
+                if state.args.human {
+                    state.output(format_args!(
+                        "Took back {}, board is now:\n{}",
+                        mv.uci(),
+                        state.game.board()
+                    ));
+                }
+            }
+            None => eprintln!("No move to take back"),
         }
     }
 }
@@ -242,16 +384,95 @@ impl Command for ListMovesCommand {
     }
 
     fn description(&self) -> &str {
-        "List all legal moves in the current position"
+        "List all legal moves in the current position, sorted, in UCI or with --san in SAN"
     }
 
-    fn execute(&self, state: &mut AppState, _args: &[String]) {
-        let mut moves = Vec::new();
-        let mut currently_in_check = false;
-        chessoteric_core::moves::generate_moves(&state.board, &mut moves, &mut currently_in_check);
-        for mv in &moves {
-            // println!("{}", mv.algebraic_notation(&state.board, &moves));
-            println!("{}", mv.uci());
+    fn execute(&self, state: &mut AppState, args: &[String]) {
+        #[derive(Parser)]
+        struct Args {
+            /// Print the moves in standard algebraic notation instead of UCI
+            #[clap(short, long)]
+            san: bool,
+        }
+
+        let args = match Args::try_parse_from(args) {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("Error parsing arguments: {}", e);
+                return;
+            }
+        };
+
+        let board = state.game.board();
+        let moves = state.game.legal_moves();
+        let mut listed: Vec<String> = moves
+            .iter()
+            .map(|mv| match args.san {
+                true => mv.algebraic_notation(&board, &moves).to_string(),
+                false => mv.to_uci(&board),
+            })
+            .collect();
+        listed.sort();
+        for mv in &listed {
+            state.output(format_args!("{}", mv));
+        }
+    }
+}
+
+pub struct PerftCommand;
+impl Command for PerftCommand {
+    fn name(&self) -> &str {
+        "perft"
+    }
+
+    fn description(&self) -> &str {
+        "Count the leaf nodes of the legal move tree of the given depth. Syntax: perft [divide] <depth>"
+    }
+
+    fn execute(&self, state: &mut AppState, args: &[String]) {
+        const USAGE: &str = "Usage: perft [divide] <depth>";
+
+        let (divide, depth) = match args {
+            [_, depth] => (false, depth),
+            [_, divide, depth] if divide == "divide" => (true, depth),
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        };
+        let Ok(depth) = depth.parse::<u32>() else {
+            eprintln!("Invalid depth: {}. {}", depth, USAGE);
+            return;
+        };
+
+        let board = state.game.board();
+        let start = std::time::Instant::now();
+        let nodes = if divide {
+            // Sorted by UCI move, as other engines do, to be diffed against them
+            let mut counts: Vec<(String, u64)> =
+                chessoteric_core::moves::perft_divide(&board, depth)
+                    .into_iter()
+                    .map(|(mv, count)| (mv.to_uci(&board), count))
+                    .collect();
+            counts.sort();
+            for (mv, count) in &counts {
+                state.output(format_args!("{}: {}", mv, count));
+            }
+            counts.iter().map(|(_, count)| count).sum()
+        } else {
+            chessoteric_core::moves::perft(&board, depth)
+        };
+        let elapsed = start.elapsed();
+
+        if state.args.human {
+            state.output(format_args!(
+                "Nodes searched: {}\nTime: {} ms ({} nodes/s)",
+                nodes,
+                elapsed.as_millis(),
+                chessoteric_core::ai::AiResult::nodes_per_second(nodes as usize, elapsed)
+            ));
+        } else {
+            state.output(format_args!("nodes {} time {}", nodes, elapsed.as_millis()));
         }
     }
 }
@@ -267,23 +488,34 @@ impl Command for GoCommand {
     }
 
     fn execute(&self, state: &mut AppState, args: &[String]) {
-        const USAGE: &str = "Usage: go [movetime <milliseconds>] [depth <ply>] [wtime <milliseconds>] [btime <milliseconds>] [winc <milliseconds>] [binc <milliseconds>]";
+        const USAGE: &str = "Usage: go [ponder] [movetime <milliseconds>] [depth <ply>] [nodes <count>] [mate <moves>] [wtime <milliseconds>] [btime <milliseconds>] [winc <milliseconds>] [binc <milliseconds>]";
 
         // let mut search_time = state.time_per_move;
         let mut movetime = None;
         let mut depth = None;
+        let mut nodes = None;
+        let mut mate = None;
         let mut wtime = None;
         let mut btime = None;
         let mut winc = None;
         let mut binc = None;
+        let mut ponder = false;
 
         {
             let mut i = 1;
             while i < args.len() {
                 match args[i].as_str() {
+                    "ponder" => {
+                        // Only honored when the Ponder option is enabled
+                        ponder = true;
+                        i += 1;
+                        continue;
+                    }
                     "infinite" => {
                         movetime.take();
                         depth.take();
+                        nodes.take();
+                        mate.take();
                         wtime.take();
                         btime.take();
                         winc.take();
@@ -317,6 +549,35 @@ impl Command for GoCommand {
                         };
                         depth.replace(depth_value);
                     }
+                    "nodes" => {
+                        if i + 1 >= args.len() {
+                            eprintln!("{}", USAGE);
+                            return;
+                        }
+                        let nodes_value = match args[i + 1].parse::<u64>() {
+                            Ok(nodes) => nodes,
+                            Err(_) => {
+                                eprintln!("Invalid nodes value: {}", args[i + 1]);
+                                return;
+                            }
+                        };
+                        nodes.replace(nodes_value);
+                    }
+                    "mate" => {
+                        if i + 1 >= args.len() {
+                            eprintln!("{}", USAGE);
+                            return;
+                        }
+                        let moves = match args[i + 1].parse::<u16>() {
+                            Ok(moves) if moves > 0 => moves,
+                            _ => {
+                                eprintln!("Invalid mate value: {}", args[i + 1]);
+                                return;
+                            }
+                        };
+                        // A mate in n moves is found 2n - 1 plies away
+                        mate.replace(moves.saturating_mul(2) - 1);
+                    }
                     "wtime" | "btime" | "winc" | "binc" => {
                         if i + 1 >= args.len() {
                             eprintln!("{}", USAGE);
@@ -358,7 +619,7 @@ impl Command for GoCommand {
             let wtime = wtime.unwrap_or_else(|| std::time::Duration::from_millis(0));
             let btime = btime.unwrap_or_else(|| std::time::Duration::from_millis(0));
 
-            let next_to_move = state.board.next_to_move();
+            let next_to_move = state.game.board().next_to_move();
             let time_for_move = match next_to_move {
                 chessoteric_core::board::Color::White => {
                     wtime.checked_div(30).unwrap_or_default() + winc
@@ -372,9 +633,26 @@ impl Command for GoCommand {
             movetime.replace(time_for_move);
         }
 
-        let limit = AiLimit { movetime, depth };
+        let limit = AiLimit {
+            movetime,
+            depth,
+            nodes,
+            mate,
+            ponder: ponder
+                && state.ponder
+                && state
+                    .ai
+                    .as_ref()
+                    .is_some_and(|ai| ai.capabilities().contains(Capabilities::PONDER)),
+        };
         if let Some(ai) = &mut state.ai {
-            ai.start(&state.board, limit, true);
+            // The last move of the position is the one expected from the opponent, which lets the AI reuse
+            // what it searched below it
+            let mut before = state.game.clone();
+            match before.pop().filter(|_| limit.ponder) {
+                Some(expected_move) => ai.ponder(&before.board(), expected_move, limit, true),
+                None => ai.start(&state.game.board(), limit, true),
+            };
         } else {
             if !state.args.human {
                 std::process::exit(1);
@@ -392,12 +670,69 @@ impl Command for EvalCommand {
     }
 
     fn description(&self) -> &str {
-        "Perform static evaluation of the current position using a simple material count"
+        "Print the static evaluation of the current position, in centipawns from white's point of view. Syntax: eval [--components]"
     }
 
-    fn execute(&self, state: &mut AppState, _args: &[String]) {
-        let score = chessoteric_core::eval::evaluate(&state.board);
-        println!("Evaluation score: {}", score);
+    fn execute(&self, state: &mut AppState, args: &[String]) {
+        #[derive(Parser)]
+        struct Args {
+            /// Also print the terms the evaluation is made of
+            #[clap(short, long)]
+            components: bool,
+        }
+
+        let args = match Args::try_parse_from(args) {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("Error parsing arguments: {}", e);
+                return;
+            }
+        };
+
+        let centipawns = |score: f32| (score * 100.0).round() as i32;
+        let board = state.game.board();
+        if args.components {
+            use chessoteric_core::eval;
+
+            #[cfg(feature = "eval_larry_kaufman")]
+            {
+                let kaufman = eval::larry_kaufman_components(&board);
+                state.output(format_args!(
+                    "Material: {} cp",
+                    centipawns(kaufman.material)
+                ));
+                state.output(format_args!("Pawns: {} cp", centipawns(kaufman.pawns)));
+                state.output(format_args!(
+                    "Mobility: {} cp",
+                    centipawns(kaufman.mobility)
+                ));
+                state.output(format_args!(
+                    "King shelter: {} cp",
+                    centipawns(kaufman.king_shelter)
+                ));
+                state.output(format_args!(
+                    "Rook files: {} cp",
+                    centipawns(kaufman.rook_files)
+                ));
+                state.output(format_args!(
+                    "King safety: {} cp",
+                    centipawns(kaufman.king_safety)
+                ));
+            }
+            // The simple evaluation is a plain material count, pawns included
+            #[cfg(not(feature = "eval_larry_kaufman"))]
+            state.output(format_args!(
+                "Material: {} cp",
+                centipawns(eval::simple_evaluation(&board))
+            ));
+            state.output(format_args!(
+                "Drawish scale: {}",
+                eval::drawish_scale(&board)
+            ));
+        }
+
+        let score = chessoteric_core::eval::evaluate(&board);
+        state.output(format_args!("Evaluation score: {} cp", centipawns(score)));
     }
 }
 
@@ -418,25 +753,11 @@ impl Command for StopCommand {
         }
 
         if let Some(ai) = &mut state.ai {
-            ai.stop();
-
-            // match ai.stop() {
-            //     Some(result) => {
-            //         if result.pv.len() > 1 {
-            //             println!(
-            //                 "bestmove {} ponder {}",
-            //                 result.best_move.uci(),
-            //                 result.pv[1].uci()
-            //             );
-            //         } else {
-            //             println!("bestmove {}", result.best_move.uci());
-            //         }
-            //         // println!("Best move: {}, score: {}", result.best_move, result.score);
-            //         // result.pv.iter().for_each(|mv| println!("PV move: {}", mv));
-            //     }
-            //     None => eprintln!("AI was not thinking or failed to return a result"),
-            // }
-            // state.ai_state = AiState::Idle;
+            // A search that ended on its own already reported its best move, stop only returns the result of
+            // an interrupted one
+            if let Some(result) = ai.stop() {
+                println!("{}", result.bestmove_line(&state.game.board()));
+            }
         } else if state.ai.is_none() {
             if !state.args.human {
                 std::process::exit(1);
@@ -464,14 +785,14 @@ impl Command for ColorCommand {
     }
 
     fn execute(&self, state: &mut AppState, _args: &[String]) {
-        let color = match state.board.next_to_move() {
+        let color = match state.game.board().next_to_move() {
             chessoteric_core::board::Color::White => "white",
             chessoteric_core::board::Color::Black => "black",
         };
         if state.args.human {
-            println!("Next player to move: {}", color);
+            state.output(format_args!("Next player to move: {}", color));
         } else {
-            println!("{}", color);
+            state.output(format_args!("{}", color));
         }
     }
 }
@@ -487,7 +808,7 @@ impl Command for UciCommand {
     }
 
     fn execute(&self, state: &mut AppState, _args: &[String]) {
-        let ai = match &mut state.ai {
+        let ai = match &state.ai {
             Some(ai) => ai,
             None => {
                 eprintln!("No AI loaded. Use 'load_ai <ai_name>' to load an AI.");
@@ -496,11 +817,35 @@ impl Command for UciCommand {
         };
         println!("id name {}", ai.name());
         println!("id author {}", ai.authors().join(", "));
-        println!("");
+        for option in uci_options(state, ai.capabilities()) {
+            println!("{}", option);
+        }
+        state.output(format_args!(""));
         println!("uciok");
     }
 }
 
+/// Highest value of the UCI `MultiPV` option.
+const MAX_MULTIPV: usize = 256;
+
+/// The `option` lines advertised in reply to `uci`, one for each feature of the AI that has a UCI option.
+fn uci_options(state: &AppState, capabilities: Capabilities) -> Vec<String> {
+    capabilities
+        .iter()
+        .filter_map(|feature| match feature {
+            Capabilities::PONDER => Some(format!(
+                "option name Ponder type check default {}",
+                state.ponder
+            )),
+            Capabilities::MULTI_PV => Some(format!(
+                "option name MultiPV type spin default {} min 1 max {}",
+                state.multipv, MAX_MULTIPV
+            )),
+            _ => None, // `go infinite` is part of the protocol, not an option
+        })
+        .collect()
+}
+
 pub struct UciNewGameCommand;
 impl Command for UciNewGameCommand {
     fn name(&self) -> &str {
@@ -536,3 +881,67 @@ impl Command for IsReadyCommand {
         }
     }
 }
+
+pub struct SetOptionCommand;
+impl Command for SetOptionCommand {
+    fn name(&self) -> &str {
+        "setoption"
+    }
+
+    fn description(&self) -> &str {
+        "Set a UCI option. Syntax: setoption name <id> [value <x>]"
+    }
+
+    fn execute(&self, state: &mut AppState, args: &[String]) {
+        let value_index = args.iter().position(|arg| arg == "value");
+        if args.len() < 3 || args[1] != "name" || value_index.is_some_and(|index| index < 3) {
+            eprintln!("Usage: setoption name <id> [value <x>]");
+            return;
+        }
+
+        let name = args[2..value_index.unwrap_or(args.len())].join(" ");
+        let value = value_index.map(|index| args[index + 1..].join(" "));
+        match (name.as_str(), value.as_deref()) {
+            ("Ponder", Some("true")) => state.ponder = true,
+            ("Ponder", Some("false")) => state.ponder = false,
+            ("Ponder", _) => eprintln!("Invalid value for option Ponder, expected true or false"),
+            ("MultiPV", value)
+                if state
+                    .ai
+                    .as_ref()
+                    .is_some_and(|ai| ai.capabilities().contains(Capabilities::MULTI_PV)) =>
+            {
+                match value.and_then(|value| value.parse::<usize>().ok()) {
+                    Some(multipv @ 1..=MAX_MULTIPV) => {
+                        // The number of lines is given to the AI when it is built
+                        state.multipv = multipv;
+                        state.ai = get_ai_with_multipv(&state.ai_name, multipv);
+                        state.set_ai_position();
+                    }
+                    _ => eprintln!(
+                        "Invalid value for option MultiPV, expected a number between 1 and {}",
+                        MAX_MULTIPV
+                    ),
+                }
+            }
+            _ => eprintln!("Unknown option: {}", name),
+        }
+    }
+}
+
+pub struct PonderHitCommand;
+impl Command for PonderHitCommand {
+    fn name(&self) -> &str {
+        "ponderhit"
+    }
+
+    fn description(&self) -> &str {
+        "The opponent played the expected move, the ponder search goes on as a regular search"
+    }
+
+    fn execute(&self, state: &mut AppState, _args: &[String]) {
+        if let Some(ai) = &state.ai {
+            ai.ponderhit();
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// Start sterm with the given arguments, its stdin and stdout piped and its stderr discarded.
+pub fn spawn_sterm(args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_sterm"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start sterm")
+}
+
+/// Read the lines of stdout up to and including the first `bestmove` line, or until the end of the output.
+pub fn read_until_bestmove(stdout: &mut impl BufRead) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in stdout.lines() {
+        let line = line.unwrap();
+        let done = line.starts_with("bestmove");
+        lines.push(line);
+        if done {
+            break;
+        }
+    }
+    lines
+}
+
+/// Run sterm with the given arguments and input, and return the lines of stdout up to and including the first
+/// `bestmove` line. sterm is told to quit once the search has reported its move.
+pub fn run_until_bestmove(args: &[&str], input: &str) -> Vec<String> {
+    let mut child = spawn_sterm(args);
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).unwrap();
+    stdin.flush().unwrap();
+
+    let lines = read_until_bestmove(&mut BufReader::new(child.stdout.take().unwrap()));
+    stdin.write_all(b"quit\n").unwrap();
+    child.wait().unwrap();
+    lines
+}
+
+/// Run sterm with the given arguments, feeding the chunks of input one after the other and pausing between them
+/// so that the searches get time to run. Returns the whole stdout and stderr.
+pub fn run_sterm(args: &[&str], chunks: &[&str]) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sterm"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start sterm");
+
+    // Read stderr aside, a chatty sterm could otherwise block on a full pipe while we read stdout
+    let mut stderr_pipe = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || {
+        let mut stderr = String::new();
+        stderr_pipe.read_to_string(&mut stderr).unwrap();
+        stderr
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    for (index, chunk) in chunks.iter().enumerate() {
+        if index > 0 {
+            thread::sleep(Duration::from_millis(300));
+        }
+        stdin.write_all(chunk.as_bytes()).unwrap();
+        stdin.flush().unwrap();
+    }
+    drop(stdin);
+
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    child.wait().unwrap();
+    (stdout, stderr.join().unwrap())
+}
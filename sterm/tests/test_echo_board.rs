@@ -0,0 +1,16 @@
+mod common;
+
+use common::run_sterm;
+
+#[test]
+fn test_echo_board_after_move() {
+    const FEN: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3";
+
+    let (stdout, stderr) = run_sterm(&["--echo-board"], &["move e2e4\nquit\n"]);
+    assert!(stderr.contains(FEN), "Unexpected stderr: {:?}", stderr);
+    assert!(stdout.is_empty(), "Unexpected stdout: {:?}", stdout);
+
+    // Nothing is echoed without the flag
+    let (_, stderr) = run_sterm(&[], &["move e2e4\nquit\n"]);
+    assert!(!stderr.contains(FEN));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::run_sterm;
+
+/// The centipawns of the line starting with the given label.
+fn centipawns(stdout: &str, label: &str) -> i32 {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix(label))
+        .and_then(|value| value.strip_suffix(" cp"))
+        .unwrap_or_else(|| panic!("No {:?} line in {:?}", label, stdout))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_eval_is_from_white_point_of_view() {
+    // Black is a queen up, whoever is to move
+    for side in ["w", "b"] {
+        let (stdout, _) = run_sterm(
+            &[],
+            &[&format!(
+                "position fen 4k3/8/8/8/8/8/q7/4K3 {} - - 0 1\neval\nquit\n",
+                side
+            )],
+        );
+        let score = centipawns(&stdout, "Evaluation score: ");
+        assert!(
+            (-1000..=-800).contains(&score),
+            "{} to move: {}",
+            side,
+            score
+        );
+    }
+}
+
+#[test]
+fn test_eval_components() {
+    // White is a knight up
+    let (stdout, _) = run_sterm(
+        &[],
+        &["position fen 4k3/pppppppp/8/8/8/8/PPPPPPPP/1N2K3 w - - 0 1\neval --components\nquit\n"],
+    );
+    assert!(centipawns(&stdout, "Material: ") >= 300);
+    assert_eq!(centipawns(&stdout, "King safety: "), 0);
+    assert!(
+        stdout.contains("Drawish scale: 1\n"),
+        "Unexpected stdout: {:?}",
+        stdout
+    );
+    assert!(centipawns(&stdout, "Evaluation score: ") >= 300);
+}
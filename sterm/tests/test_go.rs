@@ -0,0 +1,138 @@
+mod common;
+
+use common::{run_sterm, run_until_bestmove};
+
+/// Run a search with the given `go` arguments and return the `info` lines followed by the `bestmove` line.
+fn run_go(fen: &str, go: &str) -> Vec<String> {
+    run_until_bestmove(&[], &format!("position fen {}\ngo {}\n", fen, go))
+}
+
+#[test]
+fn test_go_depth() {
+    // One info line per completed depth, the last one for the requested depth
+    for depth in 1..=3 {
+        let lines = run_go(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &format!("depth {}", depth),
+        );
+        assert_ne!(lines.last().unwrap(), "bestmove (none)", "{:?}", lines);
+
+        let infos: Vec<&String> = lines
+            .iter()
+            .filter(|line| line.starts_with("info depth"))
+            .collect();
+        assert_eq!(infos.len(), depth, "{:?}", lines);
+        for (index, info) in infos.iter().enumerate() {
+            assert!(
+                info.starts_with(&format!("info depth {} ", index + 1)),
+                "{:?}",
+                lines
+            );
+            let pv = info.split(" pv ").nth(1).unwrap();
+            assert_eq!(pv.split_whitespace().count(), index + 1, "{:?}", lines);
+        }
+    }
+}
+
+#[test]
+fn test_go_nodes() {
+    let lines = run_go(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "nodes 5000",
+    );
+    assert!(lines.last().unwrap().starts_with("bestmove"), "{:?}", lines);
+
+    // The info lines come at the end of each depth, all of them but the last one are below the limit
+    let nodes: Vec<u64> = lines
+        .iter()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let index = tokens.iter().position(|token| *token == "nodes")?;
+            tokens[index + 1].parse().ok()
+        })
+        .collect();
+    assert!(!nodes.is_empty(), "{:?}", lines);
+    assert!(nodes.iter().all(|nodes| *nodes < 5000), "{:?}", lines);
+}
+
+#[test]
+fn test_go_nodes_chessoteric() {
+    // The search ends by itself, long before the quit
+    let (stdout, _) = run_sterm(
+        &["--ai", "chessoteric"],
+        &["position startpos\ngo nodes 1000\n", "quit\n"],
+    );
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(
+        lines.iter().any(|line| line.starts_with("bestmove")),
+        "{:?}",
+        lines
+    );
+    for line in lines.iter().filter(|line| line.starts_with("info depth")) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let index = tokens.iter().position(|token| *token == "nodes").unwrap();
+        let nodes: u64 = tokens[index + 1].parse().unwrap();
+        assert!(nodes < 1000, "{:?}", lines);
+    }
+}
+
+#[test]
+fn test_go_nodes_below_the_root_moves() {
+    // The root moves are always scored, whatever the limit
+    for limit in ["nodes 1", "nodes 10"] {
+        let lines = run_go(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            limit,
+        );
+        let bestmove = lines.last().unwrap();
+        assert!(bestmove.starts_with("bestmove"), "{:?}", lines);
+        assert_ne!(bestmove, "bestmove (none)", "{:?}", lines);
+    }
+}
+
+#[test]
+fn test_go_mate() {
+    // Rd8+ Rxd8 Rxd8#, the search stops by itself once the mate is found
+    let lines = run_go("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", "mate 2");
+    assert_eq!(
+        lines.last().unwrap(),
+        "bestmove d2d8 ponder c8d8",
+        "{:?}",
+        lines
+    );
+}
+
+#[test]
+fn test_go_multipv() {
+    // The option is only offered by the AIs that report several lines
+    let options = |ai: &str| run_sterm(&["--ai", ai], &["uci\n"]).0;
+    assert!(options("simple").contains("option name MultiPV type spin default 1"));
+    assert!(!options("random").contains("MultiPV"));
+
+    // Each completed depth reports the three best lines, ranked and with distinct first moves
+    let (stdout, _) = run_sterm(
+        &[],
+        &[
+            "setoption name MultiPV value 3\nposition startpos\ngo depth 2\n",
+            "quit\n",
+        ],
+    );
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth 2 "))
+        .collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    let mut first_moves = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        assert!(
+            line.contains(&format!(" multipv {} ", index + 1)),
+            "{}",
+            stdout
+        );
+        first_moves.push(line.split(" pv ").nth(1).unwrap().split_whitespace().next());
+    }
+    first_moves.sort();
+    first_moves.dedup();
+    assert_eq!(first_moves.len(), 3, "{}", stdout);
+    assert!(stdout.contains("bestmove"), "{}", stdout);
+}
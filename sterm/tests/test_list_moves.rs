@@ -0,0 +1,25 @@
+mod common;
+
+use common::run_sterm;
+
+fn list_moves(input: &str) -> Vec<String> {
+    let (stdout, _) = run_sterm(&[], &[input]);
+    stdout.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn test_list_moves_notations() {
+    // Both rooks reach d1, the a1 one may also take the queen
+    const POSITION: &str = "position fen 4k3/8/8/q7/8/8/8/R4RK1 w - - 0 1\n";
+
+    let uci = list_moves(&format!("{}list_moves\nquit\n", POSITION));
+    assert!(uci.is_sorted());
+    assert!(uci.contains(&"a1a5".to_string()));
+
+    let san = list_moves(&format!("{}list_moves --san\nquit\n", POSITION));
+    assert!(san.is_sorted());
+    assert_eq!(san.len(), uci.len());
+    assert!(san.contains(&"Rxa5".to_string()), "{:?}", san);
+    assert!(san.contains(&"Rad1".to_string()), "{:?}", san);
+    assert!(san.contains(&"Rfd1".to_string()), "{:?}", san);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::run_sterm;
+
+#[test]
+fn test_move_accepts_uci_and_san() {
+    let (uci, _) = run_sterm(
+        &[],
+        &["move g1f3\nmove d7d5\nmove g2g3\nmove c8g4\nd --fen\nquit\n"],
+    );
+    let (san, stderr) = run_sterm(
+        &[],
+        &["move Nf3\nmove d5\nmove g3\nmove Bg4\nd --fen\nquit\n"],
+    );
+    assert!(stderr.is_empty(), "Unexpected stderr: {:?}", stderr);
+    assert_eq!(san, uci);
+    assert_eq!(
+        san.trim(),
+        "rn1qkbnr/ppp1pppp/8/3p4/6b1/5NP1/PPPPPP1P/RNBQKB1R w KQkq -"
+    );
+}
+
+#[test]
+fn test_move_lists_legal_moves_on_error() {
+    // Illegal in UCI, then ambiguous in SAN (both knights can reach d2)
+    let (stdout, stderr) = run_sterm(
+        &[],
+        &["position fen 4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1\nmove e2e4\nmove Nd2\nd --fen\nquit\n"],
+    );
+    assert_eq!(stdout.trim(), "4k3/8/8/8/8/8/8/1N2KN2 w - -");
+    assert_eq!(
+        stderr.matches("Legal moves are: ").count(),
+        2,
+        "Unexpected stderr: {:?}",
+        stderr
+    );
+    assert!(stderr.contains("Invalid, illegal or ambiguous move: Nd2."));
+    assert!(stderr.contains(" Nbd2 "), "Unexpected stderr: {:?}", stderr);
+}
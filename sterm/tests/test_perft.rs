@@ -0,0 +1,45 @@
+mod common;
+
+use common::run_sterm;
+
+#[test]
+fn test_perft() {
+    let (stdout, _) = run_sterm(&[], &["perft 3\nquit\n"]);
+    assert!(
+        stdout.starts_with("nodes 8902 time "),
+        "Unexpected stdout: {:?}",
+        stdout
+    );
+
+    let (stdout, _) = run_sterm(&["--human"], &["perft 3\nquit\n"]);
+    assert!(
+        stdout.contains("Nodes searched: 8902\n"),
+        "Unexpected stdout: {:?}",
+        stdout
+    );
+}
+
+#[test]
+fn test_perft_divide() {
+    let (stdout, _) = run_sterm(
+        &[],
+        &["position startpos moves e2e4\nperft divide 2\nquit\n"],
+    );
+    let lines: Vec<&str> = stdout.lines().collect();
+    let (total, counts) = lines.split_last().unwrap();
+
+    // Every black reply, in UCI order
+    assert_eq!(counts.len(), 20);
+    assert_eq!(counts[0], "a7a5: 30");
+    let moves: Vec<&str> = counts
+        .iter()
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    assert!(moves.is_sorted());
+    let sum: u64 = counts
+        .iter()
+        .map(|line| line.split(": ").nth(1).unwrap().parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(sum, 600);
+    assert!(total.starts_with("nodes 600 time "));
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::{
+    io::{BufReader, Write},
+    time::Duration,
+};
+
+use common::{read_until_bestmove, run_sterm, spawn_sterm};
+
+#[test]
+fn test_ponder_option_and_ponderhit() {
+    let mut child = spawn_sterm(&[]);
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // While pondering the limits are not applied: the search is still running well after its movetime, the
+    // `readyok` comes before any `bestmove`
+    stdin
+        .write_all(
+            b"uci\nsetoption name Ponder value true\nposition startpos moves e2e4\ngo ponder movetime 100\n",
+        )
+        .unwrap();
+    stdin.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+    stdin.write_all(b"isready\nponderhit\n").unwrap();
+    stdin.flush().unwrap();
+
+    let lines = read_until_bestmove(&mut stdout);
+    stdin.write_all(b"quit\n").unwrap();
+    child.wait().unwrap();
+
+    assert!(lines.contains(&"option name Ponder type check default false".to_string()));
+    assert!(lines.contains(&"readyok".to_string()), "{:?}", lines);
+    assert!(lines.last().unwrap().starts_with("bestmove"), "{:?}", lines);
+
+    // The search went on after the ponderhit instead of starting over
+    let depths: Vec<u32> = lines
+        .iter()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let index = tokens.iter().position(|token| *token == "depth")?;
+            tokens[index + 1].parse().ok()
+        })
+        .collect();
+    assert!(!depths.is_empty());
+    assert!(
+        depths.windows(2).all(|pair| pair[0] < pair[1]),
+        "{:?}",
+        depths
+    );
+}
+
+#[test]
+fn test_ponder_option_follows_capabilities() {
+    let uci_options = |ai: &str| {
+        run_sterm(&["--ai", ai], &["uci\n"])
+            .0
+            .lines()
+            .filter(|line| line.starts_with("option"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    // The random AI answers instantly, it has nothing to ponder
+    assert!(
+        uci_options("simple")
+            .iter()
+            .any(|line| line.contains("Ponder"))
+    );
+    assert!(uci_options("random").is_empty());
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::run_sterm;
+
+#[test]
+fn test_illegal_move_reports_context() {
+    // The 5th move replays e2e4 while the pawn already stands on e4
+    let (_, stderr) = run_sterm(
+        &[],
+        &["position startpos moves e2e4 e7e5 g1f3 b8c6 e2e4 f8c5\nquit\n"],
+    );
+
+    assert!(
+        stderr.contains("Invalid move: e2e4 (ply 5, white to move)"),
+        "Unexpected error output: {:?}",
+        stderr
+    );
+    assert!(stderr.contains("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -"));
+}
+
+#[test]
+fn test_fen_strict() {
+    // Five fields, the fullmove number is missing
+    const POSITION: &str =
+        "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0\nquit\n";
+
+    let run = |args: &[&str]| run_sterm(args, &[POSITION]).1;
+
+    assert!(!run(&[]).contains("Invalid FEN"));
+    let stderr = run(&["--fen-strict"]);
+    assert!(
+        stderr.contains("Invalid FEN string: Invalid FEN: expected 6 fields, got 5"),
+        "Unexpected error output: {:?}",
+        stderr
+    );
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::run_sterm;
+
+/// Run sterm in human mode, returning its stdout.
+fn run_human(input: &str) -> String {
+    run_sterm(&["--human"], &[input]).0
+}
+
+#[test]
+fn test_threefold_repetition_is_announced() {
+    const SHUFFLE: &str = "move g1f3\nmove g8f6\nmove f3g1\nmove f6g8\n";
+
+    // The starting position occurs for the second time only
+    let stdout = run_human(&format!("{}quit\n", SHUFFLE));
+    assert!(!stdout.contains("Draw by threefold repetition"));
+
+    let stdout = run_human(&format!("{}{}quit\n", SHUFFLE, SHUFFLE));
+    assert_eq!(
+        stdout.matches("Draw by threefold repetition").count(),
+        1,
+        "Unexpected stdout: {:?}",
+        stdout
+    );
+
+    // The moves of a position command count as well
+    let stdout =
+        run_human("position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8\nquit\n");
+    assert!(stdout.contains("Draw by threefold repetition"));
+}
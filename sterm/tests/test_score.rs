@@ -0,0 +1,52 @@
+mod common;
+
+use common::run_until_bestmove;
+
+#[test]
+fn test_score_is_from_side_to_move() {
+    // Black is a queen up and to move
+    let lines = run_until_bestmove(
+        &[],
+        "position fen 4k3/8/8/8/8/8/q7/4K3 b - - 0 1\ngo depth 3\n",
+    );
+
+    let mut scores = Vec::new();
+    for line in &lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if let Some(index) = tokens.iter().position(|token| *token == "cp") {
+            scores.push(tokens[index + 1].parse::<i32>().unwrap());
+        }
+    }
+
+    assert!(!scores.is_empty());
+    assert!(
+        scores.iter().all(|score| *score > 0),
+        "Scores should favor black: {:?}",
+        scores
+    );
+}
+
+#[test]
+fn test_pawn_advantage_in_centipawns() {
+    // White is a pawn up, far from promoting
+    let lines = run_until_bestmove(
+        &[],
+        "position fen 4k3/8/8/8/8/8/3P4/4K3 w - - 0 1\ngo depth 2\n",
+    );
+
+    let mut score = None;
+    for line in &lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if let Some(index) = tokens.iter().position(|token| *token == "score") {
+            assert_eq!(tokens[index + 1], "cp", "{}", line);
+            score = Some(tokens[index + 2].parse::<i32>().unwrap());
+        }
+    }
+
+    let score = score.expect("No score reported");
+    assert!(
+        (50..=200).contains(&score),
+        "Expected about cp 100, got cp {}",
+        score
+    );
+}
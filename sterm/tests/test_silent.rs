@@ -0,0 +1,25 @@
+mod common;
+
+use common::run_until_bestmove;
+
+const UCI_TOKENS: [&str; 6] = ["id", "uciok", "readyok", "info", "bestmove", "option"];
+
+#[test]
+fn test_silent_mode_only_emits_uci_lines() {
+    // Mix protocol commands with commands producing human output
+    let lines = run_until_bestmove(
+        &["--silent", "--human"],
+        "help\nuci\nisready\nd\nlist_moves\neval\ncolor\nposition startpos moves e2e4\ngo depth 2\n",
+    );
+
+    assert!(lines.iter().any(|line| line == "uciok"));
+    assert!(lines.iter().any(|line| line == "readyok"));
+    for line in &lines {
+        let token = line.split_whitespace().next().unwrap_or("");
+        assert!(
+            UCI_TOKENS.contains(&token),
+            "Non-protocol line on stdout: {:?}",
+            line
+        );
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::run_sterm;
+
+/// The `bestmove` lines printed before the `readyok` answering the final `isready`.
+fn bestmoves(stdout: &str) -> Vec<&str> {
+    let lines: Vec<&str> = stdout.lines().collect();
+    let ready = lines
+        .iter()
+        .position(|line| *line == "readyok")
+        .unwrap_or_else(|| panic!("No readyok in {:?}", stdout));
+    lines[..ready]
+        .iter()
+        .copied()
+        .filter(|line| line.starts_with("bestmove"))
+        .collect()
+}
+
+#[test]
+fn test_stop_interrupts_search() {
+    for ai in ["simple", "chessoteric", "random"] {
+        let (stdout, _) = run_sterm(
+            &["--ai", ai],
+            &["position startpos\ngo infinite\n", "stop\nisready\nquit\n"],
+        );
+        let bestmoves = bestmoves(&stdout);
+        assert_eq!(bestmoves.len(), 1, "{}: {:?}", ai, stdout);
+        assert_ne!(bestmoves[0], "bestmove (none)", "{}", ai);
+    }
+}
+
+#[test]
+fn test_stop_after_search_ended() {
+    // The search is over long before the stop, which must not report its best move a second time
+    for ai in ["simple", "chessoteric", "random"] {
+        let (stdout, _) = run_sterm(
+            &["--ai", ai],
+            &[
+                "position startpos\ngo depth 1\n",
+                "stop\nstop\nisready\nquit\n",
+            ],
+        );
+        assert_eq!(bestmoves(&stdout).len(), 1, "{}: {:?}", ai, stdout);
+    }
+}
+
+#[test]
+fn test_stop_right_after_go() {
+    // Whether or not the search is over when the stop comes, exactly one best move is reported
+    for ai in ["simple", "chessoteric", "random"] {
+        for limit in ["depth 1", "depth 3", "infinite"] {
+            let input = format!("position startpos\ngo {}\nstop\nisready\nquit\n", limit);
+            let (stdout, _) = run_sterm(&["--ai", ai], &[&input]);
+            assert_eq!(
+                bestmoves(&stdout).len(),
+                1,
+                "{}, go {}: {:?}",
+                ai,
+                limit,
+                stdout
+            );
+        }
+    }
+}
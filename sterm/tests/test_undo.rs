@@ -0,0 +1,36 @@
+mod common;
+
+use common::run_sterm;
+
+#[test]
+fn test_undo_restores_start_position() {
+    let (stdout, stderr) = run_sterm(
+        &[],
+        &["move e2e4\nmove e7e5\nundo\nd --fen\nundo\nd --fen\nquit\n"],
+    );
+    let fens: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        fens,
+        [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+        ],
+        "Unexpected stderr: {:?}",
+        stderr
+    );
+    assert!(stderr.is_empty(), "Unexpected stderr: {:?}", stderr);
+}
+
+#[test]
+fn test_undo_without_moves() {
+    // The moves of the position command can be taken back, but not past its starting position
+    let (stdout, stderr) = run_sterm(
+        &[],
+        &["position startpos moves d2d4\nundo\nundo\nd --fen\nquit\n"],
+    );
+    assert_eq!(
+        stdout.trim(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+    );
+    assert_eq!(stderr.trim(), "No move to take back");
+}